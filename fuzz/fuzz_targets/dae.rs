@@ -0,0 +1,20 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rs_read_trimesh::{Format, Limits, TrimeshLoader};
+
+fuzz_target!(|data: &[u8]| {
+    // The limits keep the fuzzer from spending its time on huge allocations and deep recursion
+    let limits = Limits {
+        max_vertices: Some(1 << 16),
+        max_faces: Some(1 << 16),
+        max_xml_depth: Some(64),
+        max_decompressed_size: Some(1 << 20),
+        ..Limits::default()
+    };
+    let _ = TrimeshLoader::new()
+        .format(Format::Dae)
+        .limits(limits)
+        .welding_tolerance(0.01)
+        .load_mesh_data_reader(data);
+});
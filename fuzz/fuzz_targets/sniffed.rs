@@ -0,0 +1,24 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rs_read_trimesh::{Connectivity, Limits, TrimeshLoader, Validation};
+
+fuzz_target!(|data: &[u8]| {
+    let limits = Limits {
+        max_vertices: Some(1 << 16),
+        max_faces: Some(1 << 16),
+        max_xml_depth: Some(64),
+        max_decompressed_size: Some(1 << 20),
+        ..Limits::default()
+    };
+    // The format is recognized from the content, and the loaded data is processed further
+    if let Ok(mesh) = TrimeshLoader::new()
+        .limits(limits)
+        .validation(Validation::Strict)
+        .load_mesh_data_reader(data)
+    {
+        let _ = mesh.split_by_material();
+        let _ = mesh.split_components(Connectivity::SharedEdge);
+        let _ = mesh.volume();
+    }
+});
@@ -1,33 +1,43 @@
+import os
 import struct
 
+# PLY scalar types and their struct codes
+TYPES = {
+    'char': 'b',
+    'uchar': 'B',
+    'short': 'h',
+    'ushort': 'H',
+    'int': 'i',
+    'uint': 'I',
+    'float': 'f',
+    'double': 'd',
+}
+SIGNED_TYPES = {'char', 'short', 'int', 'float', 'double'}
 
-def write_ply(filename, vertex_format, index_format, vertices, face):
+SAMPLE_FILES = os.path.join(os.path.dirname(os.path.abspath(__file__)), '..', 'tests', 'sample_files')
+
+
+def write_ply(filename, vertex_type, index_type, vertices, face):
     """
-    Writes a PLY file with the given vertex and face formats.
+    Writes a PLY file with the given vertex and face types.
 
     Args:
         filename (str): Name of the output file.
-        vertex_format (str): Format string for vertex coordinates.
-        index_format (str): Format string for face indices.
+        vertex_type (str): PLY type of the vertex coordinates.
+        index_type (str): PLY type of the face indices.
         vertices (list): List of vertices (tuples of coordinates).
         face (list): Face data (count followed by vertex indices).
     """
-    # Map formats to PLY property declarations
-    vertex_properties = {
-        '<fff': 'property float x\nproperty float y\nproperty float z\n',
-        '<ddd': 'property double x\nproperty double y\nproperty double z\n',
-    }
-    index_properties = {
-        '<Biii': 'property list uchar int vertex_indices\n',
-        '<Bhhh': 'property list uchar short vertex_indices\n',
-    }
-
     # Create the PLY header
     header = f"""ply
 format binary_little_endian 1.0
 element vertex {len(vertices)}
-{vertex_properties[vertex_format]}element face 1
-{index_properties[index_format]}end_header
+property {vertex_type} x
+property {vertex_type} y
+property {vertex_type} z
+element face 1
+property list uchar {index_type} vertex_indices
+end_header
 """
 
     with open(filename, 'wb') as file:
@@ -35,11 +45,10 @@ element vertex {len(vertices)}
         file.write(header.encode('ascii'))
         # Write vertex data
         for vertex in vertices:
-            file.write(struct.pack(vertex_format, *vertex))
+            file.write(struct.pack('<' + TYPES[vertex_type] * 3, *vertex))
         # Write face data
         file.write(struct.pack('<B', face[0]))  # Face count as uchar
-        face_indices_format = index_format[2:]  # Extract the index part of the format
-        file.write(struct.pack(face_indices_format, *face[1:]))
+        file.write(struct.pack('<' + TYPES[index_type] * face[0], *face[1:]))
 
 
 # This defines the rotated, right triangle with edges 3:4:5
@@ -51,7 +60,81 @@ vertices = [
 face = [3, 0, 1, 2]  # A single triangular face
 
 # Generate files with various combinations
-write_ply('../example_plys/diverse_field_formats/floats_ints.ply', '<fff', '<Biii', vertices, face)  # Float vertices, Int indices
-write_ply('../example_plys/diverse_field_formats/floats_shorts.ply', '<fff', '<Bhhh', vertices, face)  # Float vertices, Short indices
-write_ply('../example_plys/diverse_field_formats/doubles_ints.ply', '<ddd', '<Biii', vertices, face)  # Double vertices, Int indices
-write_ply('../example_plys/diverse_field_formats/doubles_shorts.ply', '<ddd', '<Bhhh', vertices, face)  # Double vertices, Short indices
+write_ply(os.path.join(SAMPLE_FILES, 'floats_ints.ply'), 'float', 'int', vertices, face)  # Float vertices, Int indices
+write_ply(os.path.join(SAMPLE_FILES, 'floats_shorts.ply'), 'float', 'short', vertices, face)  # Float vertices, Short indices
+write_ply(os.path.join(SAMPLE_FILES, 'doubles_ints.ply'), 'double', 'int', vertices, face)  # Double vertices, Int indices
+write_ply(os.path.join(SAMPLE_FILES, 'doubles_shorts.ply'), 'double', 'short', vertices, face)  # Double vertices, Short indices
+
+# Every combination of coordinate and index types, named like 'short_uchar.ply'. The
+# triangle is scaled by 10 to have integer coordinates, and moved by 35 along x for the
+# unsigned types.
+directory = os.path.join(SAMPLE_FILES, 'field_types')
+os.makedirs(directory, exist_ok=True)
+for vertex_type in TYPES:
+    offset = 0 if vertex_type in SIGNED_TYPES else 35
+    scaled = [(round(x * 10) + offset, round(y * 10), round(z * 10)) for x, y, z in vertices]
+    for index_type in TYPES:
+        filename = os.path.join(directory, f'{vertex_type}_{index_type}.ply')
+        write_ply(filename, vertex_type, index_type, scaled, face)
+
+
+def write_layout_ply(filename, encoding):
+    """
+    Writes a triangle whose header has an element before the vertices, a list property
+    among the vertex coordinates, and both 'x' and 'px' names, of which 'x' is read.
+
+    Args:
+        filename (str): Name of the output file.
+        encoding (str): 'ascii' or 'binary_big_endian'.
+    """
+    header = f"""ply
+format {encoding} 1.0
+comment the x, y and z coordinates are read, px, py and pz are decoys
+element camera 1
+property float view_x
+property list uchar float clip
+element vertex 3
+property float px
+property list uchar int neighbours
+property float x
+property float py
+property float y
+property double z
+property float pz
+element face 1
+property uchar flags
+property list uchar int vertex_indices
+property list uchar float weights
+end_header
+"""
+    # Each record is a list of (struct code, value) or (list code, values)
+    camera = [('f', 1.5), ('Bf', [0.1, 100.0])]
+    vertices = [
+        [('f', 10.0), ('Bi', []), ('f', 0.0), ('f', 20.0), ('f', 0.0), ('d', 0.0), ('f', 30.0)],
+        [('f', 11.0), ('Bi', [0, 2]), ('f', 2.0), ('f', 21.0), ('f', 0.0), ('d', 0.0), ('f', 31.0)],
+        [('f', 12.0), ('Bi', [1]), ('f', 0.0), ('f', 22.0), ('f', 3.0), ('d', 0.0), ('f', 32.0)],
+    ]
+    face = [('B', 7), ('Bi', [0, 1, 2]), ('Bf', [0.5, 0.25, 0.25])]
+
+    with open(filename, 'wb') as file:
+        file.write(header.encode('ascii'))
+        for record in [camera, *vertices, face]:
+            if encoding == 'ascii':
+                words = []
+                for code, value in record:
+                    if len(code) == 2:
+                        words += [str(len(value))] + [str(item) for item in value]
+                    else:
+                        words.append(str(value))
+                file.write((' '.join(words) + '\n').encode('ascii'))
+            else:
+                for code, value in record:
+                    if len(code) == 2:
+                        file.write(struct.pack('>' + code[0], len(value)))
+                        file.write(struct.pack('>' + code[1] * len(value), *value))
+                    else:
+                        file.write(struct.pack('>' + code, value))
+
+
+write_layout_ply(os.path.join(SAMPLE_FILES, 'layout.ply'), 'ascii')
+write_layout_ply(os.path.join(SAMPLE_FILES, 'layout_big_endian.ply'), 'binary_big_endian')
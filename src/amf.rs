@@ -0,0 +1,405 @@
+use crate::error::LoadError;
+use crate::limits::{Limit, Limits};
+use crate::loader::LoadContext;
+use crate::mesh_data::{Material, MeshData, material_id, merge_meshes};
+use crate::recenter::{CoordinateFrame, Recenter};
+use crate::xml::{child_value, children};
+use crate::{Isometry, Real, Vector, na};
+use roxmltree::{Node, ParsingOptions};
+use std::collections::HashSet;
+use std::io::{Cursor, Read};
+
+/// Local file header signature, present at the start of zip-compressed .amf files.
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+
+/// Function to load a TriMesh from an AMF file, plain XML or zip-compressed.
+/// If the file defines constellations, the top-level constellations are instantiated,
+/// otherwise all objects are loaded as they are. Coordinates are converted to millimeters.
+/// Objects that are not selected are left out, also from the constellations.
+pub(crate) fn load_trimesh_from_amf<R: Read>(
+    mut reader: R,
+    amf_file_path: &str,
+    context: &mut LoadContext,
+) -> Result<MeshData, LoadError> {
+    // Read the whole file, as we need to check if it is zipped first
+    let mut bytes = Vec::new();
+    reader
+        .read_to_end(&mut bytes)
+        .map_err(|err| format!("Could not read .amf file '{}': {}", amf_file_path, err))?;
+
+    let xml = if bytes.starts_with(ZIP_MAGIC) {
+        unzip_amf(bytes, &context.options.limits)?
+    } else {
+        String::from_utf8(bytes).map_err(|err| format!("The .amf file is not UTF-8: {}", err))?
+    };
+
+    let document = context
+        .options
+        .limits
+        .parse_xml(&xml, ParsingOptions::default())
+        .map_err(|err| {
+            err.context(format_args!(
+                "Could not parse .amf file '{}'",
+                amf_file_path
+            ))
+        })?;
+    let root = document.root_element();
+    if !root.has_tag_name("amf") {
+        return Err(format!(
+            "Expected <amf> root element, found <{}>",
+            root.tag_name().name()
+        )
+        .into());
+    }
+    let unit_scale = unit_scale(root.attribute("unit").unwrap_or("millimeter"))?;
+
+    // Materials by their id, objects reference them from their volumes
+    let mut materials: Vec<(&str, Material)> = Vec::new();
+    for material in children(root, "material") {
+        let id = material.attribute("id").unwrap_or_default();
+        let name = children(material, "metadata")
+            .find(|metadata| metadata.attribute("type") == Some("name"))
+            .and_then(|metadata| metadata.text())
+            .map_or_else(|| id.to_string(), |name| name.trim().to_string());
+        let diffuse = match children(material, "color").next() {
+            Some(color) => Some(color_value(color)?),
+            None => None,
+        };
+        materials.push((id, Material { name, diffuse }));
+    }
+
+    // Instances are transformed after narrowing, so with constellations the origin
+    // can only be applied to the final points
+    let has_constellations = children(root, "constellation").next().is_some();
+    let mut constellation_frame = CoordinateFrame::new(Recenter::None);
+    context.set_total_elements(children(root, "object").count() as u64);
+
+    // Collect objects and constellations by their id, in document order
+    let mut objects = Vec::new();
+    let mut constellations = Vec::new();
+    for child in root.children().filter(Node::is_element) {
+        match child.tag_name().name() {
+            "object" => {
+                let id = child.attribute("id").unwrap_or_default();
+                context.elements_parsed(1)?;
+                let options = context.options;
+                let mesh = if options.sub_meshes.selects(objects.len(), &[Some(id)])
+                    && options.name_filter.matches(&[Some(id)])
+                {
+                    let object_frame = if has_constellations {
+                        &mut constellation_frame
+                    } else {
+                        &mut context.frame
+                    };
+                    Some(load_object(child, unit_scale, &materials, object_frame)?)
+                } else {
+                    None
+                };
+                objects.push((id, mesh));
+            }
+            "constellation" => {
+                let id = child.attribute("id").unwrap_or_default();
+                constellations.push((id, child));
+            }
+            _ => {}
+        }
+    }
+
+    let mut meshes = Vec::new();
+    if constellations.is_empty() {
+        meshes.extend(objects.into_iter().filter_map(|(_, mesh)| mesh));
+    } else {
+        let mut instantiated = Instantiated::new(&context.options.limits);
+        // Only instantiate constellations that are not part of another constellation
+        let nested: HashSet<&str> = constellations
+            .iter()
+            .flat_map(|(_, constellation)| instances(*constellation))
+            .filter_map(|instance| instance.attribute("objectid"))
+            .collect();
+
+        for (_, constellation) in constellations.iter().filter(|(id, _)| !nested.contains(id)) {
+            let mut visiting = Vec::new();
+            instantiate(
+                *constellation,
+                &Isometry::identity(),
+                unit_scale,
+                &objects,
+                &constellations,
+                &mut visiting,
+                &mut instantiated,
+            )?;
+        }
+        meshes = instantiated.meshes;
+    }
+
+    meshes.retain(|mesh| !mesh.vertices.is_empty());
+    if meshes.is_empty() {
+        return Err("The .amf file contains no mesh".into());
+    }
+
+    let mut mesh = merge_meshes(meshes)?;
+    if has_constellations {
+        context.frame.renarrow(&mut mesh.vertices);
+    }
+    Ok(mesh)
+}
+
+/// Extracts the first .amf entry (or the first file if none has this extension)
+/// from a zip archive.
+/// The size of the entry is limited as it is decompressed, not as declared in the archive.
+fn unzip_amf(bytes: Vec<u8>, limits: &Limits) -> Result<String, LoadError> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes))
+        .map_err(|err| format!("Could not open zip-compressed .amf file: {}", err))?;
+
+    let name = archive
+        .file_names()
+        .find(|name| name.to_lowercase().ends_with(".amf"))
+        .or_else(|| archive.file_names().find(|name| !name.ends_with('/')))
+        .map(str::to_string)
+        .ok_or_else(|| "The zip-compressed .amf file is empty".to_string())?;
+
+    let mut entry = archive.by_name(&name).map_err(|err| {
+        format!(
+            "Could not read '{}' from zip-compressed .amf file: {}",
+            name, err
+        )
+    })?;
+    let mut bytes = Vec::new();
+    limits
+        .bounded(Limit::DecompressedSize, &mut entry)
+        .read_to_end(&mut bytes)
+        .map_err(|err| format!("Could not decompress '{}' from .amf file: {}", name, err))?;
+    limits.check_count(Limit::DecompressedSize, bytes.len())?;
+    Ok(String::from_utf8(bytes).map_err(|err| format!("The .amf file is not UTF-8: {}", err))?)
+}
+
+/// Returns the factor to convert the given AMF unit into millimeters.
+fn unit_scale(unit: &str) -> Result<f64, String> {
+    match unit.to_lowercase().as_str() {
+        "millimeter" => Ok(1.0),
+        "meter" => Ok(1000.0),
+        "micron" | "micrometer" => Ok(0.001),
+        "inch" => Ok(25.4),
+        "feet" | "foot" => Ok(304.8),
+        _ => Err(format!("Unsupported unit '{}' in the .amf file", unit)),
+    }
+}
+
+/// Loads all volumes of the object's mesh as a single mesh, with the vertex
+/// normals and colors if all vertices have them.
+fn load_object(
+    object: Node,
+    unit_scale: f64,
+    materials: &[(&str, Material)],
+    frame: &mut CoordinateFrame,
+) -> Result<MeshData, String> {
+    let object_id = object.attribute("id").unwrap_or_default();
+    let mut data = MeshData::default();
+    let mut normals = Vec::new();
+    let mut colors = Vec::new();
+    let mut face_materials = Vec::new();
+
+    for mesh in children(object, "mesh") {
+        // Volumes of each mesh index into the vertices of this mesh only
+        let vertex_offset = data.vertices.len();
+
+        for vertex_list in children(mesh, "vertices") {
+            for vertex in children(vertex_list, "vertex") {
+                let coordinates = children(vertex, "coordinates").next().ok_or_else(|| {
+                    format!("Missing <coordinates> in vertex of object '{}'", object_id)
+                })?;
+                data.vertices.push(frame.point(
+                    child_value::<f64>(coordinates, "x")? * unit_scale,
+                    child_value::<f64>(coordinates, "y")? * unit_scale,
+                    child_value::<f64>(coordinates, "z")? * unit_scale,
+                ));
+
+                if let Some(normal) = children(vertex, "normal").next() {
+                    normals.push(Vector::new(
+                        child_value::<Real>(normal, "nx")?,
+                        child_value::<Real>(normal, "ny")?,
+                        child_value::<Real>(normal, "nz")?,
+                    ));
+                }
+                if let Some(color) = children(vertex, "color").next() {
+                    colors.push(color_value(color)?);
+                }
+            }
+        }
+
+        let vertex_count = data.vertices.len() - vertex_offset;
+        for volume in children(mesh, "volume") {
+            let material = volume.attribute("materialid").map(|id| {
+                let material = materials
+                    .iter()
+                    .find(|(material, _)| *material == id)
+                    .map_or_else(|| Material::named(id), |(_, material)| material.clone());
+                material_id(&mut data.materials, &material)
+            });
+
+            for triangle in children(volume, "triangle") {
+                let mut face = [0u32; 3];
+                for (i, name) in ["v1", "v2", "v3"].iter().enumerate() {
+                    let index = child_value::<usize>(triangle, name)?;
+                    if index >= vertex_count {
+                        return Err(format!(
+                            "Vertex index {} out of range in object '{}'",
+                            index, object_id
+                        ));
+                    }
+                    face[i] = (vertex_offset + index)
+                        .try_into()
+                        .map_err(|_| format!("Too many vertices in object '{}'", object_id))?;
+                }
+                data.indices.push(face);
+                face_materials.push(material);
+            }
+        }
+    }
+
+    // Attributes are only kept if every vertex has them
+    if !normals.is_empty() && normals.len() == data.vertices.len() {
+        data.normals = Some(normals);
+    }
+    if !colors.is_empty() && colors.len() == data.vertices.len() {
+        data.colors = Some(colors);
+    }
+    if face_materials.iter().any(Option::is_some) {
+        data.face_materials = Some(face_materials);
+    }
+
+    Ok(data)
+}
+
+/// Reads an AMF color with r, g, b and optional a channels.
+fn color_value(color: Node) -> Result<[f32; 4], String> {
+    let alpha = if color.children().any(|child| child.has_tag_name("a")) {
+        child_value::<f32>(color, "a")?
+    } else {
+        1.0
+    };
+    Ok([
+        child_value::<f32>(color, "r")?,
+        child_value::<f32>(color, "g")?,
+        child_value::<f32>(color, "b")?,
+        alpha,
+    ])
+}
+
+/// Recursively instantiates the objects and nested constellations referenced from
+/// the given constellation, appending the transformed meshes.
+fn instantiate<'a>(
+    constellation: Node<'a, '_>,
+    parent: &Isometry<Real>,
+    unit_scale: f64,
+    objects: &[(&str, Option<MeshData>)],
+    constellations: &[(&'a str, Node<'a, '_>)],
+    visiting: &mut Vec<&'a str>,
+    instantiated: &mut Instantiated,
+) -> Result<(), LoadError> {
+    let id = constellation.attribute("id").unwrap_or_default();
+    if visiting.contains(&id) {
+        return Err(format!("Constellation '{}' references itself", id).into());
+    }
+    visiting.push(id);
+    // Nested constellations are followed recursively like nested elements
+    instantiated
+        .limits
+        .check_count(Limit::XmlDepth, visiting.len())?;
+
+    for instance in instances(constellation) {
+        let object_id = instance
+            .attribute("objectid")
+            .ok_or_else(|| format!("Missing 'objectid' in instance of constellation '{}'", id))?;
+        let transform = parent * instance_transform(instance, unit_scale)?;
+
+        if let Some((_, object)) = objects.iter().find(|(id, _)| *id == object_id) {
+            // Objects that are not selected are not loaded
+            if let Some(mesh) = object {
+                instantiated.add(mesh, &transform)?;
+            }
+        } else if let Some((_, nested)) = constellations.iter().find(|(id, _)| *id == object_id) {
+            instantiate(
+                *nested,
+                &transform,
+                unit_scale,
+                objects,
+                constellations,
+                visiting,
+                instantiated,
+            )?;
+        } else {
+            return Err(format!(
+                "Constellation '{}' references unknown object '{}'",
+                id, object_id
+            )
+            .into());
+        }
+    }
+
+    visiting.pop();
+    Ok(())
+}
+
+/// The meshes of the instantiated objects, with their vertices and faces counted as
+/// instancing can multiply them.
+struct Instantiated<'l> {
+    meshes: Vec<MeshData>,
+    vertices: usize,
+    faces: usize,
+    limits: &'l Limits,
+}
+
+impl<'l> Instantiated<'l> {
+    fn new(limits: &'l Limits) -> Self {
+        Instantiated {
+            meshes: Vec::new(),
+            vertices: 0,
+            faces: 0,
+            limits,
+        }
+    }
+
+    /// Adds a transformed copy of the mesh, if the totals stay within the limits.
+    fn add(&mut self, mesh: &MeshData, transform: &Isometry<Real>) -> Result<(), LoadError> {
+        self.vertices += mesh.vertices.len();
+        self.faces += mesh.indices.len();
+        self.limits.check_count(Limit::Vertices, self.vertices)?;
+        self.limits.check_count(Limit::Faces, self.faces)?;
+
+        let mut mesh = mesh.clone();
+        mesh.transform_by(transform);
+        self.meshes.push(mesh);
+        Ok(())
+    }
+}
+
+/// Builds the transform of an instance from its deltas and rotations (in degrees,
+/// applied around x, y and z in this order). Missing components default to zero.
+fn instance_transform(instance: Node, unit_scale: f64) -> Result<Isometry<Real>, String> {
+    let component = |name: &str| -> Result<Real, String> {
+        if instance.children().any(|child| child.has_tag_name(name)) {
+            child_value::<Real>(instance, name)
+        } else {
+            Ok(0.0)
+        }
+    };
+
+    let translation = na::Translation3::new(
+        component("deltax")? * unit_scale as Real,
+        component("deltay")? * unit_scale as Real,
+        component("deltaz")? * unit_scale as Real,
+    );
+    let rotation = na::UnitQuaternion::from_euler_angles(
+        component("rx")?.to_radians(),
+        component("ry")?.to_radians(),
+        component("rz")?.to_radians(),
+    );
+    Ok(Isometry::from_parts(translation, rotation))
+}
+
+fn instances<'a, 'input>(
+    constellation: Node<'a, 'input>,
+) -> impl Iterator<Item = Node<'a, 'input>> {
+    children(constellation, "instance")
+}
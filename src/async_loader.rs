@@ -0,0 +1,163 @@
+use crate::TriMesh;
+use crate::cache::{CacheKey, Lookup};
+use crate::error::LoadError;
+use crate::limits::Limit;
+use crate::loader::TrimeshLoader;
+use crate::mesh_data::MeshData;
+use crate::progress::Tracker;
+use std::rc::Rc;
+use tokio::fs::File;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// Size of the chunks read between two checks of the cancellation token.
+const CHUNK_SIZE: usize = 64 << 10;
+
+/// Async versions of the loading functions, with the `async` feature. The data is read
+/// with async I/O, then parsed by the sync loaders on the blocking thread pool of the
+/// tokio runtime, so the executor threads are not held up by large meshes.
+impl TrimeshLoader {
+    /// Loads the `TriMesh` from the given file, like `load`.
+    pub async fn load_async(&self, file_path: &str) -> Result<TriMesh, LoadError> {
+        let file_path = &*self.resolve(file_path)?;
+        let loader = self.clone().positions_only();
+        loader
+            .load_file_async(file_path, |loader, mesh| {
+                mesh.into_trimesh(loader.options().flags)
+            })
+            .await
+    }
+
+    /// Loads the `TriMesh` from an async reader, like `load_reader`.
+    pub async fn load_reader_async<R: AsyncRead + Unpin>(
+        &self,
+        reader: R,
+    ) -> Result<TriMesh, LoadError> {
+        let loader = self.clone().positions_only();
+        let (bytes, tracker) = loader.read_async(reader, None).await?;
+        loader
+            .parse_async(bytes, None, None, tracker, |loader, mesh| {
+                mesh.into_trimesh(loader.options().flags)
+            })
+            .await
+    }
+
+    /// Loads the mesh with its attributes from the given file, like `load_mesh_data`.
+    pub async fn load_mesh_data_async(&self, file_path: &str) -> Result<MeshData, LoadError> {
+        let file_path = &*self.resolve(file_path)?;
+        self.load_file_async(file_path, |_, mesh| Ok(mesh)).await
+    }
+
+    /// Loads the mesh with its attributes from an async reader, like
+    /// `load_mesh_data_reader`.
+    pub async fn load_mesh_data_reader_async<R: AsyncRead + Unpin>(
+        &self,
+        reader: R,
+    ) -> Result<MeshData, LoadError> {
+        let (bytes, tracker) = self.read_async(reader, None).await?;
+        self.parse_async(bytes, None, None, tracker, |_, mesh| Ok(mesh))
+            .await
+    }
+
+    /// Loads a file, unless the cache has it, which is looked up before the file is read.
+    async fn load_file_async<T: Send + 'static>(
+        &self,
+        file_path: &str,
+        convert: impl FnOnce(&TrimeshLoader, MeshData) -> Result<T, String> + Send + 'static,
+    ) -> Result<T, LoadError> {
+        let path = file_path.to_string();
+        let key = match self
+            .blocking(move |loader| Ok(loader.cache_lookup(&path)))
+            .await?
+        {
+            Lookup::Hit(mesh, _) => {
+                return self
+                    .blocking(move |loader| Ok(convert(loader, mesh)?))
+                    .await;
+            }
+            Lookup::Miss(key) => key,
+        };
+
+        let (bytes, tracker) = self.read_file_async(file_path).await?;
+        self.parse_async(bytes, Some(file_path), key, tracker, convert)
+            .await
+    }
+
+    async fn read_file_async(&self, file_path: &str) -> Result<(Vec<u8>, Tracker), LoadError> {
+        let file = File::open(file_path)
+            .await
+            .map_err(|err| format!("Could not open '{}': {}", file_path, err))?;
+        let size = file
+            .metadata()
+            .await
+            .map_err(|err| format!("Could not open '{}': {}", file_path, err))?
+            .len();
+        self.options().limits.check(Limit::FileSize, size)?;
+        self.read_async(file, Some(size)).await
+    }
+
+    /// Reads everything into memory, checking the size limit and the cancellation
+    /// token as it goes.
+    async fn read_async<R: AsyncRead + Unpin>(
+        &self,
+        reader: R,
+        total_bytes: Option<u64>,
+    ) -> Result<(Vec<u8>, Tracker), LoadError> {
+        let options = self.options();
+        let tracker = Tracker::new(
+            options.progress.clone(),
+            options.cancellation.clone(),
+            total_bytes,
+        );
+        let mut reader = reader.take(options.limits.read_limit(Limit::FileSize));
+        let mut bytes = Vec::new();
+        let mut chunk = vec![0u8; CHUNK_SIZE];
+        loop {
+            tracker.check()?;
+            let read = reader
+                .read(&mut chunk)
+                .await
+                .map_err(|err| format!("Could not read the mesh: {}", err))?;
+            if read == 0 {
+                break;
+            }
+            bytes.extend_from_slice(&chunk[..read]);
+            tracker.update(|progress| progress.bytes_read = bytes.len() as u64);
+        }
+        options.limits.check_count(Limit::FileSize, bytes.len())?;
+        Ok((bytes, tracker))
+    }
+
+    /// Parses the data on the blocking thread pool, then converts the mesh there too.
+    /// The load is stored in the cache if it has a key.
+    async fn parse_async<T: Send + 'static>(
+        &self,
+        bytes: Vec<u8>,
+        file_path: Option<&str>,
+        key: Option<CacheKey>,
+        tracker: Tracker,
+        convert: impl FnOnce(&TrimeshLoader, MeshData) -> Result<T, String> + Send + 'static,
+    ) -> Result<T, LoadError> {
+        let file_path = file_path.map(str::to_string);
+        self.blocking(move |loader| {
+            let loaded = loader.load_mesh_data_bytes_recentered(
+                &bytes,
+                file_path.as_deref(),
+                Rc::new(tracker),
+            )?;
+            let (mesh, _) = loader.cache_store(key, loaded);
+            Ok(convert(loader, mesh)?)
+        })
+        .await
+    }
+
+    /// Runs the function on the blocking thread pool, with a clone of the loader.
+    async fn blocking<T: Send + 'static>(
+        &self,
+        function: impl FnOnce(&TrimeshLoader) -> Result<T, LoadError> + Send + 'static,
+    ) -> Result<T, LoadError> {
+        let loader = self.clone();
+        tokio::task::spawn_blocking(move || function(&loader))
+            .await
+            .map_err(|err| format!("The loading task failed: {}", err))?
+    }
+}
@@ -0,0 +1,373 @@
+use crate::loader::LoadOptions;
+use crate::mesh_data::{Material, MeshData};
+use crate::registry::FormatRegistry;
+use crate::{Point, Real, Vector};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Start of every cache file, with the version of the layout in the last byte.
+const MAGIC: &[u8; 8] = b"RSTRIMC\x02";
+
+/// Written for missing optional values in the cache files.
+const NONE: u32 = u32::MAX;
+
+/// Identifies a cached load: the file with its size and modification time, and
+/// everything that changes the loaded mesh. The other files read by the load are
+/// stored with the mesh, see `write`.
+pub(crate) struct CacheKey {
+    key: String,
+}
+
+impl CacheKey {
+    /// Returns None if the file or its modification time can not be read, or if a
+    /// loader has no cache version, then the load is not cached.
+    pub(crate) fn new(
+        file_path: &str,
+        options: &LoadOptions,
+        registry: &FormatRegistry,
+        positions_only: bool,
+    ) -> Option<CacheKey> {
+        let path = fs::canonicalize(file_path).ok()?;
+        let metadata = fs::metadata(&path).ok()?;
+        let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+
+        // Progress, cancellation and the cache directory do not change the mesh, and the
+        // resolved path is part of the key
+        let key = format!(
+            "{} {} {:?} {} {} {} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {}",
+            env!("CARGO_PKG_VERSION"),
+            size_of::<Real>(),
+            path,
+            metadata.len(),
+            modified.as_secs(),
+            modified.subsec_nanos(),
+            options.scale,
+            options.flags,
+            options.format,
+            options.welding_tolerance,
+            options.transform,
+            options.triangulation,
+            options.validation,
+            (
+                &options.sub_meshes,
+                &options.name_filter,
+                &options.ply_schema
+            ),
+            (options.recenter, options.limits),
+            registry.cache_versions()?,
+            positions_only,
+        );
+        Some(CacheKey { key })
+    }
+
+    /// Path of the cache file, named by a hash of the key. The key itself is stored in
+    /// the file, so a hash collision only costs a cache miss.
+    fn file_path(&self, directory: &Path) -> PathBuf {
+        // FNV-1a, which is stable across Rust versions unlike the std hasher
+        let hash = self
+            .key
+            .bytes()
+            .fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+            });
+        directory.join(format!("{:016x}.mesh", hash))
+    }
+}
+
+/// The result of looking up a load in the cache.
+pub(crate) enum Lookup {
+    /// The cached mesh and offset.
+    Hit(MeshData, [f64; 3]),
+    /// The key to store the load under, None if the load is not cached.
+    Miss(Option<CacheKey>),
+}
+
+/// Reads the cached mesh and offset. Returns None if there is no cache file for the
+/// key, if it is damaged or from another key, or if one of the other files read by
+/// the load changed.
+pub(crate) fn read(directory: &Path, key: &CacheKey) -> Option<(MeshData, [f64; 3])> {
+    let bytes = fs::read(key.file_path(directory)).ok()?;
+    let body = bytes.strip_prefix(MAGIC)?;
+    let (checksum, body) = body.split_first_chunk::<4>()?;
+    if u32::from_le_bytes(*checksum) != crc32fast::hash(body) {
+        return None;
+    }
+
+    let mut decoder = Decoder(body);
+    if decoder.string()? != key.key {
+        return None;
+    }
+    let dependencies = decoder.list(|d| Some((d.string()?, d.string()?)))?;
+    if dependencies
+        .iter()
+        .any(|(path, stamp)| file_stamp(Path::new(path)) != *stamp)
+    {
+        return None;
+    }
+    let offset = [decoder.f64()?, decoder.f64()?, decoder.f64()?];
+    let mesh = MeshData {
+        vertices: decoder.list(|d| Some(Point::new(d.real()?, d.real()?, d.real()?)))?,
+        indices: decoder.list(|d| Some([d.u32()?, d.u32()?, d.u32()?]))?,
+        normals: decoder
+            .optional(|d| d.list(|d| Some(Vector::new(d.real()?, d.real()?, d.real()?))))?,
+        colors: decoder.optional(|d| d.list(|d| Some([d.f32()?, d.f32()?, d.f32()?, d.f32()?])))?,
+        uvs: decoder.optional(|d| d.list(|d| Some([d.f32()?, d.f32()?])))?,
+        face_materials: decoder.optional(|d| {
+            d.list(|d| {
+                let material = d.u32()?;
+                Some((material != NONE).then_some(material))
+            })
+        })?,
+        materials: decoder.list(|d| {
+            Some(Material {
+                name: d.string()?,
+                diffuse: d.optional(|d| Some([d.f32()?, d.f32()?, d.f32()?, d.f32()?]))?,
+            })
+        })?,
+    };
+    decoder.0.is_empty().then_some((mesh, offset))
+}
+
+/// Stores the mesh and offset, with the size and modification time of the other files
+/// the load read, like the .mtl library of an .obj file. The file is written under a
+/// temporary name and then renamed, so concurrent loads never read a partly written file.
+pub(crate) fn write(
+    directory: &Path,
+    key: &CacheKey,
+    mesh: &MeshData,
+    offset: [f64; 3],
+    dependencies: &[PathBuf],
+) -> io::Result<()> {
+    let mut encoder = Encoder(Vec::new());
+    encoder.string(&key.key);
+    encoder.list(dependencies, |e, path| {
+        let path = fs::canonicalize(path).unwrap_or_else(|_| path.clone());
+        e.string(&path.to_string_lossy());
+        e.string(&file_stamp(&path));
+    });
+    offset.iter().for_each(|&value| encoder.f64(value));
+    encoder.list(&mesh.vertices, |e, vertex| {
+        vertex.iter().for_each(|&value| e.real(value))
+    });
+    encoder.list(&mesh.indices, |e, face| {
+        face.iter().for_each(|&index| e.u32(index))
+    });
+    encoder.optional(&mesh.normals, |e, normals| {
+        e.list(normals, |e, normal| {
+            normal.iter().for_each(|&value| e.real(value))
+        })
+    });
+    encoder.optional(&mesh.colors, |e, colors| {
+        e.list(colors, |e, color| {
+            color.iter().for_each(|&value| e.f32(value))
+        })
+    });
+    encoder.optional(&mesh.uvs, |e, uvs| {
+        e.list(uvs, |e, uv| uv.iter().for_each(|&value| e.f32(value)))
+    });
+    encoder.optional(&mesh.face_materials, |e, face_materials| {
+        e.list(face_materials, |e, material| {
+            e.u32(material.unwrap_or(NONE))
+        })
+    });
+    encoder.list(&mesh.materials, |e, material| {
+        e.string(&material.name);
+        e.optional(&material.diffuse, |e, diffuse| {
+            diffuse.iter().for_each(|&value| e.f32(value))
+        });
+    });
+
+    let mut bytes = Vec::with_capacity(MAGIC.len() + 4 + encoder.0.len());
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&crc32fast::hash(&encoder.0).to_le_bytes());
+    bytes.extend_from_slice(&encoder.0);
+
+    fs::create_dir_all(directory)?;
+    let file_path = key.file_path(directory);
+    let temporary = file_path.with_extension(format!("{}.tmp", std::process::id()));
+    fs::write(&temporary, bytes)?;
+    fs::rename(&temporary, &file_path).inspect_err(|_| {
+        fs::remove_file(&temporary).ok();
+    })
+}
+
+/// The size and modification time of a file, or "missing" if it can not be read, so
+/// that a file created later is noticed too.
+fn file_stamp(path: &Path) -> String {
+    let stamp = fs::metadata(path).ok().and_then(|metadata| {
+        let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some(format!(
+            "{} {} {}",
+            metadata.len(),
+            modified.as_secs(),
+            modified.subsec_nanos()
+        ))
+    });
+    stamp.unwrap_or_else(|| "missing".to_string())
+}
+
+/// Little-endian encoding of the cache files.
+struct Encoder(Vec<u8>);
+
+impl Encoder {
+    fn u32(&mut self, value: u32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn f32(&mut self, value: f32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn f64(&mut self, value: f64) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn real(&mut self, value: Real) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn string(&mut self, value: &str) {
+        self.0
+            .extend_from_slice(&(value.len() as u64).to_le_bytes());
+        self.0.extend_from_slice(value.as_bytes());
+    }
+
+    fn list<T>(&mut self, values: &[T], mut item: impl FnMut(&mut Self, &T)) {
+        self.0
+            .extend_from_slice(&(values.len() as u64).to_le_bytes());
+        for value in values {
+            item(self, value);
+        }
+    }
+
+    fn optional<T>(&mut self, value: &Option<T>, item: impl FnOnce(&mut Self, &T)) {
+        match value {
+            Some(value) => {
+                self.0.push(1);
+                item(self, value);
+            }
+            None => self.0.push(0),
+        }
+    }
+}
+
+/// Reads what `Encoder` writes, returning None at the end of the data.
+struct Decoder<'a>(&'a [u8]);
+
+impl Decoder<'_> {
+    fn bytes<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let (bytes, rest) = self.0.split_first_chunk::<N>()?;
+        self.0 = rest;
+        Some(*bytes)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.bytes()?))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.bytes()?))
+    }
+
+    fn f32(&mut self) -> Option<f32> {
+        Some(f32::from_le_bytes(self.bytes()?))
+    }
+
+    fn f64(&mut self) -> Option<f64> {
+        Some(f64::from_le_bytes(self.bytes()?))
+    }
+
+    fn real(&mut self) -> Option<Real> {
+        Some(Real::from_le_bytes(self.bytes()?))
+    }
+
+    fn string(&mut self) -> Option<String> {
+        let length = usize::try_from(self.u64()?).ok()?;
+        let bytes = self.0.get(..length)?;
+        self.0 = &self.0[length..];
+        String::from_utf8(bytes.to_vec()).ok()
+    }
+
+    fn list<T>(&mut self, mut item: impl FnMut(&mut Self) -> Option<T>) -> Option<Vec<T>> {
+        let count = usize::try_from(self.u64()?).ok()?;
+        // Every item takes at least a byte, which bounds the allocation
+        let mut values = Vec::with_capacity(count.min(self.0.len()));
+        for _ in 0..count {
+            values.push(item(self)?);
+        }
+        Some(values)
+    }
+
+    fn optional<T>(&mut self, item: impl FnOnce(&mut Self) -> Option<T>) -> Option<Option<T>> {
+        match self.bytes::<1>()? {
+            [0] => Some(None),
+            [1] => Some(Some(item(self)?)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let mut mesh = MeshData::new(
+            vec![
+                Point::new(0.0, 0.0, 0.0),
+                Point::new(1.0, 0.0, 0.0),
+                Point::new(0.0, 1.0, 0.5),
+            ],
+            vec![[0, 1, 2]],
+        );
+        mesh.colors = Some(vec![[1.0, 0.5, 0.25, 1.0]; 3]);
+        mesh.face_materials = Some(vec![None]);
+        mesh.materials = vec![Material {
+            name: "steel".to_string(),
+            diffuse: Some([0.5, 0.5, 0.5, 1.0]),
+        }];
+
+        let directory =
+            std::env::temp_dir().join(format!("rs-read-trimesh-cache-{}", std::process::id()));
+        let key = CacheKey {
+            key: "example".to_string(),
+        };
+        write(&directory, &key, &mesh, [1.0, 2.0, 3.0], &[]).unwrap();
+        assert_eq!(
+            read(&directory, &key),
+            Some((mesh.clone(), [1.0, 2.0, 3.0]))
+        );
+
+        // A change of a file read by the load is a miss
+        let dependency = directory.join("materials.mtl");
+        fs::write(&dependency, "newmtl steel").unwrap();
+        write(
+            &directory,
+            &key,
+            &mesh,
+            [1.0, 2.0, 3.0],
+            std::slice::from_ref(&dependency),
+        )
+        .unwrap();
+        assert!(read(&directory, &key).is_some());
+        fs::write(&dependency, "newmtl steel\nKd 0.5 0.5 0.5").unwrap();
+        assert_eq!(read(&directory, &key), None);
+        write(&directory, &key, &mesh, [1.0, 2.0, 3.0], &[dependency]).unwrap();
+
+        // A damaged file is a miss
+        let file_path = key.file_path(&directory);
+        let mut bytes = fs::read(&file_path).unwrap();
+        *bytes.last_mut().unwrap() ^= 1;
+        fs::write(&file_path, bytes).unwrap();
+        assert_eq!(read(&directory, &key), None);
+
+        let other = CacheKey {
+            key: "other".to_string(),
+        };
+        assert_eq!(read(&directory, &other), None);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,523 @@
+use crate::na;
+use crate::xml::{attribute_or, children, required, values};
+use roxmltree::Node as XmlNode;
+use std::collections::HashMap;
+
+/// A Collada document, reduced to what meshes are loaded from. It is read from the XML
+/// tree once, so that the text of the file does not need to be kept.
+pub(crate) struct Document {
+    pub(crate) geometries: Vec<Geometry>,
+    pub(crate) controllers: Vec<Controller>,
+    /// The root nodes of the visual scenes.
+    pub(crate) nodes: Vec<Node>,
+    /// The root nodes of the node libraries, loaded where they are instanced.
+    pub(crate) library_nodes: Vec<Node>,
+    pub(crate) materials: HashMap<String, MaterialDef>,
+    /// The diffuse colors of the effects with a plain color, by effect id.
+    pub(crate) effect_colors: HashMap<String, [f32; 4]>,
+}
+
+pub(crate) struct Geometry {
+    pub(crate) id: Option<String>,
+    pub(crate) name: Option<String>,
+    /// None if the geometry is not a mesh, like splines.
+    pub(crate) mesh: Option<Mesh>,
+}
+
+pub(crate) struct Mesh {
+    pub(crate) sources: Vec<Source>,
+    /// The inputs of `<vertices>`, None if the mesh has none.
+    pub(crate) vertices: Option<Vec<Input>>,
+    pub(crate) triangles: Vec<Triangles>,
+}
+
+/// A `<source>` with its float array, if it has one, and the stride of its accessor.
+pub(crate) struct Source {
+    pub(crate) id: Option<String>,
+    pub(crate) values: Option<Vec<f64>>,
+    pub(crate) stride: usize,
+}
+
+pub(crate) struct Input {
+    pub(crate) semantic: String,
+    pub(crate) source: String,
+    pub(crate) offset: usize,
+    pub(crate) set: u32,
+}
+
+pub(crate) struct Triangles {
+    pub(crate) material: Option<String>,
+    pub(crate) count: usize,
+    pub(crate) inputs: Vec<Input>,
+    /// The number of indices per corner, one more than the largest input offset.
+    pub(crate) stride: usize,
+    pub(crate) indices: Vec<u32>,
+}
+
+pub(crate) struct Controller {
+    pub(crate) id: Option<String>,
+    /// The URL of the geometry or controller that is deformed.
+    pub(crate) source: String,
+    pub(crate) kind: ControllerKind,
+}
+
+pub(crate) enum ControllerKind {
+    Skin {
+        bind_shape_matrix: Option<na::Matrix4<f64>>,
+    },
+    /// The ids of the morph target geometries.
+    Morph { targets: Vec<String> },
+}
+
+pub(crate) struct Node {
+    pub(crate) id: Option<String>,
+    pub(crate) name: Option<String>,
+    /// The transform of the node relative to its parent, from its `<matrix>`,
+    /// `<translate>`, `<rotate>` and `<scale>` elements.
+    pub(crate) transform: na::Matrix4<f64>,
+    pub(crate) instance_geometry: Vec<Instance>,
+    pub(crate) instance_controller: Vec<Instance>,
+    pub(crate) instance_node: Vec<Instance>,
+    pub(crate) children: Vec<Node>,
+}
+
+/// An instance of a geometry, controller or node, with the materials it binds as pairs
+/// of symbol and material URL.
+pub(crate) struct Instance {
+    pub(crate) url: String,
+    pub(crate) materials: Vec<(String, String)>,
+}
+
+pub(crate) struct MaterialDef {
+    pub(crate) name: Option<String>,
+    /// The URL of the instanced effect.
+    pub(crate) effect: String,
+}
+
+impl Document {
+    pub(crate) fn read(document: &roxmltree::Document) -> Result<Document, String> {
+        let root = document.root_element();
+        if !root.has_tag_name("COLLADA") {
+            return Err(format!(
+                "Expected a <COLLADA> root element, found <{}>",
+                root.tag_name().name()
+            ));
+        }
+        let library = |library: &'static str, item: &'static str| {
+            children(root, library).flat_map(move |library| children(library, item))
+        };
+
+        let geometries = library("library_geometries", "geometry")
+            .map(read_geometry)
+            .collect::<Result<_, _>>()?;
+        let controllers = library("library_controllers", "controller")
+            .filter_map(|controller| read_controller(controller).transpose())
+            .collect::<Result<_, _>>()?;
+        let nodes = library("library_visual_scenes", "visual_scene")
+            .flat_map(|scene| children(scene, "node"))
+            .map(read_node)
+            .collect::<Result<_, _>>()?;
+        let library_nodes = library("library_nodes", "node")
+            .map(read_node)
+            .collect::<Result<_, _>>()?;
+        let materials = library("library_materials", "material")
+            .filter_map(|material| {
+                let id = material.attribute("id")?;
+                let effect = children(material, "instance_effect")
+                    .next()?
+                    .attribute("url")?;
+                let material = MaterialDef {
+                    name: material.attribute("name").map(str::to_string),
+                    effect: effect.to_string(),
+                };
+                Some((id.to_string(), material))
+            })
+            .collect();
+        let effect_colors = library("library_effects", "effect")
+            .filter_map(|effect| Some((effect.attribute("id")?.to_string(), diffuse(effect)?)))
+            .collect();
+
+        Ok(Document {
+            geometries,
+            controllers,
+            nodes,
+            library_nodes,
+            materials,
+            effect_colors,
+        })
+    }
+
+    /// The root nodes of the visual scenes and of the node libraries.
+    pub(crate) fn root_nodes(&self) -> impl DoubleEndedIterator<Item = &Node> {
+        self.nodes.iter().chain(&self.library_nodes)
+    }
+
+    /// Calls `f` for every node, parents before their children.
+    pub(crate) fn for_each_node<'a>(&'a self, mut f: impl FnMut(&'a Node)) {
+        let mut stack: Vec<&Node> = self.root_nodes().rev().collect();
+        while let Some(node) = stack.pop() {
+            f(node);
+            stack.extend(node.children.iter().rev());
+        }
+    }
+}
+
+/// Returns the id of a URL that refers into the same document, like `#mesh`.
+pub(crate) fn fragment(url: &str) -> Option<&str> {
+    url.strip_prefix('#')
+}
+
+fn read_geometry(geometry: XmlNode) -> Result<Geometry, String> {
+    let mesh = children(geometry, "mesh")
+        .next()
+        .map(read_mesh)
+        .transpose()?;
+    Ok(Geometry {
+        id: geometry.attribute("id").map(str::to_string),
+        name: geometry.attribute("name").map(str::to_string),
+        mesh,
+    })
+}
+
+fn read_mesh(mesh: XmlNode) -> Result<Mesh, String> {
+    let sources = children(mesh, "source")
+        .map(read_source)
+        .collect::<Result<_, _>>()?;
+    let vertices = children(mesh, "vertices")
+        .next()
+        .map(read_inputs)
+        .transpose()?;
+    let triangles = children(mesh, "triangles")
+        .map(read_triangles)
+        .collect::<Result<_, _>>()?;
+    Ok(Mesh {
+        sources,
+        vertices,
+        triangles,
+    })
+}
+
+fn read_source(source: XmlNode) -> Result<Source, String> {
+    let values = children(source, "float_array")
+        .next()
+        .map(|array| {
+            let id = array.attribute("id").unwrap_or_default();
+            values(array, &format!("float array '{}'", id))
+        })
+        .transpose()?;
+    let stride = children(source, "technique_common")
+        .flat_map(|technique| children(technique, "accessor"))
+        .next()
+        .map(|accessor| attribute_or(accessor, "stride", 1))
+        .transpose()?
+        .unwrap_or(1);
+    Ok(Source {
+        id: source.attribute("id").map(str::to_string),
+        values,
+        stride,
+    })
+}
+
+fn read_inputs(parent: XmlNode) -> Result<Vec<Input>, String> {
+    children(parent, "input")
+        .map(|input| {
+            Ok(Input {
+                semantic: required(input, "semantic")?.to_string(),
+                source: required(input, "source")?.to_string(),
+                offset: attribute_or(input, "offset", 0)?,
+                set: attribute_or(input, "set", 0)?,
+            })
+        })
+        .collect()
+}
+
+fn read_triangles(triangles: XmlNode) -> Result<Triangles, String> {
+    let inputs = read_inputs(triangles)?;
+    let stride = match inputs.iter().map(|input| input.offset).max() {
+        Some(offset) => offset
+            .checked_add(1)
+            .ok_or_else(|| format!("Invalid input offset {} of the triangles", offset))?,
+        None => 1,
+    };
+    let indices = match children(triangles, "p").next() {
+        Some(p) => values(p, "the indices of the triangles")?,
+        None => Vec::new(),
+    };
+    Ok(Triangles {
+        material: triangles.attribute("material").map(str::to_string),
+        count: attribute_or(triangles, "count", 0)?,
+        inputs,
+        stride,
+        indices,
+    })
+}
+
+/// Reads a skin or morph controller, returns None for other controllers.
+fn read_controller(controller: XmlNode) -> Result<Option<Controller>, String> {
+    let id = controller.attribute("id").map(str::to_string);
+    if let Some(skin) = children(controller, "skin").next() {
+        let bind_shape_matrix = match children(skin, "bind_shape_matrix").next() {
+            Some(matrix) => {
+                let values: Vec<f64> = values(matrix, "the bind shape matrix")?;
+                if values.len() != 16 {
+                    return Err(format!(
+                        "The bind shape matrix has {} values instead of 16",
+                        values.len()
+                    ));
+                }
+                Some(na::Matrix4::from_row_slice(&values))
+            }
+            None => None,
+        };
+        return Ok(Some(Controller {
+            id,
+            source: required(skin, "source")?.to_string(),
+            kind: ControllerKind::Skin { bind_shape_matrix },
+        }));
+    }
+    if let Some(morph) = children(controller, "morph").next() {
+        // The target geometries are listed by the source of the MORPH_TARGET input
+        let inputs = match children(morph, "targets").next() {
+            Some(targets) => read_inputs(targets)?,
+            None => Vec::new(),
+        };
+        let mut targets = Vec::new();
+        if let Some(input) = inputs
+            .iter()
+            .find(|input| input.semantic == "MORPH_TARGET")
+            .and_then(|input| fragment(&input.source))
+        {
+            for source in children(morph, "source") {
+                if source.attribute("id") == Some(input) {
+                    let ids = children(source, "IDREF_array")
+                        .filter_map(|array| array.text())
+                        .flat_map(str::split_whitespace);
+                    targets.extend(ids.map(str::to_string));
+                }
+            }
+        }
+        return Ok(Some(Controller {
+            id,
+            source: required(morph, "source")?.to_string(),
+            kind: ControllerKind::Morph { targets },
+        }));
+    }
+    Ok(None)
+}
+
+fn read_node(node: XmlNode) -> Result<Node, String> {
+    let instances = |name: &'static str| {
+        children(node, name)
+            .map(|instance| {
+                let materials = children(instance, "bind_material")
+                    .flat_map(|bind| children(bind, "technique_common"))
+                    .flat_map(|technique| children(technique, "instance_material"))
+                    .map(|material| {
+                        Ok((
+                            required(material, "symbol")?.to_string(),
+                            required(material, "target")?.to_string(),
+                        ))
+                    })
+                    .collect::<Result<_, String>>()?;
+                Ok(Instance {
+                    url: required(instance, "url")?.to_string(),
+                    materials,
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()
+    };
+    Ok(Node {
+        id: node.attribute("id").map(str::to_string),
+        name: node.attribute("name").map(str::to_string),
+        transform: read_transform(node)?,
+        instance_geometry: instances("instance_geometry")?,
+        instance_controller: instances("instance_controller")?,
+        instance_node: instances("instance_node")?,
+        children: children(node, "node")
+            .map(read_node)
+            .collect::<Result<_, _>>()?,
+    })
+}
+
+/// Composes the transform elements of a node in their order, the last one applies
+/// first. Other transforms, like `<lookat>` and `<skew>`, are ignored.
+fn read_transform(node: XmlNode) -> Result<na::Matrix4<f64>, String> {
+    let mut transform = na::Matrix4::identity();
+    for element in node.children() {
+        let name = element.tag_name().name();
+        let expected = match name {
+            "matrix" => 16,
+            "rotate" => 4,
+            "translate" | "scale" => 3,
+            _ => continue,
+        };
+        let values: Vec<f64> = values(element, &format!("<{}>", name))?;
+        if values.len() != expected {
+            return Err(format!(
+                "<{}> has {} values instead of {}",
+                name,
+                values.len(),
+                expected
+            ));
+        }
+        let vector = na::Vector3::new(values[0], values[1], values[2]);
+        transform *= match name {
+            "matrix" => na::Matrix4::from_row_slice(&values),
+            "rotate" => match na::Unit::try_new(vector, f64::EPSILON) {
+                Some(axis) => {
+                    na::Rotation3::from_axis_angle(&axis, values[3].to_radians()).to_homogeneous()
+                }
+                // Without an axis there is no rotation
+                None => na::Matrix4::identity(),
+            },
+            "translate" => na::Matrix4::new_translation(&vector),
+            _ => na::Matrix4::new_nonuniform_scaling(&vector),
+        };
+    }
+    Ok(transform)
+}
+
+/// Returns the diffuse color of the common profile, if it is a plain color and not a texture.
+fn diffuse(effect: XmlNode) -> Option<[f32; 4]> {
+    let diffuse = children(effect, "profile_COMMON")
+        .flat_map(|profile| children(profile, "technique"))
+        .flat_map(|technique| technique.children())
+        .filter(|shader| ["blinn", "lambert", "phong"].contains(&shader.tag_name().name()))
+        .find_map(|shader| children(shader, "diffuse").next())?;
+    let color: Vec<f32> = values(children(diffuse, "color").next()?, "the diffuse color").ok()?;
+    match color[..] {
+        [r, g, b] => Some([r, g, b, 1.0]),
+        [r, g, b, a] => Some([r, g, b, a]),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(text: &str) -> Result<Document, String> {
+        let document = roxmltree::Document::parse(text).map_err(|e| e.to_string())?;
+        Document::read(&document)
+    }
+
+    #[test]
+    fn test_read_document() {
+        let document = read(
+            r##"<COLLADA>
+              <library_effects>
+                <effect id="red-fx"><profile_COMMON><technique><lambert>
+                  <diffuse><color>1 0 0</color></diffuse>
+                </lambert></technique></profile_COMMON></effect>
+              </library_effects>
+              <library_materials>
+                <material id="red" name="Red"><instance_effect url="#red-fx"/></material>
+              </library_materials>
+              <library_geometries>
+                <geometry id="tri" name="Triangle"><mesh>
+                  <source id="positions">
+                    <float_array id="values" count="9">0 0 0 1 0 0 0 1 0</float_array>
+                    <technique_common><accessor source="#values" stride="3"/></technique_common>
+                  </source>
+                  <vertices id="vertices"><input semantic="POSITION" source="#positions"/></vertices>
+                  <triangles material="surface" count="1">
+                    <input semantic="VERTEX" source="#vertices" offset="0"/>
+                    <input semantic="TEXCOORD" source="#uv" offset="1" set="1"/>
+                    <p>0 0 1 1 2 2</p>
+                  </triangles>
+                </mesh></geometry>
+                <geometry id="curve"><spline/></geometry>
+              </library_geometries>
+              <library_visual_scenes><visual_scene id="scene">
+                <node id="parent" name="Parent">
+                  <translate>1 2 3</translate>
+                  <node id="child">
+                    <instance_geometry url="#tri">
+                      <bind_material><technique_common>
+                        <instance_material symbol="surface" target="#red"/>
+                      </technique_common></bind_material>
+                    </instance_geometry>
+                  </node>
+                </node>
+              </visual_scene></library_visual_scenes>
+              <library_nodes><node id="shared"><instance_node url="#parent"/></node></library_nodes>
+            </COLLADA>"##,
+        )
+        .unwrap();
+
+        assert_eq!(document.geometries.len(), 2);
+        assert!(document.geometries[1].mesh.is_none());
+        let geometry = &document.geometries[0];
+        assert_eq!(geometry.name.as_deref(), Some("Triangle"));
+        let mesh = geometry.mesh.as_ref().unwrap();
+        assert_eq!(mesh.sources[0].stride, 3);
+        assert_eq!(mesh.sources[0].values.as_ref().map(Vec::len), Some(9));
+        assert_eq!(mesh.vertices.as_ref().map(Vec::len), Some(1));
+        let triangles = &mesh.triangles[0];
+        assert_eq!(triangles.material.as_deref(), Some("surface"));
+        assert_eq!((triangles.count, triangles.stride), (1, 2));
+        assert_eq!(triangles.inputs[1].set, 1);
+        assert_eq!(triangles.indices, [0, 0, 1, 1, 2, 2]);
+
+        // Scene nodes come before library nodes
+        let ids: Vec<_> = document
+            .root_nodes()
+            .map(|node| node.id.as_deref())
+            .collect();
+        assert_eq!(ids, [Some("parent"), Some("shared")]);
+        assert_eq!(document.library_nodes.len(), 1);
+        let parent = &document.nodes[0];
+        assert_eq!(
+            parent.transform,
+            na::Matrix4::new_translation(&na::Vector3::new(1.0, 2.0, 3.0))
+        );
+        let instance = &parent.children[0].instance_geometry[0];
+        assert_eq!(instance.url, "#tri");
+        assert_eq!(
+            instance.materials,
+            [("surface".to_string(), "#red".to_string())]
+        );
+        assert_eq!(document.library_nodes[0].instance_node[0].url, "#parent");
+
+        let mut visited = Vec::new();
+        document.for_each_node(|node| visited.push(node.id.as_deref()));
+        assert_eq!(visited, [Some("parent"), Some("child"), Some("shared")]);
+
+        assert_eq!(document.materials["red"].effect, "#red-fx");
+        assert_eq!(document.effect_colors["red-fx"], [1.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn test_read_transform() {
+        // The last transform applies first: the point is scaled, then rotated, then moved
+        let document = read(
+            r#"<COLLADA><library_nodes><node>
+              <translate>0 0 1</translate>
+              <rotate>0 0 1 90</rotate>
+              <scale>2 2 2</scale>
+              <lookat>0 0 0 1 1 1 0 0 1</lookat>
+            </node></library_nodes></COLLADA>"#,
+        )
+        .unwrap();
+        let point = document.library_nodes[0]
+            .transform
+            .transform_point(&na::Point3::new(1.0, 0.0, 0.0));
+        assert!((point - na::Point3::new(0.0, 2.0, 1.0)).norm() < 1e-9);
+    }
+
+    #[test]
+    fn test_read_errors() {
+        assert!(read("<amf/>").is_err());
+        // Values that cannot be converted are errors rather than skipped
+        let invalid = [
+            "<library_nodes><node><matrix>1 0 0</matrix></node></library_nodes>",
+            "<library_nodes><node><instance_geometry/></node></library_nodes>",
+            "<library_geometries><geometry><mesh><triangles count='x'/></mesh></geometry></library_geometries>",
+            "<library_geometries><geometry><mesh><triangles><p>0 -1 2</p></triangles></mesh></geometry></library_geometries>",
+        ];
+        for content in invalid {
+            let text = format!("<COLLADA>{}</COLLADA>", content);
+            assert!(read(&text).is_err(), "{}", content);
+        }
+    }
+}
@@ -0,0 +1,231 @@
+use crate::mesh_data::MeshData;
+use crate::{Point, Real, TriMesh, TriMeshFlags};
+use std::collections::HashMap;
+
+/// How faces must touch to belong to the same connected component.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Connectivity {
+    /// Faces sharing at least one vertex are connected.
+    #[default]
+    SharedVertex,
+    /// Faces are only connected if they share an edge, so bodies touching
+    /// in a single corner are kept apart.
+    SharedEdge,
+}
+
+/// A connected component of a mesh, with the values needed to decide what to do with it.
+#[derive(Clone)]
+pub struct MeshComponent {
+    /// The component as its own mesh.
+    pub mesh: TriMesh,
+    /// Minimum corner of the axis-aligned bounding box.
+    pub mins: Point<Real>,
+    /// Maximum corner of the axis-aligned bounding box.
+    pub maxs: Point<Real>,
+    /// Enclosed volume, computed from the faces. Only meaningful for closed components.
+    pub volume: Real,
+}
+
+impl MeshComponent {
+    /// Builds the component mesh with the given flags and computes its bounds and volume.
+    pub fn from_mesh_data(mesh: MeshData, flags: TriMeshFlags) -> Result<Self, String> {
+        let (mins, maxs) = mesh.bounds();
+        let volume = mesh.volume();
+        Ok(MeshComponent {
+            mesh: mesh.into_trimesh(flags)?,
+            mins,
+            maxs,
+            volume,
+        })
+    }
+}
+
+impl MeshData {
+    /// Splits the mesh into its connected components, ordered by their first face.
+    /// Vertices at exactly the same position are treated as one, as mesh formats
+    /// like STL and OBJ often repeat them.
+    pub fn split_components(&self, connectivity: Connectivity) -> Vec<MeshData> {
+        // Indices out of range connect nothing
+        let position_ids = self.position_ids();
+        let vertex_id = |index: u32| position_ids.get(index as usize).copied();
+        let mut faces = UnionFind::new(self.indices.len());
+
+        match connectivity {
+            Connectivity::SharedVertex => {
+                let mut first_face = HashMap::new();
+                for (face, triangle) in self.indices.iter().enumerate() {
+                    for id in triangle.iter().filter_map(|&index| vertex_id(index)) {
+                        let other = *first_face.entry(id).or_insert(face);
+                        faces.union(face, other);
+                    }
+                }
+            }
+            Connectivity::SharedEdge => {
+                let mut first_face = HashMap::new();
+                for (face, triangle) in self.indices.iter().enumerate() {
+                    for corner in 0..3 {
+                        let (Some(a), Some(b)) = (
+                            vertex_id(triangle[corner]),
+                            vertex_id(triangle[(corner + 1) % 3]),
+                        ) else {
+                            continue;
+                        };
+                        let other = *first_face.entry((a.min(b), a.max(b))).or_insert(face);
+                        faces.union(face, other);
+                    }
+                }
+            }
+        }
+
+        // Group faces by their root, in order of the first face of each group
+        let mut groups: Vec<Vec<usize>> = Vec::new();
+        let mut group_of_root = HashMap::new();
+        for face in 0..self.indices.len() {
+            let root = faces.find(face);
+            let group = *group_of_root.entry(root).or_insert_with(|| {
+                groups.push(Vec::new());
+                groups.len() - 1
+            });
+            groups[group].push(face);
+        }
+
+        groups
+            .iter()
+            .map(|faces| self.extract_faces(faces))
+            .collect()
+    }
+
+    /// Returns the minimum and maximum corner of the axis-aligned bounding box.
+    /// An empty mesh has both corners at the origin.
+    pub fn bounds(&self) -> (Point<Real>, Point<Real>) {
+        let Some(first) = self.vertices.first() else {
+            return (Point::origin(), Point::origin());
+        };
+        self.vertices
+            .iter()
+            .fold((*first, *first), |(mins, maxs), vertex| {
+                (mins.inf(vertex), maxs.sup(vertex))
+            })
+    }
+
+    /// Returns the volume enclosed by the faces, as the sum of the signed volumes of the
+    /// tetrahedra they form with the origin. The result is only meaningful for closed
+    /// meshes. It is returned as absolute value, so inverted winding does not matter.
+    /// Faces with indices out of range are skipped.
+    pub fn volume(&self) -> Real {
+        let corner = |index: u32| {
+            let vertex = self.vertices.get(index as usize)?;
+            Some([vertex.x as f64, vertex.y as f64, vertex.z as f64])
+        };
+        let volume: f64 = self
+            .indices
+            .iter()
+            .filter_map(|&[a, b, c]| Some([corner(a)?, corner(b)?, corner(c)?]))
+            .map(|[a, b, c]| {
+                (a[0] * (b[1] * c[2] - b[2] * c[1]) - a[1] * (b[0] * c[2] - b[2] * c[0])
+                    + a[2] * (b[0] * c[1] - b[1] * c[0]))
+                    / 6.0
+            })
+            .sum();
+        volume.abs() as Real
+    }
+
+    /// Returns an id per vertex that is equal for vertices at the same position.
+    fn position_ids(&self) -> Vec<usize> {
+        let mut ids = HashMap::new();
+        self.vertices
+            .iter()
+            .map(|vertex| {
+                // Adding zero turns -0.0 into 0.0, so both get the same id
+                let key = [vertex.x, vertex.y, vertex.z].map(|value| (value + 0.0).to_bits());
+                let next = ids.len();
+                *ids.entry(key).or_insert(next)
+            })
+            .collect()
+    }
+}
+
+/// Disjoint sets of faces, merged as connections between them are found.
+struct UnionFind {
+    parents: Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(size: usize) -> Self {
+        UnionFind {
+            parents: (0..size).collect(),
+        }
+    }
+
+    fn find(&mut self, mut item: usize) -> usize {
+        while self.parents[item] != item {
+            // Path halving keeps the trees flat
+            self.parents[item] = self.parents[self.parents[item]];
+            item = self.parents[item];
+        }
+        item
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a != b {
+            self.parents[a.max(b)] = a.min(b);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two triangles touching in a single corner, and a third one apart.
+    fn corner_touching() -> MeshData {
+        MeshData::new(
+            vec![
+                Point::new(0.0, 0.0, 0.0),
+                Point::new(1.0, 0.0, 0.0),
+                Point::new(0.0, 1.0, 0.0),
+                // Repeats vertex 1, as STL and OBJ files often do
+                Point::new(1.0, 0.0, 0.0),
+                Point::new(2.0, 0.0, 0.0),
+                Point::new(2.0, 1.0, 0.0),
+                Point::new(5.0, 5.0, 5.0),
+                Point::new(6.0, 5.0, 5.0),
+                Point::new(5.0, 6.0, 5.0),
+            ],
+            vec![[0, 1, 2], [6, 7, 8], [3, 4, 5]],
+        )
+    }
+
+    #[test]
+    fn test_split_components_shared_vertex() {
+        let components = corner_touching().split_components(Connectivity::SharedVertex);
+        assert_eq!(components.len(), 2);
+        assert_eq!(components[0].indices, vec![[0, 1, 2], [3, 4, 5]]);
+        assert_eq!(components[1].indices, vec![[0, 1, 2]]);
+        assert_eq!(components[1].vertices[0], Point::new(5.0, 5.0, 5.0));
+    }
+
+    #[test]
+    fn test_split_components_shared_edge() {
+        let components = corner_touching().split_components(Connectivity::SharedEdge);
+        assert_eq!(components.len(), 3);
+        assert_eq!(components[1].bounds().0, Point::new(5.0, 5.0, 5.0));
+        assert_eq!(components[2].bounds().1, Point::new(2.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn test_volume() {
+        // Unit tetrahedron with outward winding
+        let mesh = MeshData::new(
+            vec![
+                Point::new(0.0, 0.0, 0.0),
+                Point::new(1.0, 0.0, 0.0),
+                Point::new(0.0, 1.0, 0.0),
+                Point::new(0.0, 0.0, 1.0),
+            ],
+            vec![[0, 2, 1], [0, 1, 3], [0, 3, 2], [1, 2, 3]],
+        );
+        assert!((mesh.volume() - 1.0 / 6.0).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,805 @@
+use crate::collada::{ControllerKind, Document, Geometry, Input, Mesh, Node, fragment};
+use crate::error::LoadError;
+use crate::limits::Limit;
+use crate::loader::{LoadContext, SubMeshes};
+use crate::mesh_data::{CornerVertices, Material, MeshData, NO_INDEX, material_id, merge_meshes};
+use crate::name_filter::NameFilter;
+use crate::resolver::{self, UriResolver};
+use crate::{Real, Vector, na};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// Function to load a TriMesh from a Collada file. The geometries, controllers and
+/// nodes that the nodes of the visual scenes instance are loaded for each instance,
+/// placed by the transforms of the nodes, also from other files, see `References`.
+/// Geometries that no node instances are loaded as they are. All triangle meshes are
+/// merged, unless only some geometries are selected.
+///
+/// Skinned meshes are loaded in their bind pose, transformed by the bind shape matrix
+/// of their skin controller. Morphed meshes are loaded as their base mesh, the morph
+/// targets are skipped unless they are also instanced as geometries.
+pub(crate) fn load_trimesh_from_dae<R: Read>(
+    mut reader: R,
+    dae_file_path: &str,
+    context: &mut LoadContext,
+) -> Result<MeshData, LoadError> {
+    // Read the file
+    let mut text = String::new();
+    reader
+        .read_to_string(&mut text)
+        .map_err(|e| format!("Failed to open .dae file {}: {}", dae_file_path, e))?;
+    let collada = Collada::parse(&text, context)?;
+    context.set_total_elements(collada.geometries().count() as u64);
+
+    let mut meshes = Vec::new();
+    let mut references = References::new(context);
+    references.load(&collada, "", &na::Matrix4::identity(), context, &mut meshes)?;
+
+    if meshes.is_empty() {
+        Err("The .dae file contains no mesh".into())
+    } else {
+        Ok(merge_meshes(meshes)?)
+    }
+}
+
+/// A parsed Collada document, with what is needed to convert its meshes.
+struct Collada {
+    document: Document,
+    materials: HashMap<String, Material>,
+    /// The bind shape matrices of the skinned geometries, by geometry id.
+    bind_shapes: HashMap<String, na::Matrix4<f64>>,
+    /// The geometries only used as morph targets, by id.
+    morph_targets: HashSet<String>,
+    /// The names of the nodes that instance the geometries, by geometry id.
+    node_names: HashMap<String, Vec<String>>,
+}
+
+impl Collada {
+    fn parse(text: &str, context: &LoadContext) -> Result<Collada, LoadError> {
+        let options = roxmltree::ParsingOptions {
+            allow_dtd: true,
+            ..Default::default()
+        };
+        let xml = context
+            .options
+            .limits
+            .parse_xml(text, options)
+            .map_err(|e| e.context("Failed to parse .dae file"))?;
+        let document =
+            Document::read(&xml).map_err(|e| format!("Failed to parse .dae file: {}", e))?;
+        let (bind_shapes, morph_targets) = controlled_geometries(&document);
+        Ok(Collada {
+            materials: material_bindings(&document),
+            node_names: node_names(&document),
+            document,
+            bind_shapes,
+            morph_targets,
+        })
+    }
+
+    fn geometries(&self) -> impl Iterator<Item = &Geometry> {
+        self.document.geometries.iter()
+    }
+
+    /// Whether a geometry passes the name filter, by its own names or those of the nodes
+    /// that instance it.
+    fn filter_matches(&self, geometry: &Geometry, filter: &NameFilter) -> bool {
+        let mut names = vec![geometry.id.as_deref(), geometry.name.as_deref()];
+        if let Some(node_names) = geometry.id.as_ref().and_then(|id| self.node_names.get(id)) {
+            names.extend(node_names.iter().map(|name| Some(name.as_str())));
+        }
+        filter.matches(&names)
+    }
+
+    /// Converts a geometry, placed by the transform of the node that instances it, if
+    /// any. Returns None if it is not a mesh, has no vertices or is a morph target.
+    fn convert(
+        &self,
+        geometry: &Geometry,
+        transform: Option<&na::Matrix4<f64>>,
+        context: &mut LoadContext,
+    ) -> Result<Option<MeshData>, String> {
+        let id = geometry.id.as_deref().unwrap_or_default();
+        if self.morph_targets.contains(id) {
+            return Ok(None);
+        }
+        let transform = match (transform, self.bind_shapes.get(id)) {
+            (Some(transform), Some(bind_shape)) => Some(transform * bind_shape),
+            (transform, bind_shape) => transform.or(bind_shape).copied(),
+        };
+        match &geometry.mesh {
+            Some(mesh) => mesh_from_collada(mesh, &self.materials, transform.as_ref(), context),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the node with the given id.
+    fn node(&self, id: &str) -> Option<&Node> {
+        let mut found = None;
+        self.document.for_each_node(|node| {
+            if found.is_none() && node.id.as_deref() == Some(id) {
+                found = Some(node);
+            }
+        });
+        found
+    }
+}
+
+/// The URLs of the geometries, controllers and nodes that a node instances.
+fn instance_urls(node: &Node) -> impl Iterator<Item = &str> {
+    let instances = node.instance_geometry.iter();
+    let instances = instances.chain(&node.instance_controller);
+    let instances = instances.chain(&node.instance_node);
+    instances.map(|instance| instance.url.as_str())
+}
+
+/// Finds the bind shape matrices of the skinned geometries, and the morph targets that
+/// are not instanced as geometries. Skins of morphed meshes apply to the base mesh.
+fn controlled_geometries(
+    document: &Document,
+) -> (HashMap<String, na::Matrix4<f64>>, HashSet<String>) {
+    let sources = controller_sources(document);
+    let mut bind_shapes = HashMap::new();
+    let mut morph_targets = HashSet::new();
+    for controller in &document.controllers {
+        match &controller.kind {
+            ControllerKind::Skin { bind_shape_matrix } => {
+                let Some(source) = fragment(&controller.source) else {
+                    continue;
+                };
+                let source = controlled_geometry(&sources, source);
+                if let Some(matrix) = bind_shape_matrix {
+                    bind_shapes.entry(source.to_string()).or_insert(*matrix);
+                }
+            }
+            ControllerKind::Morph { targets } => {
+                morph_targets.extend(targets.iter().cloned());
+            }
+        }
+    }
+
+    // Targets that are also instanced on their own are loaded
+    document.for_each_node(|node| {
+        for instance in &node.instance_geometry {
+            if let Some(id) = fragment(&instance.url) {
+                morph_targets.remove(id);
+            }
+        }
+    });
+    (bind_shapes, morph_targets)
+}
+
+/// The sources of the controllers with a local source, by controller id.
+fn controller_sources(document: &Document) -> HashMap<&str, &str> {
+    document
+        .controllers
+        .iter()
+        .filter_map(|controller| Some((controller.id.as_deref()?, fragment(&controller.source)?)))
+        .collect()
+}
+
+/// Resolves the id of a controller to the geometry it deforms, through the morph
+/// controllers a skin may deform. Other ids are returned as they are.
+fn controlled_geometry<'a>(sources: &HashMap<&'a str, &'a str>, mut id: &'a str) -> &'a str {
+    // Bounded, in case controllers refer to each other in a cycle
+    for _ in 0..sources.len() {
+        match sources.get(id) {
+            Some(source) => id = source,
+            None => break,
+        }
+    }
+    id
+}
+
+/// Finds the ids and names of the nodes that instance each geometry, directly or through
+/// a controller, and of their ancestors, by geometry id.
+fn node_names(document: &Document) -> HashMap<String, Vec<String>> {
+    let sources = controller_sources(document);
+    let mut names = HashMap::new();
+    for node in document.root_nodes() {
+        add_node_names(node, &sources, &mut Vec::new(), &mut names);
+    }
+    names
+}
+
+fn add_node_names<'a>(
+    node: &'a Node,
+    sources: &HashMap<&'a str, &'a str>,
+    ancestors: &mut Vec<&'a str>,
+    names: &mut HashMap<String, Vec<String>>,
+) {
+    let depth = ancestors.len();
+    ancestors.extend(node.id.as_deref());
+    ancestors.extend(node.name.as_deref());
+    let instances = node
+        .instance_geometry
+        .iter()
+        .chain(&node.instance_controller);
+    for instance in instances {
+        if let Some(id) = fragment(&instance.url) {
+            let geometry = controlled_geometry(sources, id);
+            let geometry_names = names.entry(geometry.to_string()).or_default();
+            geometry_names.extend(ancestors.iter().map(|name| name.to_string()));
+        }
+    }
+    for child in &node.children {
+        add_node_names(child, sources, ancestors, names);
+    }
+    ancestors.truncate(depth);
+}
+
+/// Follows the instances of a Collada file, to elements in the same file like `#wheel`
+/// and in other files like `parts.dae#wheel`. Other files are resolved relative to the
+/// file they are in, or with the resolver of the options, checked against the same
+/// limits and each parsed once. An element is loaded every time it is instanced,
+/// placed by the transforms of the nodes down to the instance. An element that refers
+/// back to itself, directly or through other elements, is an error. The references
+/// and the nodes they pass through nest like XML elements, and are limited as deep.
+///
+/// Geometries are selected by their index in the loaded file only, in other files by
+/// their id or name.
+struct References {
+    documents: HashMap<PathBuf, Rc<Collada>>,
+    /// The elements being loaded, by file and id (empty for the whole file). The loaded
+    /// file is at the bottom, with an empty path if it is loaded from a reader.
+    stack: Vec<(PathBuf, String)>,
+    /// The number of nodes being loaded, within the elements on the stack.
+    nodes: usize,
+    /// The geometries converted at least once, by file and id.
+    converted: HashSet<(PathBuf, String)>,
+    resolver: UriResolver,
+    /// The faces loaded so far, as instancing can multiply them.
+    faces: usize,
+}
+
+impl References {
+    /// Starts from the loaded file.
+    fn new(context: &LoadContext) -> References {
+        let path = context.file_path().map_or_else(PathBuf::new, |file_path| {
+            fs::canonicalize(file_path).unwrap_or_else(|_| PathBuf::from(file_path))
+        });
+        let resolver = context.options.resolver.clone();
+        References {
+            documents: HashMap::new(),
+            stack: vec![(path, String::new())],
+            nodes: 0,
+            converted: HashSet::new(),
+            resolver: resolver.unwrap_or_else(UriResolver::from_env),
+            faces: 0,
+        }
+    }
+
+    /// Loads what a URL to another file refers to, relative to the file being loaded.
+    fn follow(
+        &mut self,
+        url: &str,
+        transform: &na::Matrix4<f64>,
+        context: &mut LoadContext,
+        meshes: &mut Vec<MeshData>,
+    ) -> Result<(), LoadError> {
+        if self.stack[0].0.as_os_str().is_empty() {
+            return Err(format!(
+                "The .dae file refers to '{}', other files can only be loaded from a file",
+                url
+            )
+            .into());
+        }
+        let (referrer, _) = self.stack.last().expect("The loaded file is on the stack");
+        let (file, id) = url.split_once('#').unwrap_or((url, ""));
+        let file = resolver::percent_decode(file);
+        let directory = referrer.parent().unwrap_or(Path::new(""));
+        let path = self
+            .resolver
+            .resolve(&file, directory)
+            .and_then(|path| fs::canonicalize(path).map_err(|e| e.to_string()))
+            .map_err(|e| {
+                format!(
+                    "Failed to open .dae file {} referenced by {}: {}",
+                    file,
+                    referrer.display(),
+                    e
+                )
+            })?;
+
+        self.enter(path.clone(), id, context)?;
+        let result = self
+            .open(&path, context)
+            .and_then(|collada| self.load(&collada, id, transform, context, meshes));
+        self.stack.pop();
+        result
+    }
+
+    /// Loads an element of the file on top of the stack, given by id.
+    fn load_local(
+        &mut self,
+        collada: &Collada,
+        id: &str,
+        transform: &na::Matrix4<f64>,
+        context: &mut LoadContext,
+        meshes: &mut Vec<MeshData>,
+    ) -> Result<(), LoadError> {
+        let (path, _) = self.stack.last().expect("The loaded file is on the stack");
+        self.enter(path.clone(), id, context)?;
+        let result = self.load(collada, id, transform, context, meshes);
+        self.stack.pop();
+        result
+    }
+
+    /// Pushes an element on the stack. Fails if it is being loaded, or if the
+    /// references are nested too deep.
+    fn enter(&mut self, path: PathBuf, id: &str, context: &LoadContext) -> Result<(), LoadError> {
+        let key = (path, id.to_string());
+        if self.stack.contains(&key) {
+            return Err(format!(
+                "Cyclic reference to '{}#{}' in the .dae files",
+                key.0.display(),
+                id
+            )
+            .into());
+        }
+        self.stack.push(key);
+        let depth = self.stack.len() + self.nodes;
+        Ok(context.options.limits.check_count(Limit::XmlDepth, depth)?)
+    }
+
+    /// Loads the element with the given id, or the whole file if the id is empty.
+    fn load(
+        &mut self,
+        collada: &Collada,
+        id: &str,
+        transform: &na::Matrix4<f64>,
+        context: &mut LoadContext,
+        meshes: &mut Vec<MeshData>,
+    ) -> Result<(), LoadError> {
+        if id.is_empty() {
+            for node in &collada.document.nodes {
+                self.load_node(collada, node, transform, context, meshes)?;
+            }
+            // Geometries that no node instances are loaded as they are
+            let (path, _) = self.stack.last().expect("The loaded file is on the stack");
+            let path = path.clone();
+            for geometry in collada.geometries() {
+                let instanced = geometry
+                    .id
+                    .as_ref()
+                    .is_some_and(|id| self.converted.contains(&(path.clone(), id.clone())));
+                if !instanced {
+                    self.convert(collada, geometry, transform, context, meshes)?;
+                }
+            }
+            Ok(())
+        } else if let Some(geometry) = collada
+            .geometries()
+            .find(|geometry| geometry.id.as_deref() == Some(id))
+        {
+            self.convert(collada, geometry, transform, context, meshes)
+        } else if let Some(node) = collada.node(id) {
+            self.load_node(collada, node, transform, context, meshes)
+        } else if let Some(controller) = collada
+            .document
+            .controllers
+            .iter()
+            .find(|controller| controller.id.as_deref() == Some(id))
+        {
+            // The controlled mesh, skins are applied by `Collada::convert`
+            match fragment(&controller.source) {
+                Some(id) => self.load_local(collada, id, transform, context, meshes),
+                None => self.follow(&controller.source, transform, context, meshes),
+            }
+        } else {
+            let (path, _) = self.stack.last().expect("The loaded file is on the stack");
+            Err(format!(
+                "The .dae file {} has no geometry, controller or node '{}'",
+                path.display(),
+                id
+            )
+            .into())
+        }
+    }
+
+    /// Loads what a node and its children instance, placed by the node transforms.
+    fn load_node(
+        &mut self,
+        collada: &Collada,
+        node: &Node,
+        parent: &na::Matrix4<f64>,
+        context: &mut LoadContext,
+        meshes: &mut Vec<MeshData>,
+    ) -> Result<(), LoadError> {
+        self.nodes += 1;
+        let depth = self.stack.len() + self.nodes;
+        context.options.limits.check_count(Limit::XmlDepth, depth)?;
+
+        let transform = parent * node.transform;
+        for url in instance_urls(node) {
+            match fragment(url) {
+                Some(id) => self.load_local(collada, id, &transform, context, meshes)?,
+                None => self.follow(url, &transform, context, meshes)?,
+            }
+        }
+        for child in &node.children {
+            self.load_node(collada, child, &transform, context, meshes)?;
+        }
+        self.nodes -= 1;
+        Ok(())
+    }
+
+    fn convert(
+        &mut self,
+        collada: &Collada,
+        geometry: &Geometry,
+        transform: &na::Matrix4<f64>,
+        context: &mut LoadContext,
+        meshes: &mut Vec<MeshData>,
+    ) -> Result<(), LoadError> {
+        // The progress counts the geometries of the loaded file, when first converted
+        let (path, _) = self.stack.last().expect("The loaded file is on the stack");
+        let in_loaded_file = *path == self.stack[0].0;
+        let first = match &geometry.id {
+            Some(id) => self.converted.insert((path.clone(), id.clone())),
+            None => true,
+        };
+        context.elements_parsed(u64::from(in_loaded_file && first))?;
+
+        let sub_meshes = &context.options.sub_meshes;
+        let index = match sub_meshes {
+            SubMeshes::Indices(_) if !in_loaded_file => return Ok(()),
+            SubMeshes::Indices(_) => collada
+                .geometries()
+                .position(|item| std::ptr::eq(item, geometry))
+                .unwrap_or_default(),
+            _ => 0,
+        };
+        let names = [geometry.id.as_deref(), geometry.name.as_deref()];
+        if !sub_meshes.selects(index, &names)
+            || !collada.filter_matches(geometry, &context.options.name_filter)
+        {
+            return Ok(());
+        }
+        let transform = (*transform != na::Matrix4::identity()).then_some(transform);
+        if let Some(mesh_data) = collada.convert(geometry, transform, context)? {
+            self.faces = self.faces.saturating_add(mesh_data.indices.len());
+            context
+                .options
+                .limits
+                .check_count(Limit::Faces, self.faces)?;
+            meshes.push(mesh_data);
+        }
+        Ok(())
+    }
+
+    /// Parses a referenced file, or returns it if it is already parsed.
+    fn open(&mut self, path: &Path, context: &mut LoadContext) -> Result<Rc<Collada>, LoadError> {
+        if let Some(collada) = self.documents.get(path) {
+            return Ok(collada.clone());
+        }
+        context.add_dependency(path);
+        let size = fs::metadata(path)
+            .map_err(|e| format!("Failed to open .dae file {}: {}", path.display(), e))?
+            .len();
+        context.options.limits.check(Limit::FileSize, size)?;
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to open .dae file {}: {}", path.display(), e))?;
+        let collada =
+            Rc::new(Collada::parse(&text, context).map_err(|e| e.context(path.display()))?);
+        self.documents.insert(path.to_path_buf(), collada.clone());
+        Ok(collada)
+    }
+}
+
+/// Resolves the material symbols bound by the geometry and controller instances of the
+/// scene nodes. The first binding of a symbol wins if instances bind it differently.
+fn material_bindings(document: &Document) -> HashMap<String, Material> {
+    let mut bindings = HashMap::new();
+    document.for_each_node(|node| {
+        let instances = node
+            .instance_geometry
+            .iter()
+            .chain(&node.instance_controller);
+        for (symbol, target) in instances.flat_map(|instance| &instance.materials) {
+            if bindings.contains_key(symbol) {
+                continue;
+            }
+            let Some((id, material)) =
+                fragment(target).and_then(|id| Some((id, document.materials.get(id)?)))
+            else {
+                continue;
+            };
+            let diffuse = fragment(&material.effect)
+                .and_then(|effect| document.effect_colors.get(effect))
+                .copied();
+            let name = material.name.clone().unwrap_or_else(|| id.to_string());
+            bindings.insert(symbol.clone(), Material { name, diffuse });
+        }
+    });
+    bindings
+}
+
+/// Converts a Collada mesh, returns None if it has no vertices. Fails if an input offset
+/// is outside the stride of its primitive. Material symbols
+/// of the primitives are resolved through `materials`, unbound symbols keep their name.
+/// Positions and normals are transformed by `transform`, the bind shape matrix of a
+/// skinned mesh and the transforms of the nodes that place a referenced geometry.
+fn mesh_from_collada(
+    mesh: &Mesh,
+    materials: &HashMap<String, Material>,
+    transform: Option<&na::Matrix4<f64>>,
+    context: &mut LoadContext,
+) -> Result<Option<MeshData>, String> {
+    let Some(vertices) = mesh.vertices.as_ref() else {
+        return Ok(None);
+    };
+
+    let mut mesh_vertices = Vec::new();
+    for input in vertices {
+        if input.semantic == "POSITION"
+            && let Some((positions, stride)) = source_array(mesh, &input.source, 3)
+        {
+            mesh_vertices.reserve(positions.len() / stride);
+            for pos in positions.chunks_exact(stride) {
+                let mut position = na::Point3::new(pos[0], pos[1], pos[2]);
+                if let Some(matrix) = transform {
+                    position = matrix.transform_point(&position);
+                }
+                mesh_vertices.push(context.point(position.x, position.y, position.z));
+            }
+        }
+    }
+    if mesh_vertices.is_empty() {
+        return Ok(None);
+    }
+
+    let mut data = MeshData::new(mesh_vertices, Vec::new());
+
+    // Attributes may be attached to <vertices>, sharing the position index, or to the
+    // triangles with an own offset. Corners that use other values than the first corner
+    // of their vertex get a copy of the vertex. When the attributes are not used, the
+    // corners are their positions.
+    let mut corners = CornerVertices::new(data.vertices.len());
+    let attributes = context.attributes();
+    let mut normals = AttributeValues::new(normal_values, attributes);
+    let mut colors = AttributeValues::new(color_values, attributes);
+    let mut uvs = AttributeValues::new(uv_values, attributes);
+    let shared_normals =
+        find_input(vertices, "NORMAL").and_then(|input| normals.source(mesh, &input.source));
+    let shared_colors =
+        find_input(vertices, "COLOR").and_then(|input| colors.source(mesh, &input.source));
+    let shared_uvs =
+        find_input(vertices, "TEXCOORD").and_then(|input| uvs.source(mesh, &input.source));
+
+    let mut face_materials = Vec::new();
+    let mut has_materials = false;
+
+    for triangles in &mesh.triangles {
+        // only add indices if the input semantic is a vertex
+        let Some(vertex_input) = triangles
+            .inputs
+            .iter()
+            .find(|input| input.semantic == "VERTEX")
+        else {
+            continue;
+        };
+        let offset = vertex_input.offset;
+        let stride = triangles.stride;
+        let prim = &triangles.indices;
+        // All offsets come from the file, with this check the corners below are in bounds
+        if let Some(input) = triangles.inputs.iter().find(|input| input.offset >= stride) {
+            return Err(format!(
+                "Input offset {} is outside the stride {} of the triangles",
+                input.offset, stride
+            ));
+        }
+        let triangle_stride = stride
+            .checked_mul(3)
+            .ok_or_else(|| format!("Invalid stride {} of the triangles", stride))?;
+
+        let normal = own_source(&mut normals, mesh, &triangles.inputs, "NORMAL");
+        let color = own_source(&mut colors, mesh, &triangles.inputs, "COLOR");
+        let uv = own_source(&mut uvs, mesh, &triangles.inputs, "TEXCOORD");
+        let corner_vertices: Vec<u32> = prim
+            .chunks_exact(stride)
+            .map(|corner| {
+                let position = corner[offset];
+                corners.vertex(
+                    position,
+                    [
+                        value_index(shared_normals, normal, position, corner),
+                        value_index(shared_colors, color, position, corner),
+                        value_index(shared_uvs, uv, position, corner),
+                    ],
+                )
+            })
+            .collect();
+
+        // The declared count is not trusted, there can't be more triangles than indices
+        let first_face = data.indices.len();
+        data.indices
+            .reserve(triangles.count.min(prim.len() / triangle_stride));
+        data.indices.extend(
+            corner_vertices
+                .chunks_exact(3)
+                .map(|corner| [corner[0], corner[1], corner[2]]),
+        );
+
+        let material = triangles.material.as_deref().map(|symbol| {
+            let material = materials
+                .get(symbol)
+                .cloned()
+                .unwrap_or_else(|| Material::named(symbol));
+            material_id(&mut data.materials, &material)
+        });
+        has_materials |= material.is_some();
+        face_materials.resize(first_face, None);
+        face_materials.resize(data.indices.len(), material);
+    }
+
+    corners.extend(&mut data.vertices);
+    let has_normals = !normals.values.is_empty();
+    data.normals = has_normals.then(|| corners.attribute(0, &normals.values, Vector::zeros()));
+    if let Some(matrix) = transform {
+        data.normals = data
+            .normals
+            .take()
+            .and_then(|normals| transform_normals(normals, matrix));
+    }
+    let has_colors = !colors.values.is_empty();
+    data.colors = has_colors.then(|| corners.attribute(1, &colors.values, [1.0; 4]));
+    let has_uvs = !uvs.values.is_empty();
+    data.uvs = has_uvs.then(|| corners.attribute(2, &uvs.values, [0.0; 2]));
+    data.face_materials = has_materials.then_some(face_materials);
+    Ok(Some(data))
+}
+
+/// Transforms normals by the transform of a mesh. Normals are transformed by the
+/// inverse transpose, to stay normal under scaling. A singular matrix flattens the mesh,
+/// which then has no normals.
+fn transform_normals(
+    mut normals: Vec<Vector<Real>>,
+    matrix: &na::Matrix4<f64>,
+) -> Option<Vec<Vector<Real>>> {
+    let matrix = matrix.try_inverse()?.transpose();
+    for normal in &mut normals {
+        let vector = na::Vector3::new(normal.x as f64, normal.y as f64, normal.z as f64);
+        let vector = matrix
+            .transform_vector(&vector)
+            .try_normalize(f64::EPSILON)
+            .unwrap_or_default();
+        *normal = Vector::new(vector.x as Real, vector.y as Real, vector.z as Real);
+    }
+    Some(normals)
+}
+
+/// Finds the primitive input with the given semantic. For texture coordinates,
+/// the first set is used.
+fn find_input<'a>(inputs: &'a [Input], semantic: &str) -> Option<&'a Input> {
+    inputs
+        .iter()
+        .filter(|input| input.semantic == semantic)
+        .min_by_key(|input| input.set)
+}
+
+/// The start and the number of the values of a source in `AttributeValues`.
+type ValueRange = (u32, u32);
+
+/// The values of an attribute from all the sources a mesh uses for it, so that each
+/// corner refers to its value by a single index.
+struct AttributeValues<'a, T> {
+    values: Vec<T>,
+    /// The values of each source read so far, None if it has no float array.
+    sources: HashMap<&'a str, Option<ValueRange>>,
+    read: fn(&Mesh, &str) -> Option<Vec<T>>,
+    /// Whether the attribute is used, no source is read if not.
+    used: bool,
+}
+
+impl<'a, T> AttributeValues<'a, T> {
+    fn new(read: fn(&Mesh, &str) -> Option<Vec<T>>, used: bool) -> Self {
+        AttributeValues {
+            values: Vec::new(),
+            sources: HashMap::new(),
+            read,
+            used,
+        }
+    }
+
+    /// Returns where the values of the source are, reading them on first use.
+    fn source(&mut self, mesh: &Mesh, source_uri: &'a str) -> Option<ValueRange> {
+        if !self.used {
+            return None;
+        }
+        if let Some(&range) = self.sources.get(source_uri) {
+            return range;
+        }
+        let range = (self.read)(mesh, source_uri).and_then(|values| {
+            let start = u32::try_from(self.values.len()).ok()?;
+            let count = u32::try_from(values.len()).ok()?;
+            // The indices must stay apart from NO_INDEX
+            start.checked_add(count).filter(|&end| end < NO_INDEX)?;
+            self.values.extend(values);
+            Some((start, count))
+        });
+        self.sources.insert(source_uri, range);
+        range
+    }
+}
+
+/// Finds the input of the triangles for an attribute, and where its values are.
+fn own_source<'a, T>(
+    values: &mut AttributeValues<'a, T>,
+    mesh: &Mesh,
+    inputs: &'a [Input],
+    semantic: &str,
+) -> Option<(&'a Input, ValueRange)> {
+    let input = find_input(inputs, semantic)?;
+    Some((input, values.source(mesh, &input.source)?))
+}
+
+/// Returns the index of the value of a corner among all values of an attribute. The
+/// source of <vertices> comes first, it is indexed by the position.
+fn value_index(
+    shared: Option<ValueRange>,
+    own: Option<(&Input, ValueRange)>,
+    position: u32,
+    corner: &[u32],
+) -> u32 {
+    let (index, (start, count)) = match (shared, own) {
+        (Some(range), _) => (position, range),
+        (None, Some((input, range))) => (corner[input.offset], range),
+        (None, None) => return NO_INDEX,
+    };
+    match index < count {
+        true => start + index,
+        false => NO_INDEX,
+    }
+}
+
+/// Returns the float array of the source referenced by the URI and the stride to
+/// iterate it with, which is at least `min_stride`.
+fn source_array<'a>(
+    mesh: &'a Mesh,
+    source_uri: &str,
+    min_stride: usize,
+) -> Option<(&'a [f64], usize)> {
+    let source_id = fragment(source_uri).unwrap_or(source_uri);
+    let source = mesh
+        .sources
+        .iter()
+        .find(|source| source.id.as_deref() == Some(source_id))?;
+    Some((source.values.as_deref()?, source.stride.max(min_stride)))
+}
+
+fn normal_values(mesh: &Mesh, source_uri: &str) -> Option<Vec<Vector<Real>>> {
+    let (values, stride) = source_array(mesh, source_uri, 3)?;
+    Some(
+        values
+            .chunks_exact(stride)
+            .map(|n| Vector::new(n[0] as Real, n[1] as Real, n[2] as Real))
+            .collect(),
+    )
+}
+
+fn color_values(mesh: &Mesh, source_uri: &str) -> Option<Vec<[f32; 4]>> {
+    let (values, stride) = source_array(mesh, source_uri, 3)?;
+    Some(
+        values
+            .chunks_exact(stride)
+            .map(|c| {
+                let alpha = c.get(3).copied().unwrap_or(1.0);
+                [c[0] as f32, c[1] as f32, c[2] as f32, alpha as f32]
+            })
+            .collect(),
+    )
+}
+
+fn uv_values(mesh: &Mesh, source_uri: &str) -> Option<Vec<[f32; 2]>> {
+    let (values, stride) = source_array(mesh, source_uri, 2)?;
+    Some(
+        values
+            .chunks_exact(stride)
+            .map(|t| [t[0] as f32, t[1] as f32])
+            .collect(),
+    )
+}
@@ -0,0 +1,73 @@
+use crate::limits::LimitExceeded;
+use crate::progress::CANCELLED;
+use std::fmt;
+
+/// The error of a load through `TrimeshLoader` or a `MeshFormatLoader`. Loads over one
+/// of the `Limits` keep the `LimitExceeded` and cancelled loads are `Cancelled`, other
+/// failures are described by a message.
+/// The `load_trimesh` functions return the message of this error. See `Limits` for
+/// an example.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LoadError {
+    /// The input went over one of the `Limits`.
+    LimitExceeded(LimitExceeded),
+    /// The load was cancelled through its `CancellationToken`. Displayed as `CANCELLED`.
+    Cancelled,
+    /// Any other failure, like a missing file or malformed content.
+    Message(String),
+}
+
+impl LoadError {
+    /// Returns the exceeded limit, or None if the load failed for another reason.
+    pub fn limit_exceeded(&self) -> Option<&LimitExceeded> {
+        match self {
+            LoadError::LimitExceeded(exceeded) => Some(exceeded),
+            _ => None,
+        }
+    }
+
+    /// Prefixes the message with what was being loaded. Exceeded limits and
+    /// cancellations are kept as they are, so that they can still be matched.
+    pub(crate) fn context(self, context: impl fmt::Display) -> LoadError {
+        match self {
+            LoadError::Message(message) => format!("{}: {}", context, message).into(),
+            error => error,
+        }
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::LimitExceeded(exceeded) => exceeded.fmt(f),
+            LoadError::Cancelled => f.write_str(CANCELLED),
+            LoadError::Message(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+impl From<LimitExceeded> for LoadError {
+    fn from(exceeded: LimitExceeded) -> Self {
+        LoadError::LimitExceeded(exceeded)
+    }
+}
+
+impl From<String> for LoadError {
+    fn from(message: String) -> Self {
+        LoadError::Message(message)
+    }
+}
+
+impl From<&str> for LoadError {
+    fn from(message: &str) -> Self {
+        LoadError::Message(message.to_string())
+    }
+}
+
+impl From<LoadError> for String {
+    fn from(error: LoadError) -> Self {
+        error.to_string()
+    }
+}
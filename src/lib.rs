@@ -1,33 +1,103 @@
-use dae_parser::{ArrayElement, Document, GeometryElement, LibraryElement, Primitive, Semantic};
-use ply_rs_bw::parser::Parser;
-use ply_rs_bw::ply::{DefaultElement, Property};
-use std::fs::File;
-use std::io::BufReader;
+// Casts to `Real` are needed with the f64 features, but are no-ops with the f32 ones
+#![allow(clippy::unnecessary_cast)]
+
 use std::path::Path;
-use stl_io::read_stl;
-use tobj;
+
+mod amf;
+#[cfg(feature = "async")]
+mod async_loader;
+mod cache;
+mod collada;
+mod components;
+mod dae;
+mod error;
+mod limits;
+mod loader;
+mod mesh_data;
+#[cfg(feature = "mjcf")]
+mod mjcf;
+mod name_filter;
+mod obj;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod ply;
+mod points;
+mod progress;
+mod recenter;
+mod registry;
+mod resolver;
+#[cfg(any(feature = "urdf", feature = "sdf", feature = "mjcf"))]
+mod robot;
+#[cfg(feature = "sdf")]
+mod sdf;
+mod stl;
+#[cfg(feature = "urdf")]
+mod urdf;
+mod xml;
+
+pub use components::{Connectivity, MeshComponent};
+pub use error::LoadError;
+pub use limits::{DEFAULT_MAX_XML_DEPTH, Limit, LimitExceeded, Limits};
+pub use loader::{
+    Format, LoadContext, LoadOptions, SubMeshes, Triangulation, TrimeshLoader, Validation,
+};
+pub use mesh_data::{Material, MeshData};
+#[cfg(feature = "mjcf")]
+pub use mjcf::MjcfLoader;
+pub use name_filter::NameFilter;
+pub use ply::PlySchema;
+pub use progress::{CANCELLED, CancellationToken, Progress, ProgressCallback};
+use recenter::CoordinateFrame;
+pub use recenter::Recenter;
+pub use registry::{FormatRegistry, MeshFormatLoader, MeshReader, SNIFF_LENGTH};
+pub use resolver::UriResolver;
+#[cfg(feature = "sdf")]
+pub use sdf::SdfLoader;
+#[cfg(feature = "urdf")]
+pub use urdf::UrdfLoader;
 
 #[cfg(feature = "parry13")]
 use {
-    parry13::math::Point,
-    parry13::na::Point3,
+    parry13::math::{Isometry, Point, Real, Vector},
+    parry13::na,
     parry13::shape::{TriMesh, TriMeshFlags},
 };
 
 #[cfg(feature = "parry17")]
 use {
-    parry17::math::Point,
-    parry17::na::Point3,
+    parry17::math::{Isometry, Point, Real, Vector},
+    parry17::na,
     parry17::shape::{TriMesh, TriMeshFlags},
 };
 
 #[cfg(feature = "parry_19")]
 use {
-    parry_19::math::Point,
-    parry_19::na::Point3,
+    parry_19::math::{Isometry, Point, Real, Vector},
+    parry_19::na,
     parry_19::shape::{TriMesh, TriMeshFlags},
 };
 
+#[cfg(feature = "parry13_f64")]
+use {
+    parry13_f64::math::{Isometry, Point, Real, Vector},
+    parry13_f64::na,
+    parry13_f64::shape::{TriMesh, TriMeshFlags},
+};
+
+#[cfg(feature = "parry17_f64")]
+use {
+    parry17_f64::math::{Isometry, Point, Real, Vector},
+    parry17_f64::na,
+    parry17_f64::shape::{TriMesh, TriMeshFlags},
+};
+
+#[cfg(feature = "parry_19_f64")]
+use {
+    parry_19_f64::math::{Isometry, Point, Real, Vector},
+    parry_19_f64::na,
+    parry_19_f64::shape::{TriMesh, TriMeshFlags},
+};
+
 /// Loads a 3D triangular mesh (TriMesh) from a given file, applies optional scaling
 /// and returns the constructed mesh. This function supports multiple formats.
 ///
@@ -35,8 +105,8 @@ use {
 ///
 /// * `file_path` - A string slice that represents the path to the input file containing the 3D mesh.
 /// * `scale` - A floating-point value used to apply scaling to the vertex data.
-///             If `scale` is 1.0, no scaling is applied. For ply files scaling is more part of
-///             the format, as they are unit-agnostic and may come in meters, millimeters or inches.
+///   If `scale` is 1.0, no scaling is applied. For ply files scaling is more part of
+///   the format, as they are unit-agnostic and may come in meters, millimeters or inches.
 ///
 /// This function applies flags FIX_INTERNAL_EDGES and MERGE_DUPLICATE_VERTICES. For precise control
 /// over flags, use `load_trimesh_with_flags`
@@ -53,6 +123,8 @@ use {
 /// * `.ply` - Polygon files that can represent geometric 3D data.
 /// * `.obj` - Wavefront OBJ files.
 /// * `.dae` - Collada files
+/// * `.amf` - Additive Manufacturing Format files, plain XML or zip-compressed.
+///   Coordinates are converted to millimeters, the default AMF unit.
 ///
 /// # Errors
 ///
@@ -77,16 +149,34 @@ use {
 ///     }
 /// }
 /// ```
-pub fn load_trimesh(file_path: &str, scale: f32) -> Result<TriMesh, String> {
-    #[cfg(feature = "parry13")]
-    return load_trimesh_with_flags(file_path, scale, TriMeshFlags::MERGE_DUPLICATE_VERTICES);
+pub fn load_trimesh(file_path: &str, scale: Real) -> Result<TriMesh, String> {
+    compatible_loader()
+        .scale(scale)
+        .load(file_path)
+        .map_err(String::from)
+}
+
+/// Loader with the behavior `load_trimesh` always had: polygons only contribute their
+/// first triangle, and faces referencing missing vertices are errors, while degenerate
+/// faces are kept. `TrimeshLoader` defaults to fans and drops invalid faces instead.
+fn compatible_loader() -> TrimeshLoader {
+    TrimeshLoader::new()
+        .triangulation(Triangulation::FirstTriangle)
+        .validation(Validation::Indices)
+}
 
-    #[cfg(any(feature = "parry_19", feature = "parry17"))]
-    return load_trimesh_with_flags(
-        file_path,
-        scale,
-        TriMeshFlags::FIX_INTERNAL_EDGES | TriMeshFlags::MERGE_DUPLICATE_VERTICES,
-    );
+/// Flags applied by `load_trimesh` and the other loaders that do not take flags.
+pub(crate) fn default_flags() -> TriMeshFlags {
+    #[cfg(any(feature = "parry13", feature = "parry13_f64"))]
+    return TriMeshFlags::MERGE_DUPLICATE_VERTICES;
+
+    #[cfg(any(
+        feature = "parry17",
+        feature = "parry_19",
+        feature = "parry17_f64",
+        feature = "parry_19_f64"
+    ))]
+    return TriMeshFlags::FIX_INTERNAL_EDGES | TriMeshFlags::MERGE_DUPLICATE_VERTICES;
 }
 
 /// Loads a 3D triangular mesh (TriMesh) from a given file. Allows specifying flags
@@ -94,387 +184,317 @@ pub fn load_trimesh(file_path: &str, scale: f32) -> Result<TriMesh, String> {
 /// See `load_trimesh,` for example, and a more detailed description.
 pub fn load_trimesh_with_flags(
     file_path: &str,
-    scale: f32,
+    scale: Real,
     flags: TriMeshFlags,
 ) -> Result<TriMesh, String> {
-    let path = Path::new(file_path);
-    let mut vertices;
-    let indices;
-
-    // Determine the file extension and call the appropriate loader
-    (vertices, indices) = match path
-        .extension()
-        .and_then(|ext| ext.to_str())
-        .map(|ext| ext.to_lowercase())
-        .as_deref() // Convert Option<String> to Option<&str> for matching
-    {
-        Some("stl") => load_trimesh_from_stl(file_path)?,
-        Some("ply") => load_trimesh_from_ply(file_path)?,
-        Some("obj") => load_trimesh_from_obj(file_path)?,
-        Some("dae") => load_trimesh_from_dae(file_path)?,
-        _ => {
-            return Err(format!(
-                "Unsupported file extension for '{}', only .stl, .ply, and .obj are supported.",
-                file_path
-            ));
-        }
-    };
-
-    // Apply scaling in place to all vertices
-    if (scale - 1.0).abs() > f32::EPSILON {
-        for vertex in &mut vertices {
-            *vertex *= scale; // Scale the vertex in place
-        }
-    }
-
-    // Create and return the TriMesh
-    #[cfg(feature = "parry_19")]
-    {
-        return TriMesh::with_flags(vertices, indices, flags).map_err(|e| e.to_string());
-    }
-
-    #[cfg(any(feature = "parry13", feature = "parry17"))]
-    {
-        return Ok(TriMesh::with_flags(vertices, indices, flags));
-    }
+    compatible_loader()
+        .scale(scale)
+        .flags(flags)
+        .load(file_path)
+        .map_err(String::from)
 }
 
-/// Function to load a TriMesh from a PLY file
-fn load_trimesh_from_ply(ply_file_path: &str) -> Result<(Vec<Point<f32>>, Vec<[u32; 3]>), String> {
-    // Open the file
-    let file = File::open(ply_file_path)
-        .map_err(|err| format!("Could not open .ply file '{}': {}", ply_file_path, err))?;
-    let mut reader = BufReader::new(file);
-
-    // Create a PLY parser and parse the header
-    let parser = Parser::<DefaultElement>::new();
-    let ply = parser
-        .read_ply(&mut reader)
-        .map_err(|err| format!("Could not parse .ply file '{}': {}", ply_file_path, err))?;
-
-    // Initialize containers for vertices and indices
-    let mut vertices = Vec::new();
-    let mut indices = Vec::new();
-
-    // Extract vertices
-    if let Some(vertices_elem) = ply.payload.get("vertex") {
-        for vertex in vertices_elem {
-            let x = vertex
-                .get("x")
-                .ok_or_else(|| "Missing 'x' coordinate in vertex".to_string())
-                .and_then(|prop| match prop {
-                    Property::Float(val) => Ok(*val),
-                    Property::Double(val) => Ok(*val as f32),
-                    _ => Err("Unexpected type for vertex 'x' coordinate".to_string()),
-                })?;
-
-            let y = vertex
-                .get("y")
-                .ok_or_else(|| "Missing 'y' coordinate in vertex".to_string())
-                .and_then(|prop| match prop {
-                    Property::Float(val) => Ok(*val),
-                    Property::Double(val) => Ok(*val as f32),
-                    _ => Err("Unexpected type for vertex 'y' coordinate".to_string()),
-                })?;
-
-            let z = vertex
-                .get("z")
-                .ok_or_else(|| "Missing 'z' coordinate in vertex".to_string())
-                .and_then(|prop| match prop {
-                    Property::Float(val) => Ok(*val),
-                    Property::Double(val) => Ok(*val as f32),
-                    _ => Err("Unexpected type for vertex 'z' coordinate".to_string()),
-                })?;
-
-            vertices.push(Point::new(x, y, z));
-        }
-    } else {
-        return Err("No 'vertex' payload found in the .ply file".to_string());
-    }
-
-    // Extract faces (indices)
-    if let Some(faces_elem) = ply.payload.get("face") {
-        for (i, face) in faces_elem.iter().enumerate() {
-            match face.get("vertex_indices") {
-                Some(Property::ListUInt(indices_list)) => {
-                    indices.push(extract_indices(indices_list, i)?);
-                }
-                Some(Property::ListInt(indices_list)) => {
-                    indices.push(extract_indices(indices_list, i)?);
-                }
-                Some(Property::ListUShort(indices_list)) => {
-                    indices.push(extract_indices(indices_list, i)?);
-                }
-                Some(Property::ListShort(indices_list)) => {
-                    indices.push(extract_indices(indices_list, i)?);
-                }
-
-                Some(_) => {
-                    return Err(format!(
-                        "Unexpected property type for 'vertex_indices' in face {}",
-                        i
-                    ));
-                }
-                None => {
-                    return Err(format!("Missing 'vertex_indices' property for face {}", i));
-                }
-            }
-        }
-    } else {
-        return Err("No 'face' payload found in the PLY file".to_string());
-    }
-
-    Ok((vertices, indices))
-}
-
-// Helper function to handle index extraction
-fn extract_indices<T>(indices_list: &[T], i: usize) -> Result<[u32; 3], String>
-where
-    T: TryInto<u32> + Copy,
-    <T as TryInto<u32>>::Error: std::fmt::Debug,
-{
-    if indices_list.len() < 3 {
-        return Err(format!("Insufficient indices for a triangle in face {}", i));
-    }
-
-    let a = indices_list[0]
-        .try_into()
-        .map_err(|_| format!("Failed to convert index 0 in face {} to u32", i))?;
-    let b = indices_list[1]
-        .try_into()
-        .map_err(|_| format!("Failed to convert index 1 in face {} to u32", i))?;
-    let c = indices_list[2]
-        .try_into()
-        .map_err(|_| format!("Failed to convert index 2 in face {} to u32", i))?;
-
-    Ok([a, b, c])
-}
-
-/// Function to load a TriMesh from an STL file
-fn load_trimesh_from_stl(stl_file_path: &str) -> Result<(Vec<Point<f32>>, Vec<[u32; 3]>), String> {
-    // Open the STL file
-    let file = File::open(stl_file_path)
-        .map_err(|err| format!("Could not open STL file {}: {}", stl_file_path, err))?;
-    let mut reader = BufReader::new(file);
-
-    // Read the STL file into IndexedMesh
-    let stl = read_stl(&mut reader)
-        .map_err(|err| format!("Could not parse STL file {}: {}", stl_file_path, err))?;
-
-    // Extract vertices and convert them to Point3<f32>
-    let vertices: Vec<Point<f32>> = stl
-        .vertices
+/// Loads a mesh from a given file and splits it into one `TriMesh` per material, so that
+/// parts like glass and frame can be handled separately. Applies scaling and the same
+/// flags as `load_trimesh`.
+///
+/// Materials come from `usemtl` in `.obj` files (with the diffuse color from the .mtl
+/// library), from the material bound to each primitive in `.dae` files, and from the
+/// volume materials in `.amf` files. Faces without material are returned last, with `None`
+/// as material. Formats without materials (`.stl`, `.ply`) return a single mesh.
+///
+/// # Example
+///
+/// ```rust
+/// use rs_read_trimesh::load_trimeshes_by_material;
+///
+/// match load_trimeshes_by_material("example.obj", 1.0) {
+///     Ok(parts) => {
+///         for (material, mesh) in parts {
+///             let name = material.map_or("none".to_string(), |material| material.name);
+///             println!("Material {} has {} triangles.", name, mesh.indices().len());
+///         }
+///     }
+///     Err(e) => eprintln!("Failed to load mesh: {}", e),
+/// }
+/// ```
+pub fn load_trimeshes_by_material(
+    file_path: &str,
+    scale: Real,
+) -> Result<Vec<(Option<Material>, TriMesh)>, String> {
+    let mesh = TrimeshLoader::new()
+        .scale(scale)
+        .positions_only()
+        .load_mesh_data(file_path)?;
+
+    mesh.split_by_material()
         .into_iter()
-        .map(|vertex| Point::new(vertex[0], vertex[1], vertex[2]))
-        .collect();
+        .map(|(material, part)| Ok((material, part.into_trimesh(default_flags())?)))
+        .collect()
+}
 
-    // Convert face indices from `usize` to `u32` and handle any potential issues
-    let indices: Vec<[u32; 3]> = stl
-        .faces
+/// Loads a mesh from a given file and splits it into its connected components, for files
+/// that hold several disjoint bodies exported together. Each component is returned as its
+/// own `TriMesh` with its bounding box and volume, so the caller can decide which ones become
+/// colliders. Applies scaling and the same flags as `load_trimesh`.
+///
+/// Vertices at the same position are treated as one, whether or not the file shares them.
+/// With `Connectivity::SharedEdge`, bodies that only touch in a corner are kept apart.
+///
+/// # Example
+///
+/// ```rust
+/// use rs_read_trimesh::{load_trimesh_components, Connectivity};
+///
+/// match load_trimesh_components("example.stl", 0.001, Connectivity::SharedEdge) {
+///     Ok(components) => {
+///         for component in components.iter().filter(|component| component.volume > 1e-6) {
+///             println!("Body between {} and {}", component.mins, component.maxs);
+///         }
+///     }
+///     Err(e) => eprintln!("Failed to load mesh: {}", e),
+/// }
+/// ```
+pub fn load_trimesh_components(
+    file_path: &str,
+    scale: Real,
+    connectivity: Connectivity,
+) -> Result<Vec<MeshComponent>, String> {
+    let mesh = TrimeshLoader::new()
+        .scale(scale)
+        .positions_only()
+        .load_mesh_data(file_path)?;
+
+    mesh.split_components(connectivity)
         .into_iter()
-        .map(|face| {
-            let mut converted_face = [0u32; 3];
-            for (i, &vertex_index) in face.vertices.iter().enumerate() {
-                converted_face[i] = vertex_index.try_into().map_err(|_| {
-                    format!(
-                        "Could not convert vertex index {} in face {:?} to u32",
-                        vertex_index, face.vertices
-                    )
-                })?;
-            }
-            Ok(converted_face)
-        })
-        .collect::<Result<Vec<[u32; 3]>, String>>()?; // Collect and propagate errors
-
-    Ok((vertices, indices))
+        .map(|component| MeshComponent::from_mesh_data(component, default_flags()))
+        .collect()
 }
 
-/// Function to load a TriMesh from an OBJ file
-fn load_trimesh_from_obj(obj_file_path: &str) -> Result<(Vec<Point<f32>>, Vec<[u32; 3]>), String> {
-    // Load the OBJ file using the `tobj` library
-    let (models, _) = tobj::load_obj(obj_file_path, &tobj::LoadOptions::default())
-        .map_err(|e| format!("Failed to load .obj file '{}': {}", obj_file_path, e))?;
-
-    // Collect vertices and indices
-    let mut vertices = Vec::new();
-    let mut indices = Vec::new();
-
-    for model in models {
-        let mesh = model.mesh;
-
-        // Extract vertices
-        vertices.extend(
-            mesh.positions
-                .chunks_exact(3)
-                .map(|chunk| Point::new(chunk[0], chunk[1], chunk[2])),
-        );
-
-        // Extract indices (assume triangulated mesh)
-        indices.extend(
-            mesh.indices
-                .chunks_exact(3)
-                .map(|chunk| [chunk[0], chunk[1], chunk[2]]),
-        );
-    }
-
-    Ok((vertices, indices))
+/// Loads a mesh from a given file together with the vertex normals, colors, texture
+/// coordinates and face materials the file provides. Supports the same formats as
+/// `load_trimesh`, no scaling or flags are applied.
+///
+/// Attributes are only filled in if the format and the file carry them:
+/// * `.ply` - Normals (`nx`, `ny`, `nz`), colors (`red`, `green`, `blue`, `alpha`) and
+///   texture coordinates (`u`, `v` or `s`, `t`) of the 'vertex' element.
+/// * `.obj` - Normals, texture coordinates, vertex colors and the material of each object.
+/// * `.dae` - `NORMAL`, `COLOR` and `TEXCOORD` inputs, and the material of each primitive.
+/// * `.amf` - Vertex normals and colors, and the material of each volume.
+/// * `.stl` - Only vertices and indices.
+///
+/// # Example
+///
+/// ```rust
+/// use rs_read_trimesh::load_mesh_data;
+///
+/// match load_mesh_data("example.ply") {
+///     Ok(mesh) => {
+///         if let Some(colors) = &mesh.colors {
+///             println!("The first vertex has color {:?}", colors[0]);
+///         }
+///     }
+///     Err(e) => eprintln!("Failed to load mesh: {}", e),
+/// }
+/// ```
+pub fn load_mesh_data(file_path: &str) -> Result<MeshData, String> {
+    Ok(load_mesh_data_recentered(file_path, Recenter::None)?.0)
 }
 
-fn load_trimesh_from_dae(dae_file_path: &str) -> Result<(Vec<Point3<f32>>, Vec<[u32; 3]>), String> {
-    // Open the file
-    let file = File::open(Path::new(dae_file_path))
-        .map_err(|e| format!("Failed to open .dae file: {}", e))?;
-    let reader = BufReader::new(file);
-
-    // Parse the Collada document
-    let document =
-        Document::from_reader(reader).map_err(|e| format!("Failed to parse .dae file {:?}", e))?;
-
-    let mut meshes = Vec::new();
-
-    // Iterate through geometries in the document
-    for geometry in document.library.iter() {
-        if let LibraryElement::Geometries(geometry) = geometry {
-            for item in geometry.items.iter() {
-                if let GeometryElement::Mesh(mesh) = &item.element {
-                    let mut mesh_vertices = Vec::new();
-                    let mut mesh_indices = Vec::new();
-
-                    if let Some(vertices) = &mesh.vertices {
-                        for input in vertices.inputs.iter() {
-                            if input.semantic == Semantic::Position {
-                                let source_uri = input.source.to_string();
-                                let source_id =
-                                    source_uri.strip_prefix('#').unwrap_or(&*source_uri);
-
-                                for source in mesh.sources.iter() {
-                                    if let Some(id) = &source.id {
-                                        if id == &source_id {
-                                            if let Some(positions) = &source.array {
-                                                if let ArrayElement::Float(positions) = positions {
-                                                    mesh_vertices.reserve(positions.len() / 3);
-                                                    for pos in positions.chunks_exact(3) {
-                                                        mesh_vertices.push(Point3::new(
-                                                            pos[0], pos[1], pos[2],
-                                                        ));
-                                                    }
-                                                }
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        }
-
-                        for primitive in mesh.elements.iter() {
-                            if let Primitive::Triangles(triangles) = primitive {
-                                // only add indices if the input semantic is a vertex
-                                if let Some(vertex_input) = triangles.inputs.inputs.iter().
-                                    find(|input| { input.semantic == Semantic::Vertex }) {
-                                    let offset = vertex_input.offset as usize;
-                                    let num_semantics = triangles.inputs.inputs.len();
-                                    mesh_indices.reserve(triangles.count * 3);
-
-                                    if let Some(prim) = &triangles.data.prim {
-                                        for pos in prim.chunks_exact(num_semantics * 3) {
-                                            mesh_indices.push([
-                                                pos[offset],
-                                                pos[num_semantics + offset],
-                                                pos[(2 * num_semantics) + offset]
-                                            ])
-                                        }
-                                    }
-                                }
-                            }
-                        }
-
-                        if !mesh_vertices.is_empty() {
-                            meshes.push((mesh_vertices, mesh_indices));
-                        }
-                    }
-                }
-            }
-        }
-    }
-
-    if meshes.is_empty() {
-        Err("The .dae file contains no mesh".to_string())
-    } else {
-        Ok(merge_meshes(meshes))
-    }
+/// Loads a 3D triangular mesh (TriMesh) from a given file, moving it close to zero before
+/// the coordinates are narrowed to the precision of the mesh. This keeps meshes in survey
+/// or site coordinates (like 500000, 4000000) precise, which is lost if they are converted
+/// to f32 as they are. Applies scaling and the same flags as `load_trimesh`.
+///
+/// Returns the mesh and the offset applied, in the scaled units. Adding the offset to the
+/// vertices gives the coordinates from the file, so the mesh can be placed back in world
+/// coordinates by a translation of this offset.
+///
+/// With `Recenter::BoundingBoxCenter`, the center of the bounding box is computed in f64
+/// while parsing. `.ply`, `.obj`, `.dae`, `.xyz`, `.pts` and `.pcd` files are read in
+/// f64 for this. For `.amf` constellations, the origin is subtracted after the instances
+/// are placed, so it does not improve the precision in that case.
+///
+/// # Example
+///
+/// ```rust
+/// use rs_read_trimesh::{load_trimesh_recentered, Recenter};
+///
+/// match load_trimesh_recentered("survey.ply", 1.0, Recenter::BoundingBoxCenter) {
+///     Ok((mesh, offset)) => {
+///         println!("{} vertices around {:?}", mesh.vertices().len(), offset);
+///     }
+///     Err(e) => eprintln!("Failed to load mesh: {}", e),
+/// }
+/// ```
+pub fn load_trimesh_recentered(
+    file_path: &str,
+    scale: Real,
+    recenter: Recenter,
+) -> Result<(TriMesh, [f64; 3]), String> {
+    TrimeshLoader::new()
+        .scale(scale)
+        .recenter(recenter)
+        .load_recentered(file_path)
+        .map_err(String::from)
 }
 
-fn merge_meshes(
-    meshes: Vec<(Vec<Point3<f32>>, Vec<[u32; 3]>)>,
-) -> (Vec<Point3<f32>>, Vec<[u32; 3]>) {
-    if meshes.len() == 1 {
-        return meshes.into_iter().next().unwrap();
-    }
-
-    let mut merged_vertices = Vec::new();
-    let mut merged_indices = Vec::new();
-    let mut vertex_offset = 0u32;
-
-    for (vertices, indices) in meshes {
-        // Add vertices
-        merged_vertices.extend(vertices.iter().cloned());
+/// Loads the mesh data from a given file like `load_mesh_data`, subtracting the origin
+/// as described for `load_trimesh_recentered`. Returns the mesh data and the offset
+/// applied, no scaling is done.
+pub fn load_mesh_data_recentered(
+    file_path: &str,
+    recenter: Recenter,
+) -> Result<(MeshData, [f64; 3]), String> {
+    TrimeshLoader::new()
+        .recenter(recenter)
+        .load_mesh_data_recentered(file_path)
+        .map_err(String::from)
+}
 
-        // Adjust indices and add them
-        merged_indices.extend(
-            indices
-                .into_iter()
-                .map(|[i0, i1, i2]| [i0 + vertex_offset, i1 + vertex_offset, i2 + vertex_offset]),
-        );
+/// Loads a 3D triangular mesh (TriMesh) from a given file without blocking the async
+/// runtime, with the `async` feature. The file is read with tokio's async I/O and parsed
+/// on the blocking thread pool, so this must be called within a tokio runtime. Supports
+/// the same formats as `load_trimesh`. `TrimeshLoader::load_reader_async` loads from
+/// other `AsyncRead` sources, like network streams.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use rs_read_trimesh::{load_trimesh_async, LoadOptions};
+///
+/// async fn load_asset() {
+///     let options = LoadOptions {
+///         scale: 0.001,
+///         ..LoadOptions::default()
+///     };
+///     match load_trimesh_async("example.stl", options).await {
+///         Ok(mesh) => println!("Loaded mesh with {} vertices.", mesh.vertices().len()),
+///         Err(e) => eprintln!("Failed to load mesh: {}", e),
+///     }
+/// }
+/// ```
+#[cfg(feature = "async")]
+pub async fn load_trimesh_async(file_path: &str, options: LoadOptions) -> Result<TriMesh, String> {
+    TrimeshLoader::with_options(options)
+        .load_async(file_path)
+        .await
+        .map_err(String::from)
+}
 
-        // Update vertex offset for next mesh
-        vertex_offset += vertices.len() as u32;
-    }
+/// Loads the 3D triangular meshes (TriMesh) of many files in parallel, with the `rayon`
+/// feature, and returns the results in the order of the paths. A mesh referenced by
+/// several paths, or stored in several files with the same content, is parsed once and
+/// shared through its `Arc`. This suits robot models, whose links often reuse meshes.
+///
+/// Files that refer to other files (.obj files with their .mtl libraries) are only
+/// shared with copies in the same directory.
+///
+/// # Example
+///
+/// ```rust
+/// use rs_read_trimesh::{load_trimeshes_parallel, LoadOptions};
+///
+/// let links = ["base.stl", "arm.stl", "wrist.dae", "arm.stl"];
+/// let results = load_trimeshes_parallel(&links, LoadOptions::default());
+/// for (file_path, result) in links.iter().zip(results) {
+///     match result {
+///         Ok(mesh) => println!("{} has {} vertices.", file_path, mesh.vertices().len()),
+///         Err(e) => eprintln!("Failed to load mesh: {}", e),
+///     }
+/// }
+/// ```
+#[cfg(feature = "rayon")]
+pub fn load_trimeshes_parallel<S: AsRef<str> + Sync>(
+    file_paths: &[S],
+    options: LoadOptions,
+) -> Vec<Result<std::sync::Arc<TriMesh>, String>> {
+    TrimeshLoader::with_options(options)
+        .load_parallel(file_paths)
+        .into_iter()
+        .map(|result| result.map_err(String::from))
+        .collect()
+}
 
-    (merged_vertices, merged_indices)
+/// Loads a point cloud from a given file and applies optional scaling. This is useful
+/// for scanner output that has no faces, for instance to build a convex hull.
+///
+/// # Arguments
+///
+/// * `file_path` - A string slice that represents the path to the input file.
+/// * `scale` - A floating-point value used to apply scaling to the points, see `load_trimesh`.
+///
+/// # Supported Formats
+///
+/// This function determines the file type based on the file extension:
+/// * `.ply` - Only the 'vertex' element is read, the 'face' element is not required.
+/// * `.xyz`, `.pts` - ASCII files with one point per line, extra columns are ignored.
+/// * `.pcd` - Point Cloud Library files with ascii or binary data. Points with
+///   non-finite coordinates are skipped.
+/// * `.stl`, `.obj`, `.dae`, `.amf` - The vertices of the mesh are returned.
+///
+/// # Example
+///
+/// ```rust
+/// use rs_read_trimesh::load_points;
+///
+/// match load_points("scan.pcd", 0.001) {
+///     Ok(points) => println!("Loaded {} points.", points.len()),
+///     Err(e) => eprintln!("Failed to load points: {}", e),
+/// }
+/// ```
+pub fn load_points(file_path: &str, scale: Real) -> Result<Vec<Point<Real>>, String> {
+    Ok(load_points_recentered(file_path, scale, Recenter::None)?.0)
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Loads a point cloud like `load_points`, subtracting the origin before the coordinates
+/// are narrowed to the precision of the points. Returns the points and the offset applied,
+/// in the scaled units. See `load_trimesh_recentered` for details.
+pub fn load_points_recentered(
+    file_path: &str,
+    scale: Real,
+    recenter: Recenter,
+) -> Result<(Vec<Point<Real>>, [f64; 3]), String> {
+    let file_path = &*resolver::resolve_path(file_path, None)?;
+    let path = Path::new(file_path);
+    let mut frame = CoordinateFrame::new(recenter);
 
-    #[test]
-    fn test_merge_meshes() {
-        fn point(x: f32, y: f32, z: f32) -> Point3<f32> {
-            Point3::new(x, y, z)
+    let mut points = match path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase())
+        .as_deref()
+    {
+        Some("ply") => {
+            // The loader applies the origin itself, only keep the offset
+            let (points, offset) = TrimeshLoader::new()
+                .recenter(recenter)
+                .load_ply_points_recentered(file_path)?;
+            frame = CoordinateFrame::new(Recenter::Origin(offset));
+            points
         }
+        Some("xyz") | Some("pts") => points::load_points_from_xyz(file_path, &mut frame)?,
+        Some("pcd") => points::load_points_from_pcd(file_path, &mut frame)?,
+        Some("stl") | Some("obj") | Some("dae") | Some("amf") => {
+            // The mesh loaders apply the origin themselves, only keep the offset
+            let (mesh, offset) = load_mesh_data_recentered(file_path, recenter)?;
+            frame = CoordinateFrame::new(Recenter::Origin(offset));
+            mesh.vertices
+        }
+        _ => {
+            return Err(format!(
+                "Unsupported file extension for '{}', only .ply, .xyz, .pts, .pcd, .stl, .obj, .dae and .amf are supported.",
+                file_path
+            ));
+        }
+    };
 
-        let mesh1 = (
-            vec![
-                point(0.0, 0.0, 0.0),
-                point(1.0, 0.0, 0.0),
-                point(0.0, 1.0, 0.0),
-            ],
-            vec![[0, 1, 2]],
-        );
-
-        let mesh2 = (
-            vec![
-                point(1.0, 1.0, 0.0),
-                point(2.0, 1.0, 0.0),
-                point(1.0, 2.0, 0.0),
-            ],
-            vec![[0, 1, 2]],
-        );
-
-        let (merged_vertices, merged_indices) = merge_meshes(vec![mesh1, mesh2]);
-
-        let expected_vertices = vec![
-            point(0.0, 0.0, 0.0),
-            point(1.0, 0.0, 0.0),
-            point(0.0, 1.0, 0.0),
-            point(1.0, 1.0, 0.0),
-            point(2.0, 1.0, 0.0),
-            point(1.0, 2.0, 0.0),
-        ];
-
-        let expected_indices = vec![[0, 1, 2], [3, 4, 5]];
-
-        assert_eq!(merged_vertices, expected_vertices);
-        assert_eq!(merged_indices, expected_indices);
+    let offset = frame.finish(&mut points);
+    if (scale - 1.0).abs() > Real::EPSILON {
+        for point in &mut points {
+            *point *= scale;
+        }
     }
+
+    Ok((points, offset.map(|value| value * scale as f64)))
 }
@@ -0,0 +1,339 @@
+use crate::error::LoadError;
+use std::fmt;
+use std::io::{Read, Take};
+
+/// Upper bounds on the input, for loading meshes from untrusted sources. Counts declared
+/// in file headers are checked before memory is allocated for them. All limits are
+/// off by default, except the XML depth, see `DEFAULT_MAX_XML_DEPTH`.
+///
+/// # Example
+///
+/// ```rust
+/// use rs_read_trimesh::{Limits, LoadError, TrimeshLoader};
+///
+/// let limits = Limits {
+///     max_file_size: Some(64 << 20),
+///     max_vertices: Some(1_000_000),
+///     max_faces: Some(2_000_000),
+///     ..Limits::default()
+/// };
+///
+/// match TrimeshLoader::new().limits(limits).load("upload.stl") {
+///     Ok(mesh) => println!("Loaded mesh with {} vertices.", mesh.vertices().len()),
+///     Err(LoadError::LimitExceeded(exceeded)) => {
+///         eprintln!("Rejected, {} is too large", exceeded.limit)
+///     }
+///     Err(e) => eprintln!("Failed to load mesh: {}", e),
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Limits {
+    /// Maximal size of the file, or of the data read from a reader, in bytes.
+    pub max_file_size: Option<u64>,
+    /// Maximal number of vertices, as declared or as read.
+    pub max_vertices: Option<u64>,
+    /// Maximal number of faces, as declared or as read. Polygons count once.
+    pub max_faces: Option<u64>,
+    /// Maximal nesting depth of XML elements in .dae, .amf and robot description files,
+    /// `DEFAULT_MAX_XML_DEPTH` if not set. Chains of Collada references and of AMF
+    /// constellations count as nested elements.
+    pub max_xml_depth: Option<u64>,
+    /// Maximal number of XML nodes in .dae, .amf and robot description files. Besides
+    /// the elements, the text and comments between them count. Parsing stops at the
+    /// first node over it.
+    pub max_xml_elements: Option<u64>,
+    /// Maximal size of the data decompressed from zipped .amf files, in bytes.
+    pub max_decompressed_size: Option<u64>,
+}
+
+/// The XML depth allowed when `Limits::max_xml_depth` is not set. Parsing XML and
+/// following the nested elements is recursive, deeper documents could overflow the
+/// stack of the thread. A larger maximum needs threads with a larger stack, especially
+/// in debug builds.
+pub const DEFAULT_MAX_XML_DEPTH: u64 = 100;
+
+/// The quantity bounded by one of the `Limits`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Limit {
+    FileSize,
+    Vertices,
+    Faces,
+    XmlDepth,
+    XmlElements,
+    DecompressedSize,
+}
+
+impl Limit {
+    fn description(self) -> &'static str {
+        match self {
+            Limit::FileSize => "file size",
+            Limit::Vertices => "vertex count",
+            Limit::Faces => "face count",
+            Limit::XmlDepth => "XML depth",
+            Limit::XmlElements => "XML node count",
+            Limit::DecompressedSize => "decompressed size",
+        }
+    }
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.description())
+    }
+}
+
+/// The error of a load that went over one of the `Limits`, kept by
+/// `LoadError::LimitExceeded`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LimitExceeded {
+    /// The limit that was exceeded.
+    pub limit: Limit,
+    /// The value found in the input. For sizes read incrementally and for XML nodes,
+    /// this is where reading stopped, which is the maximum plus one.
+    pub actual: u64,
+    /// The configured maximum.
+    pub max: u64,
+}
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Limit exceeded: {} {} is over the maximum of {}",
+            self.limit, self.actual, self.max
+        )
+    }
+}
+
+impl Limits {
+    /// Checks a value against the maximum for the given limit.
+    pub fn check(&self, limit: Limit, actual: u64) -> Result<(), LimitExceeded> {
+        let max = match limit {
+            Limit::FileSize => self.max_file_size,
+            Limit::Vertices => self.max_vertices,
+            Limit::Faces => self.max_faces,
+            Limit::XmlDepth => Some(self.max_xml_depth.unwrap_or(DEFAULT_MAX_XML_DEPTH)),
+            Limit::XmlElements => self.max_xml_elements,
+            Limit::DecompressedSize => self.max_decompressed_size,
+        };
+        match max {
+            Some(max) if actual > max => Err(LimitExceeded { limit, actual, max }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Like `check`, for counts that are known as `usize`.
+    pub(crate) fn check_count(&self, limit: Limit, count: usize) -> Result<(), LimitExceeded> {
+        self.check(limit, count as u64)
+    }
+
+    /// Limits the reader to one byte more than the maximum for the given size limit,
+    /// so that `check_count` on the length of everything read detects larger input
+    /// without reading all of it.
+    pub(crate) fn bounded<R: Read>(&self, limit: Limit, reader: R) -> Take<R> {
+        reader.take(self.read_limit(limit))
+    }
+
+    /// Number of bytes to read at most for the given size limit, see `bounded`.
+    pub(crate) fn read_limit(&self, limit: Limit) -> u64 {
+        let max = match limit {
+            Limit::FileSize => self.max_file_size,
+            Limit::DecompressedSize => self.max_decompressed_size,
+            _ => None,
+        };
+        max.map_or(u64::MAX, |max| max.saturating_add(1))
+    }
+
+    /// Parses an XML document within the limits. The nesting depth is checked first by
+    /// a scan of the text, as roxmltree recurses into nested elements while parsing.
+    /// Parsing stops at the first node over the maximum, so that larger documents are
+    /// not built in memory.
+    pub(crate) fn parse_xml<'input>(
+        &self,
+        text: &'input str,
+        options: roxmltree::ParsingOptions<'input>,
+    ) -> Result<roxmltree::Document<'input>, LoadError> {
+        self.check_xml_depth(text)?;
+
+        // The root of the document is a node for roxmltree, but not one of the file
+        let nodes_limit = self
+            .max_xml_elements
+            .and_then(|max| u32::try_from(max.saturating_add(1)).ok())
+            .unwrap_or(u32::MAX);
+        let options = roxmltree::ParsingOptions {
+            nodes_limit,
+            ..options
+        };
+        roxmltree::Document::parse_with_options(text, options).map_err(|err| {
+            match (err, self.max_xml_elements) {
+                (roxmltree::Error::NodesLimitReached, Some(max)) => LimitExceeded {
+                    limit: Limit::XmlElements,
+                    actual: max.saturating_add(1),
+                    max,
+                }
+                .into(),
+                (err, _) => err.to_string().into(),
+            }
+        })
+    }
+
+    /// Checks the nesting depth of an XML document without building it, stopping at
+    /// the first element too deep. Malformed markup is left for the parser to report.
+    ///
+    /// Entities declared in the document type may hold elements too, and roxmltree
+    /// expands references in them up to 10 levels deep. The elements of the entity
+    /// with the most of them therefore count 10 times, below all the elements of the
+    /// document.
+    fn check_xml_depth(&self, text: &str) -> Result<(), LoadError> {
+        let text = text.as_bytes();
+        let mut depth: u64 = 0;
+        let mut entity_depth: u64 = 0;
+        let mut pos = 0;
+        while let Some(offset) = text
+            .get(pos..)
+            .and_then(|rest| rest.iter().position(|&byte| byte == b'<'))
+        {
+            pos += offset;
+            let rest = &text[pos..];
+            pos = if rest.starts_with(b"<!--") {
+                skip_past(text, pos + 4, b"-->")
+            } else if rest.starts_with(b"<![CDATA[") {
+                skip_past(text, pos + 9, b"]]>")
+            } else if rest.starts_with(b"<?") {
+                skip_past(text, pos + 2, b"?>")
+            } else if rest.starts_with(b"<!") {
+                let (end, elements) = skip_declaration(text, pos + 2);
+                entity_depth = entity_depth.max(10 * elements);
+                end
+            } else if rest.starts_with(b"</") {
+                depth = depth.saturating_sub(1);
+                skip_past(text, pos + 2, b">")
+            } else {
+                let Some(end) = find_tag_end(text, pos + 1) else {
+                    break;
+                };
+                // An empty element like <a/> is as deep as one with content
+                self.check(Limit::XmlDepth, depth + 1 + entity_depth)?;
+                if text[end - 1] != b'/' {
+                    depth += 1;
+                }
+                end + 1
+            };
+        }
+        Ok(())
+    }
+}
+
+/// Position after the first `pattern` at or after `from`, or the end of the text.
+fn skip_past(text: &[u8], from: usize, pattern: &[u8]) -> usize {
+    text.get(from..)
+        .and_then(|rest| {
+            rest.windows(pattern.len())
+                .position(|window| window == pattern)
+        })
+        .map_or(text.len(), |offset| from + offset + pattern.len())
+}
+
+/// Position of the `>` closing the tag started before `from`, skipping quoted
+/// attribute values, if the tag is closed.
+fn find_tag_end(text: &[u8], from: usize) -> Option<usize> {
+    let mut pos = from;
+    while pos < text.len() {
+        match text[pos] {
+            b'>' => return Some(pos),
+            quote @ (b'"' | b'\'') => pos = skip_past(text, pos + 1, &[quote]),
+            _ => pos += 1,
+        }
+    }
+    None
+}
+
+/// Position after a declaration like the document type, with its internal subset in
+/// brackets, or the end of the text. Also returns the largest number of elements
+/// started in one of the quoted values in it, the entities.
+fn skip_declaration(text: &[u8], from: usize) -> (usize, u64) {
+    let mut brackets = 0usize;
+    let mut elements = 0;
+    let mut pos = from;
+    while pos < text.len() {
+        match text[pos] {
+            b'[' => brackets += 1,
+            b']' => brackets = brackets.saturating_sub(1),
+            b'>' if brackets == 0 => return (pos + 1, elements),
+            quote @ (b'"' | b'\'') => {
+                let end = skip_past(text, pos + 1, &[quote]);
+                let value = &text[pos + 1..end.saturating_sub(1).max(pos + 1)];
+                let started = value.iter().filter(|&&byte| byte == b'<').count();
+                elements = elements.max(started as u64);
+                pos = end;
+                continue;
+            }
+            b'<' if text[pos..].starts_with(b"<!--") => {
+                pos = skip_past(text, pos + 4, b"-->");
+                continue;
+            }
+            _ => {}
+        }
+        pos += 1;
+    }
+    (text.len(), elements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_xml() {
+        let text = "<a><b><c/></b>\n<b/></a>";
+        let limits = Limits {
+            max_xml_depth: Some(3),
+            max_xml_elements: Some(5),
+            ..Limits::default()
+        };
+        let options = roxmltree::ParsingOptions::default;
+        assert!(limits.parse_xml(text, options()).is_ok());
+
+        // The text between the elements counts as a node
+        let limits = Limits {
+            max_xml_elements: Some(4),
+            ..Limits::default()
+        };
+        let error = limits.parse_xml(text, options()).unwrap_err();
+        assert_eq!(
+            error.limit_exceeded().map(|exceeded| exceeded.limit),
+            Some(Limit::XmlElements)
+        );
+
+        let limits = Limits {
+            max_xml_depth: Some(2),
+            ..Limits::default()
+        };
+        let error = limits.parse_xml(text, options()).unwrap_err();
+        assert_eq!(
+            error.limit_exceeded().map(|exceeded| exceeded.limit),
+            Some(Limit::XmlDepth)
+        );
+
+        // Markup in comments, character data and attribute values is not nested
+        let text = "<a x='b>' y=\"/>\"><!-- <b><b> --><![CDATA[<b><b>]]><?p <b>?><c/></a>";
+        assert!(limits.parse_xml(text, options()).is_ok());
+
+        // Elements may come from entities, which nest up to 10 levels
+        let text = "<!DOCTYPE a [<!ENTITY e '<b/>'>]><a>&e;</a>";
+        let options = || roxmltree::ParsingOptions {
+            allow_dtd: true,
+            ..Default::default()
+        };
+        let limits = Limits {
+            max_xml_depth: Some(11),
+            ..Limits::default()
+        };
+        assert!(limits.parse_xml(text, options()).is_ok());
+        let limits = Limits {
+            max_xml_depth: Some(10),
+            ..Limits::default()
+        };
+        assert!(limits.parse_xml(text, options()).is_err());
+    }
+}
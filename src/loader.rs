@@ -0,0 +1,791 @@
+use crate::cache::{self, CacheKey, Lookup};
+use crate::error::LoadError;
+use crate::limits::{Limit, Limits};
+use crate::mesh_data::MeshData;
+use crate::name_filter::NameFilter;
+use crate::ply::{self, PlySchema};
+use crate::progress::{CancellationToken, Progress, ProgressCallback, TrackedReader, Tracker};
+use crate::recenter::{CoordinateFrame, Recenter};
+use crate::registry::{FormatRegistry, MeshFormatLoader, MeshReader, SNIFF_LENGTH};
+use crate::resolver::{self, UriResolver};
+use crate::{Isometry, Point, Real, TriMesh, TriMeshFlags, default_flags};
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Cursor, Read};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// Built-in mesh file formats, normally determined by the file extension. Any format
+/// converts to the name of its loader in the `FormatRegistry`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Stl,
+    Ply,
+    Obj,
+    Dae,
+    Amf,
+}
+
+impl Format {
+    /// Returns the format for the extension of the given path, ignoring case.
+    pub fn from_path(file_path: &str) -> Option<Format> {
+        let extension = Path::new(file_path)
+            .extension()
+            .and_then(|ext| ext.to_str())?
+            .to_lowercase();
+        match extension.as_str() {
+            "stl" => Some(Format::Stl),
+            "ply" => Some(Format::Ply),
+            "obj" => Some(Format::Obj),
+            "dae" => Some(Format::Dae),
+            "amf" => Some(Format::Amf),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the built-in loader of this format.
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::Stl => "stl",
+            Format::Ply => "ply",
+            Format::Obj => "obj",
+            Format::Dae => "dae",
+            Format::Amf => "amf",
+        }
+    }
+}
+
+impl From<Format> for String {
+    fn from(format: Format) -> Self {
+        format.name().to_string()
+    }
+}
+
+/// How faces with more than three vertices are turned into triangles.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Triangulation {
+    /// Split each polygon into a fan of triangles around its first vertex.
+    /// This is correct for convex polygons, which most exporters write.
+    #[default]
+    Fan,
+    /// Only keep the triangle formed by the first three vertices, as versions
+    /// before 2.1 did for .ply files.
+    FirstTriangle,
+    /// Fail on faces with more than three vertices.
+    Reject,
+}
+
+impl Triangulation {
+    /// Appends the triangles of a polygon to `triangles`. Fails for polygons with less
+    /// than three vertices, and for larger ones if the policy rejects them.
+    pub(crate) fn triangulate(
+        self,
+        polygon: &[u32],
+        face: usize,
+        triangles: &mut Vec<[u32; 3]>,
+    ) -> Result<(), String> {
+        match (polygon.len(), self) {
+            (0..3, _) => Err(format!(
+                "Insufficient indices for a triangle in face {}",
+                face
+            )),
+            (3, _) | (_, Triangulation::FirstTriangle) => {
+                triangles.push([polygon[0], polygon[1], polygon[2]]);
+                Ok(())
+            }
+            (_, Triangulation::Fan) => {
+                triangles.extend(
+                    polygon[1..]
+                        .windows(2)
+                        .map(|edge| [polygon[0], edge[0], edge[1]]),
+                );
+                Ok(())
+            }
+            (count, Triangulation::Reject) => Err(format!(
+                "Face {} has {} vertices, only triangles are accepted",
+                face, count
+            )),
+        }
+    }
+}
+
+/// How strictly the loaded faces are checked before the mesh is built.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Validation {
+    /// Faces that reference missing vertices or vertices with non-finite coordinates
+    /// are dropped, so that building the `TriMesh` cannot fail on them.
+    #[default]
+    Lenient,
+    /// Such faces are an error, as are degenerate faces that use a vertex twice.
+    Strict,
+    /// Only faces that reference missing vertices are an error, other faces are kept as
+    /// they are. This is what `load_trimesh` does.
+    Indices,
+}
+
+/// Which parts of a file are loaded, for formats that hold several: objects (groups)
+/// in .obj files, geometries in .dae files and objects in .amf files. Formats with a
+/// single mesh (.stl, .ply) are always loaded completely.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum SubMeshes {
+    /// Load all parts.
+    #[default]
+    All,
+    /// Load the parts with one of the given names (or ids, for .dae and .amf).
+    Names(Vec<String>),
+    /// Load the parts at the given positions, counting from zero in file order.
+    Indices(Vec<usize>),
+}
+
+impl SubMeshes {
+    /// Whether the part at the given position, known under the given names, is loaded.
+    pub(crate) fn selects(&self, index: usize, names: &[Option<&str>]) -> bool {
+        match self {
+            SubMeshes::All => true,
+            SubMeshes::Names(selected) => names
+                .iter()
+                .flatten()
+                .any(|name| selected.iter().any(|selected| selected == name)),
+            SubMeshes::Indices(selected) => selected.contains(&index),
+        }
+    }
+}
+
+/// All settings of a `TrimeshLoader`.
+#[derive(Clone, Debug)]
+pub struct LoadOptions {
+    /// Factor applied to all coordinates.
+    pub scale: Real,
+    /// Flags used to build the `TriMesh`.
+    pub flags: TriMeshFlags,
+    /// Name of the loader to use. If not set, it is determined from the file extension,
+    /// or from the content if the extension is unknown.
+    pub format: Option<String>,
+    /// Vertices closer than this distance (after scaling) are merged into one.
+    pub welding_tolerance: Option<Real>,
+    /// Transform applied to the mesh after scaling.
+    pub transform: Option<Isometry<Real>>,
+    /// How faces with more than three vertices are split.
+    pub triangulation: Triangulation,
+    /// How strictly faces are checked.
+    pub validation: Validation,
+    /// Which parts of the file are loaded.
+    pub sub_meshes: SubMeshes,
+    /// Which parts of the file are loaded, by name patterns.
+    pub name_filter: NameFilter,
+    /// Names of the elements and properties with the geometry of .ply files.
+    pub ply_schema: PlySchema,
+    /// Origin subtracted from the coordinates before they are narrowed.
+    pub recenter: Recenter,
+    /// Upper bounds on the input.
+    pub limits: Limits,
+    /// Called with the progress of the load.
+    pub progress: Option<ProgressCallback>,
+    /// Token to cancel the load from another thread.
+    pub cancellation: Option<CancellationToken>,
+    /// Directory where meshes loaded from files are cached.
+    pub cache_directory: Option<PathBuf>,
+    /// Resolves the file paths that are URIs, like `package://`. If not set, the
+    /// resolver of the environment is used.
+    pub resolver: Option<UriResolver>,
+}
+
+impl Default for LoadOptions {
+    fn default() -> Self {
+        LoadOptions {
+            scale: 1.0,
+            flags: default_flags(),
+            format: None,
+            welding_tolerance: None,
+            transform: None,
+            triangulation: Triangulation::default(),
+            validation: Validation::default(),
+            sub_meshes: SubMeshes::default(),
+            name_filter: NameFilter::default(),
+            ply_schema: PlySchema::default(),
+            recenter: Recenter::default(),
+            limits: Limits::default(),
+            progress: None,
+            cancellation: None,
+            cache_directory: None,
+            resolver: None,
+        }
+    }
+}
+
+/// State shared with the format loaders while a file is loaded.
+pub struct LoadContext<'a> {
+    pub(crate) frame: CoordinateFrame,
+    pub(crate) options: &'a LoadOptions,
+    file_path: Option<&'a str>,
+    tracker: Rc<Tracker>,
+    dependencies: Vec<PathBuf>,
+    attributes: bool,
+}
+
+impl<'a> LoadContext<'a> {
+    pub(crate) fn new(
+        options: &'a LoadOptions,
+        file_path: Option<&'a str>,
+        tracker: Rc<Tracker>,
+    ) -> Self {
+        LoadContext {
+            frame: CoordinateFrame::new(options.recenter),
+            options,
+            file_path,
+            tracker,
+            dependencies: Vec::new(),
+            attributes: true,
+        }
+    }
+
+    /// Returns the options the mesh is loaded with.
+    pub fn options(&self) -> &'a LoadOptions {
+        self.options
+    }
+
+    /// Whether the normals, colors and texture coordinates are used. They are not when
+    /// a `TriMesh` is loaded, then loaders should index the triangles by position only,
+    /// rather than split vertices whose corners have different attributes.
+    pub fn attributes(&self) -> bool {
+        self.attributes
+    }
+
+    /// Returns the path of the file, or None when loading from a reader. Loaders
+    /// can use it to find files the mesh refers to.
+    pub fn file_path(&self) -> Option<&'a str> {
+        self.file_path
+    }
+
+    /// Returns the path of the file, or "<reader>", for error messages.
+    pub fn name(&self) -> &'a str {
+        self.file_path.unwrap_or("<reader>")
+    }
+
+    /// Converts coordinates read from the file to a point of the mesh, subtracting
+    /// the origin if the mesh is recentered.
+    pub fn point(&mut self, x: f64, y: f64, z: f64) -> Point<Real> {
+        self.frame.point(x, y, z)
+    }
+
+    /// Records another file that the mesh is loaded from, like the material library of
+    /// an .obj file, whether it could be read or not. Cached loads are redone when one
+    /// of these files changes.
+    pub fn add_dependency(&mut self, path: impl Into<PathBuf>) {
+        self.dependencies.push(path.into());
+    }
+
+    /// Sets the number of elements for the progress reports, once it is known.
+    pub fn set_total_elements(&self, total: u64) {
+        self.tracker
+            .update(|progress| progress.total_elements = Some(total));
+    }
+
+    /// Counts elements as converted for the progress reports, and fails if the load
+    /// is cancelled. Loaders should call this regularly, it is cheap.
+    pub fn elements_parsed(&self, count: u64) -> Result<(), LoadError> {
+        self.tracker.update(|progress| progress.elements += count);
+        self.tracker.check()
+    }
+}
+
+/// Builder to load meshes with more settings than the `load_trimesh` functions take.
+///
+/// # Example
+///
+/// ```rust
+/// use rs_read_trimesh::{TrimeshLoader, Triangulation};
+///
+/// let result = TrimeshLoader::new()
+///     .scale(0.001)
+///     .welding_tolerance(1e-6)
+///     .triangulation(Triangulation::Reject)
+///     .load("example.ply");
+///
+/// match result {
+///     Ok(mesh) => println!("Loaded mesh with {} vertices.", mesh.vertices().len()),
+///     Err(e) => eprintln!("Failed to load mesh: {}", e),
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct TrimeshLoader {
+    options: LoadOptions,
+    registry: Arc<FormatRegistry>,
+    /// Set for the loads of `TriMesh`es, which only use the positions. The triangles
+    /// then share the vertices at a position, which are not split by their attributes.
+    positions_only: bool,
+}
+
+impl TrimeshLoader {
+    /// Creates a loader with the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a loader with the given options.
+    pub fn with_options(options: LoadOptions) -> Self {
+        TrimeshLoader {
+            options,
+            registry: Arc::default(),
+            positions_only: false,
+        }
+    }
+
+    /// Returns the options of this loader.
+    pub fn options(&self) -> &LoadOptions {
+        &self.options
+    }
+
+    /// Sets the factor applied to all coordinates.
+    pub fn scale(mut self, scale: Real) -> Self {
+        self.options.scale = scale;
+        self
+    }
+
+    /// Sets the flags used to build the `TriMesh`.
+    pub fn flags(mut self, flags: TriMeshFlags) -> Self {
+        self.options.flags = flags;
+        self
+    }
+
+    /// Forces the format instead of determining it from the file extension, by the name
+    /// of a registered loader or as a built-in `Format`.
+    pub fn format(mut self, format: impl Into<String>) -> Self {
+        self.options.format = Some(format.into());
+        self
+    }
+
+    /// Uses the loaders of the given registry instead of the built-in ones.
+    pub fn registry(mut self, registry: Arc<FormatRegistry>) -> Self {
+        self.registry = registry;
+        self
+    }
+
+    /// Merges vertices closer than the given distance, after scaling.
+    pub fn welding_tolerance(mut self, tolerance: Real) -> Self {
+        self.options.welding_tolerance = Some(tolerance);
+        self
+    }
+
+    /// Sets a transform applied to the mesh after scaling.
+    pub fn transform(mut self, transform: Isometry<Real>) -> Self {
+        self.options.transform = Some(transform);
+        self
+    }
+
+    /// Sets how faces with more than three vertices are split.
+    pub fn triangulation(mut self, triangulation: Triangulation) -> Self {
+        self.options.triangulation = triangulation;
+        self
+    }
+
+    /// Sets how strictly faces are checked.
+    pub fn validation(mut self, validation: Validation) -> Self {
+        self.options.validation = validation;
+        self
+    }
+
+    /// Selects which parts of the file are loaded.
+    pub fn sub_meshes(mut self, sub_meshes: SubMeshes) -> Self {
+        self.options.sub_meshes = sub_meshes;
+        self
+    }
+
+    /// Selects which parts of the file are loaded by name patterns, on top of the
+    /// `sub_meshes` selection.
+    pub fn name_filter(mut self, name_filter: NameFilter) -> Self {
+        self.options.name_filter = name_filter;
+        self
+    }
+
+    /// Sets the names of the elements and properties read from .ply files, see
+    /// `PlySchema`.
+    pub fn ply_schema(mut self, ply_schema: PlySchema) -> Self {
+        self.options.ply_schema = ply_schema;
+        self
+    }
+
+    /// Sets the origin subtracted from the coordinates before they are narrowed.
+    /// Use `load_recentered` to obtain the offset applied.
+    pub fn recenter(mut self, recenter: Recenter) -> Self {
+        self.options.recenter = recenter;
+        self
+    }
+
+    /// Sets upper bounds on the input, for files from untrusted sources.
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.options.limits = limits;
+        self
+    }
+
+    /// Sets a callback receiving the progress of the load.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rs_read_trimesh::{CancellationToken, TrimeshLoader};
+    ///
+    /// let token = CancellationToken::new();
+    /// let loader = TrimeshLoader::new()
+    ///     .cancellation(token.clone())
+    ///     .progress(|progress| {
+    ///         if let Some(total) = progress.total_bytes {
+    ///             println!("{} of {} bytes read", progress.bytes_read, total);
+    ///         }
+    ///     });
+    ///
+    /// // A UI would call token.cancel() from its own thread
+    /// match loader.load("large.ply") {
+    ///     Ok(mesh) => println!("Loaded mesh with {} vertices.", mesh.vertices().len()),
+    ///     Err(e) => eprintln!("Failed to load mesh: {}", e),
+    /// }
+    /// ```
+    pub fn progress(mut self, callback: impl Fn(Progress) + Send + Sync + 'static) -> Self {
+        self.options.progress = Some(ProgressCallback::new(callback));
+        self
+    }
+
+    /// Sets a token to cancel the load with. Cancelled loads fail with
+    /// `LoadError::Cancelled`.
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.options.cancellation = Some(token);
+        self
+    }
+
+    /// Caches the meshes loaded from files in the given directory, which is created if
+    /// needed. Later loads of the same file with the same options read the cached mesh
+    /// instead of parsing the file again.
+    ///
+    /// Entries are keyed on the path, size and modification time of the file, and on the
+    /// options and the `MeshFormatLoader::cache_version` of the registered loaders. Loads
+    /// are not cached while a loader without a version is registered. The size and modification time of the files
+    /// referenced by the mesh file, like the .mtl libraries of .obj files, are stored
+    /// with the entry and checked as well. Cache files carry a checksum,
+    /// damaged ones are ignored and rewritten. Stale entries are not removed, the
+    /// directory can be deleted at any time to clear the cache.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rs_read_trimesh::TrimeshLoader;
+    ///
+    /// let loader = TrimeshLoader::new().cache_directory(std::env::temp_dir().join("meshes"));
+    /// match loader.load("large.dae") {
+    ///     Ok(mesh) => println!("Loaded mesh with {} vertices.", mesh.vertices().len()),
+    ///     Err(e) => eprintln!("Failed to load mesh: {}", e),
+    /// }
+    /// ```
+    pub fn cache_directory(mut self, directory: impl Into<PathBuf>) -> Self {
+        self.options.cache_directory = Some(directory.into());
+        self
+    }
+
+    /// Sets how the file paths that are URIs are resolved, like
+    /// `package://my_robot/meshes/base.stl`. Without it, `UriResolver::from_env` is used.
+    /// Plain paths are loaded as they are.
+    pub fn resolver(mut self, resolver: UriResolver) -> Self {
+        self.options.resolver = Some(resolver);
+        self
+    }
+
+    /// Loads the `TriMesh` from the given file.
+    pub fn load(&self, file_path: &str) -> Result<TriMesh, LoadError> {
+        Ok(self.load_recentered(file_path)?.0)
+    }
+
+    /// Loads the `TriMesh` from a reader. As there is no file extension, the format is
+    /// recognized from the content unless it is set. Materials of .obj files can not be
+    /// resolved in this case.
+    pub fn load_reader<R: Read>(&self, reader: R) -> Result<TriMesh, LoadError> {
+        let loader = self.clone().positions_only();
+        let (mesh, _) = loader.load_mesh_data_reader_recentered(reader)?;
+        Ok(mesh.into_trimesh(self.options.flags)?)
+    }
+
+    /// Loads the `TriMesh` from the given file, together with the offset subtracted
+    /// by `recenter` (in scaled units, before the transform).
+    pub fn load_recentered(&self, file_path: &str) -> Result<(TriMesh, [f64; 3]), LoadError> {
+        let loader = self.clone().positions_only();
+        let (mesh, offset) = loader.load_mesh_data_recentered(file_path)?;
+        Ok((mesh.into_trimesh(self.options.flags)?, offset))
+    }
+
+    /// Loads only what the `TriMesh` uses: the attributes are dropped, so that vertices
+    /// are not split by them and the triangles index the positions of the file.
+    pub(crate) fn positions_only(mut self) -> Self {
+        self.positions_only = true;
+        self
+    }
+
+    /// Loads the mesh with its attributes from the given file, applying all options
+    /// except the flags.
+    pub fn load_mesh_data(&self, file_path: &str) -> Result<MeshData, LoadError> {
+        Ok(self.load_mesh_data_recentered(file_path)?.0)
+    }
+
+    /// Loads the mesh with its attributes from a reader, see `load_reader`.
+    pub fn load_mesh_data_reader<R: Read>(&self, reader: R) -> Result<MeshData, LoadError> {
+        Ok(self.load_mesh_data_reader_recentered(reader)?.0)
+    }
+
+    /// Loads the mesh with its attributes from the given file, together with the offset
+    /// subtracted by `recenter`, see `load_recentered`.
+    pub fn load_mesh_data_recentered(
+        &self,
+        file_path: &str,
+    ) -> Result<(MeshData, [f64; 3]), LoadError> {
+        let file_path = self.resolve(file_path)?;
+        self.cached(&file_path, || self.load_file_recentered(&file_path))
+    }
+
+    /// Returns the path of the file to load, resolving it if it is a URI.
+    pub(crate) fn resolve<'a>(&self, file_path: &'a str) -> Result<Cow<'a, str>, String> {
+        resolver::resolve_path(file_path, self.options.resolver.as_ref())
+    }
+
+    /// Returns the cached load of the file if there is one, otherwise loads it and
+    /// stores it in the cache. Without a cache directory, this just loads.
+    pub(crate) fn cached(
+        &self,
+        file_path: &str,
+        load: impl FnOnce() -> Result<Loaded, LoadError>,
+    ) -> Result<(MeshData, [f64; 3]), LoadError> {
+        match self.cache_lookup(file_path) {
+            Lookup::Hit(mesh, offset) => Ok((mesh, offset)),
+            Lookup::Miss(key) => Ok(self.cache_store(key, load()?)),
+        }
+    }
+
+    /// Looks the load of the file up in the cache, before the file is read.
+    pub(crate) fn cache_lookup(&self, file_path: &str) -> Lookup {
+        let Some(directory) = &self.options.cache_directory else {
+            return Lookup::Miss(None);
+        };
+        let Some(key) = CacheKey::new(
+            file_path,
+            &self.options,
+            &self.registry,
+            self.positions_only,
+        ) else {
+            return Lookup::Miss(None);
+        };
+        match cache::read(directory, &key) {
+            Some((mesh, offset)) => Lookup::Hit(mesh, offset),
+            None => Lookup::Miss(Some(key)),
+        }
+    }
+
+    /// Stores a load under the key of its lookup, if it has one.
+    pub(crate) fn cache_store(
+        &self,
+        key: Option<CacheKey>,
+        loaded: Loaded,
+    ) -> (MeshData, [f64; 3]) {
+        if let (Some(directory), Some(key)) = (&self.options.cache_directory, key) {
+            // The cache only saves time, a failure to write it does not fail the load
+            let Loaded {
+                mesh,
+                offset,
+                dependencies,
+            } = &loaded;
+            cache::write(directory, &key, mesh, *offset, dependencies).ok();
+        }
+        (loaded.mesh, loaded.offset)
+    }
+
+    /// Opens a file within the size limit, with a reader tracking the progress.
+    fn open_file(
+        &self,
+        file_path: &str,
+    ) -> Result<(TrackedReader<BufReader<File>>, Rc<Tracker>), LoadError> {
+        let file = File::open(file_path)
+            .map_err(|err| format!("Could not open '{}': {}", file_path, err))?;
+        let size = file
+            .metadata()
+            .map_err(|err| format!("Could not open '{}': {}", file_path, err))?
+            .len();
+        self.options.limits.check(Limit::FileSize, size)?;
+        let tracker = self.tracker(Some(size));
+        let reader = TrackedReader::new(BufReader::new(file), tracker.clone());
+        Ok((reader, tracker))
+    }
+
+    fn load_file_recentered(&self, file_path: &str) -> Result<Loaded, LoadError> {
+        let (mut reader, tracker) = self.open_file(file_path)?;
+
+        let extension = Path::new(file_path)
+            .extension()
+            .and_then(|ext| ext.to_str());
+        let by_extension = extension.and_then(|extension| self.registry.by_extension(extension));
+        let loader = match (&self.options.format, by_extension) {
+            (Some(format), _) => self.loader_by_name(format)?,
+            (None, Some(loader)) => loader,
+            (None, None) => {
+                // Unknown extension, try to recognize the content
+                let header = reader
+                    .fill_buf()
+                    .map_err(|err| format!("Could not read '{}': {}", file_path, err))?;
+                self.registry
+                    .sniff(&header[..header.len().min(SNIFF_LENGTH)])
+                    .ok_or_else(|| {
+                        format!(
+                            "Unsupported file extension for '{}', only {} are supported.",
+                            file_path,
+                            self.registry.describe_extensions()
+                        )
+                    })?
+            }
+        };
+
+        self.run(loader, &mut reader, Some(file_path), tracker)
+    }
+
+    /// Loads the vertices of a .ply file as points, the file needs no faces. Returns the
+    /// points and the offset applied, before scaling.
+    pub(crate) fn load_ply_points_recentered(
+        &self,
+        file_path: &str,
+    ) -> Result<(Vec<Point<Real>>, [f64; 3]), LoadError> {
+        let (reader, tracker) = self.open_file(file_path)?;
+        let mut context = LoadContext::new(&self.options, Some(file_path), tracker.clone());
+        let mut points = ply::load_ply_points(reader, file_path, &mut context).map_err(|err| {
+            match tracker.is_cancelled() {
+                true => LoadError::Cancelled,
+                false => err,
+            }
+        })?;
+        tracker.check()?;
+
+        let offset = context.frame.finish(&mut points);
+        tracker.finish();
+        Ok((points, offset))
+    }
+
+    fn load_mesh_data_reader_recentered<R: Read>(
+        &self,
+        mut reader: R,
+    ) -> Result<(MeshData, [f64; 3]), LoadError> {
+        // The parsers need different reader traits (stl_io also needs Seek),
+        // which an in-memory cursor provides all of
+        let limits = &self.options.limits;
+        let tracker = self.tracker(None);
+        let mut bytes = Vec::new();
+        TrackedReader::new(
+            limits.bounded(Limit::FileSize, &mut reader),
+            tracker.clone(),
+        )
+        .read_to_end(&mut bytes)
+        .map_err(|err| match tracker.is_cancelled() {
+            true => LoadError::Cancelled,
+            false => format!("Could not read the mesh: {}", err).into(),
+        })?;
+        limits.check_count(Limit::FileSize, bytes.len())?;
+        let loaded = self.load_mesh_data_bytes_recentered(&bytes, None, tracker)?;
+        Ok((loaded.mesh, loaded.offset))
+    }
+
+    /// Loads the mesh from data read into memory. The format is forced, or taken from
+    /// the extension of the file path if there is one, or recognized from the content.
+    pub(crate) fn load_mesh_data_bytes_recentered(
+        &self,
+        bytes: &[u8],
+        file_path: Option<&str>,
+        tracker: Rc<Tracker>,
+    ) -> Result<Loaded, LoadError> {
+        let by_extension = file_path
+            .and_then(|file_path| Path::new(file_path).extension())
+            .and_then(|ext| ext.to_str())
+            .and_then(|extension| self.registry.by_extension(extension));
+        let loader = match (&self.options.format, by_extension) {
+            (Some(format), _) => self.loader_by_name(format)?,
+            (None, Some(loader)) => loader,
+            (None, None) => self
+                .registry
+                .sniff(&bytes[..bytes.len().min(SNIFF_LENGTH)])
+                .ok_or_else(|| match file_path {
+                    Some(file_path) => format!(
+                        "Unsupported file extension for '{}', only {} are supported.",
+                        file_path,
+                        self.registry.describe_extensions()
+                    ),
+                    None => "The format of the mesh is not recognized, it must be set to load from this reader"
+                        .to_string(),
+                })?,
+        };
+
+        self.run(loader, &mut Cursor::new(bytes), file_path, tracker)
+    }
+
+    pub(crate) fn tracker(&self, total_bytes: Option<u64>) -> Rc<Tracker> {
+        Rc::new(Tracker::new(
+            self.options.progress.clone(),
+            self.options.cancellation.clone(),
+            total_bytes,
+        ))
+    }
+
+    /// Runs the loader and applies the options that do not depend on the format.
+    fn run(
+        &self,
+        loader: &Arc<dyn MeshFormatLoader>,
+        reader: &mut dyn MeshReader,
+        file_path: Option<&str>,
+        tracker: Rc<Tracker>,
+    ) -> Result<Loaded, LoadError> {
+        let mut context = LoadContext::new(&self.options, file_path, tracker.clone());
+        context.attributes = !self.positions_only;
+        // Loaders wrap the error of the reader in their own messages
+        let mesh =
+            loader
+                .load(reader, &mut context)
+                .map_err(|err| match tracker.is_cancelled() {
+                    true => LoadError::Cancelled,
+                    false => err,
+                })?;
+        tracker.check()?;
+
+        let result = self.finish(mesh, context)?;
+        tracker.finish();
+        Ok(result)
+    }
+
+    fn loader_by_name(&self, name: &str) -> Result<&Arc<dyn MeshFormatLoader>, String> {
+        self.registry
+            .by_name(name)
+            .ok_or_else(|| format!("No loader is registered for the format '{}'", name))
+    }
+
+    /// Applies the options that do not depend on the format.
+    fn finish(&self, mut mesh: MeshData, context: LoadContext) -> Result<Loaded, LoadError> {
+        // Loaders check declared counts where they can, this covers the others
+        let limits = &self.options.limits;
+        limits.check_count(Limit::Vertices, mesh.vertices.len())?;
+        limits.check_count(Limit::Faces, mesh.indices.len())?;
+
+        let offset = context.frame.finish(&mut mesh.vertices);
+        mesh.validate(self.options.validation)?;
+
+        mesh.scale(self.options.scale);
+        if let Some(transform) = &self.options.transform {
+            mesh.transform_by(transform);
+        }
+        if let Some(tolerance) = self.options.welding_tolerance {
+            mesh.weld(tolerance);
+        }
+
+        Ok(Loaded {
+            mesh,
+            offset: offset.map(|value| value * self.options.scale as f64),
+            dependencies: context.dependencies,
+        })
+    }
+}
+
+/// A mesh as loaded from a file, with the offset subtracted by `recenter` and the other
+/// files that were read for it.
+pub(crate) struct Loaded {
+    pub(crate) mesh: MeshData,
+    pub(crate) offset: [f64; 3],
+    pub(crate) dependencies: Vec<PathBuf>,
+}
@@ -0,0 +1,728 @@
+use crate::loader::Validation;
+use crate::{Isometry, Point, Real, TriMesh, TriMeshFlags, Vector};
+use std::collections::HashMap;
+
+/// A triangle mesh together with the optional per-vertex and per-face attributes
+/// found in the file. Use `load_mesh_data` to load it and `into_trimesh` to obtain
+/// a `TriMesh`.
+///
+/// Per-vertex attributes, when present, have exactly one entry per vertex. Some formats
+/// (OBJ, Collada) index normals, colors or texture coordinates separately from positions.
+/// A vertex whose corners refer to different values is split in this case: it keeps the
+/// values of its first corner, and copies of it are added after the vertices of the file
+/// for the other values, like the corners of a cube with a normal per face. As the
+/// `TriMesh` loaders do not read the attributes, their vertices are not split and
+/// `load_trimesh` may return fewer vertices than `into_trimesh` of this mesh.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MeshData {
+    /// Vertex positions.
+    pub vertices: Vec<Point<Real>>,
+    /// Triangles as indices into `vertices`.
+    pub indices: Vec<[u32; 3]>,
+    /// Per-vertex normals, if the file provides them.
+    pub normals: Option<Vec<Vector<Real>>>,
+    /// Per-vertex RGBA colors in the range 0.0 to 1.0, if the file provides them.
+    pub colors: Option<Vec<[f32; 4]>>,
+    /// Per-vertex texture coordinates, if the file provides them.
+    pub uvs: Option<Vec<[f32; 2]>>,
+    /// Per-face material, as index into `materials`, if the file assigns materials.
+    /// Faces without a material have `None`.
+    pub face_materials: Option<Vec<Option<u32>>>,
+    /// Materials referenced from `face_materials`.
+    pub materials: Vec<Material>,
+}
+
+/// Material assigned to faces, with the attributes the file provides for it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Material {
+    /// Name of the material, or its id if the file does not name it.
+    pub name: String,
+    /// Diffuse RGBA color in the range 0.0 to 1.0, if the file defines it.
+    pub diffuse: Option<[f32; 4]>,
+}
+
+impl Material {
+    /// Creates a material with the given name and no other attributes.
+    pub fn named(name: impl Into<String>) -> Self {
+        Material {
+            name: name.into(),
+            diffuse: None,
+        }
+    }
+}
+
+impl MeshData {
+    /// Creates mesh data with only vertices and indices.
+    pub fn new(vertices: Vec<Point<Real>>, indices: Vec<[u32; 3]>) -> Self {
+        MeshData {
+            vertices,
+            indices,
+            ..Default::default()
+        }
+    }
+
+    /// Multiplies all vertex positions by the given factor.
+    /// If `scale` is 1.0, vertices are left untouched.
+    pub fn scale(&mut self, scale: Real) {
+        if (scale - 1.0).abs() > Real::EPSILON {
+            for vertex in &mut self.vertices {
+                *vertex *= scale; // Scale the vertex in place
+            }
+        }
+    }
+
+    /// Multiplies the vertex positions by a separate factor per axis, as robot descriptions
+    /// specify. Normals are scaled inversely and normalized, and faces are flipped if the
+    /// scale mirrors the mesh, so that they keep facing outwards.
+    pub fn scale_axes(&mut self, scale: Vector<Real>) {
+        if scale
+            .iter()
+            .all(|factor| (factor - 1.0).abs() <= Real::EPSILON)
+        {
+            return;
+        }
+        for vertex in &mut self.vertices {
+            vertex.coords.component_mul_assign(&scale);
+        }
+        if let Some(normals) = &mut self.normals {
+            for normal in normals {
+                let scaled = normal.component_div(&scale);
+                *normal = scaled.try_normalize(Real::EPSILON).unwrap_or(*normal);
+            }
+        }
+        if scale.x * scale.y * scale.z < 0.0 {
+            for face in &mut self.indices {
+                face.swap(1, 2);
+            }
+        }
+    }
+
+    /// Applies the given rigid transform to vertex positions and normals.
+    pub fn transform_by(&mut self, isometry: &Isometry<Real>) {
+        for vertex in &mut self.vertices {
+            *vertex = isometry * *vertex;
+        }
+        if let Some(normals) = &mut self.normals {
+            for normal in normals {
+                *normal = isometry * *normal;
+            }
+        }
+    }
+
+    /// Splits the mesh into one part per material. Each part only contains the vertices
+    /// its faces reference, with their attributes. Faces without a material form
+    /// the last part, with `None` as material. Parts are ordered as `materials`,
+    /// and materials not used by any face are skipped.
+    pub fn split_by_material(&self) -> Vec<(Option<Material>, MeshData)> {
+        let Some(face_materials) = &self.face_materials else {
+            return vec![(None, self.clone())];
+        };
+
+        // Group the faces by material, with the faces without material last
+        let mut groups = vec![Vec::new(); self.materials.len() + 1];
+        for face in 0..self.indices.len() {
+            let group = face_materials
+                .get(face)
+                .copied()
+                .flatten()
+                .map(|id| id as usize)
+                .filter(|&id| id < self.materials.len())
+                .unwrap_or(self.materials.len());
+            groups[group].push(face);
+        }
+
+        groups
+            .into_iter()
+            .enumerate()
+            .filter(|(_, faces)| !faces.is_empty())
+            .map(|(group, faces)| {
+                (
+                    self.materials.get(group).cloned(),
+                    self.extract_faces(&faces),
+                )
+            })
+            .collect()
+    }
+
+    /// Merges vertices closer to each other than the tolerance into the first of them.
+    /// Faces that collapse as a result are removed, as are vertices no face references.
+    pub fn weld(&mut self, tolerance: Real) {
+        if tolerance.is_nan() || tolerance <= 0.0 || self.vertices.is_empty() {
+            return;
+        }
+
+        // Kept vertices by grid cell, a close vertex is always in a neighbouring cell
+        // Casts saturate, so that huge coordinates land in the outermost cells
+        let cell = |vertex: &Point<Real>| {
+            [vertex.x, vertex.y, vertex.z].map(|value| (value / tolerance).floor() as i64)
+        };
+        let mut grid: HashMap<[i64; 3], Vec<usize>> = HashMap::new();
+        let mut remap = Vec::with_capacity(self.vertices.len());
+        for (index, vertex) in self.vertices.iter().enumerate() {
+            let [x, y, z] = cell(vertex);
+            let mut found = None;
+            'search: for dx in -1..=1 {
+                for dy in -1..=1 {
+                    for dz in -1..=1 {
+                        let neighbour = [
+                            x.saturating_add(dx),
+                            y.saturating_add(dy),
+                            z.saturating_add(dz),
+                        ];
+                        let Some(candidates) = grid.get(&neighbour) else {
+                            continue;
+                        };
+                        found = candidates.iter().copied().find(|&candidate| {
+                            (self.vertices[candidate] - vertex).norm() <= tolerance
+                        });
+                        if found.is_some() {
+                            break 'search;
+                        }
+                    }
+                }
+            }
+            remap.push(found.unwrap_or_else(|| {
+                grid.entry([x, y, z]).or_default().push(index);
+                index
+            }) as u32);
+        }
+
+        for triangle in &mut self.indices {
+            for index in triangle.iter_mut() {
+                if let Some(&welded) = remap.get(*index as usize) {
+                    *index = welded;
+                }
+            }
+        }
+        let faces: Vec<usize> = (0..self.indices.len())
+            .filter(|&face| !is_degenerate(&self.indices[face]))
+            .collect();
+        *self = self.extract_faces(&faces);
+    }
+
+    /// Checks that faces only reference existing vertices with finite coordinates. Other
+    /// faces are dropped if the validation is lenient, or reported as error if it is strict.
+    /// Strict validation also rejects degenerate faces that use a vertex twice, while
+    /// `Validation::Indices` only rejects faces that reference missing vertices.
+    pub(crate) fn validate(&mut self, validation: Validation) -> Result<(), String> {
+        self.validate_attributes(validation)?;
+
+        let vertex_count = self.vertices.len();
+        let mut faces = Vec::with_capacity(self.indices.len());
+
+        for (face, triangle) in self.indices.iter().enumerate() {
+            let problem = if let Some(index) = triangle
+                .iter()
+                .find(|&&index| index as usize >= vertex_count)
+            {
+                Some(format!(
+                    "Face {} references vertex {}, but there are only {} vertices",
+                    face, index, vertex_count
+                ))
+            } else if validation != Validation::Indices
+                && triangle.iter().any(|&index| {
+                    let vertex = &self.vertices[index as usize];
+                    !(vertex.x.is_finite() && vertex.y.is_finite() && vertex.z.is_finite())
+                })
+            {
+                Some(format!(
+                    "Face {} uses a vertex with non-finite coordinates",
+                    face
+                ))
+            } else if validation == Validation::Strict && is_degenerate(triangle) {
+                Some(format!(
+                    "Face {} is degenerate, it uses a vertex twice",
+                    face
+                ))
+            } else {
+                None
+            };
+
+            match problem {
+                Some(problem) if validation != Validation::Lenient => return Err(problem),
+                Some(_) => {}
+                None => faces.push(face),
+            }
+        }
+
+        if faces.len() < self.indices.len() {
+            *self = self.extract_faces(&faces);
+        }
+        Ok(())
+    }
+
+    /// Checks that the attributes have one value per vertex, and the face materials one
+    /// per face. Lenient validation drops those that do not.
+    fn validate_attributes(&mut self, validation: Validation) -> Result<(), String> {
+        let vertex_count = self.vertices.len();
+        let face_count = self.indices.len();
+        let mismatches = [
+            ("normals", self.normals.as_ref().map(Vec::len), vertex_count),
+            ("colors", self.colors.as_ref().map(Vec::len), vertex_count),
+            (
+                "texture coordinates",
+                self.uvs.as_ref().map(Vec::len),
+                vertex_count,
+            ),
+            (
+                "face materials",
+                self.face_materials.as_ref().map(Vec::len),
+                face_count,
+            ),
+        ];
+        for (name, len, expected) in mismatches {
+            match len {
+                Some(len) if len != expected && validation == Validation::Strict => {
+                    return Err(format!(
+                        "There are {} {}, but {} were expected",
+                        len, name, expected
+                    ));
+                }
+                _ => {}
+            }
+        }
+
+        if self
+            .normals
+            .as_ref()
+            .is_some_and(|values| values.len() != vertex_count)
+        {
+            self.normals = None;
+        }
+        if self
+            .colors
+            .as_ref()
+            .is_some_and(|values| values.len() != vertex_count)
+        {
+            self.colors = None;
+        }
+        if self
+            .uvs
+            .as_ref()
+            .is_some_and(|values| values.len() != vertex_count)
+        {
+            self.uvs = None;
+        }
+        if self
+            .face_materials
+            .as_ref()
+            .is_some_and(|values| values.len() != face_count)
+        {
+            self.face_materials = None;
+        }
+        Ok(())
+    }
+
+    /// Returns the given faces as a new mesh, keeping only the vertices they reference.
+    /// Indices out of range stay out of range, and attributes that do not have one
+    /// value per vertex (or face) are dropped.
+    pub(crate) fn extract_faces(&self, faces: &[usize]) -> MeshData {
+        let mut remap = vec![None; self.vertices.len()];
+        let mut kept = Vec::new();
+        let mut indices = Vec::with_capacity(faces.len());
+
+        for triangle in faces.iter().filter_map(|&face| self.indices.get(face)) {
+            indices.push(triangle.map(|index| match remap.get_mut(index as usize) {
+                Some(slot) => *slot.get_or_insert_with(|| {
+                    kept.push(index as usize);
+                    (kept.len() - 1) as u32
+                }),
+                None => u32::MAX,
+            }));
+        }
+
+        let (materials, face_materials) = match &self.face_materials {
+            Some(face_materials) if face_materials.len() == self.indices.len() => {
+                let mut materials = Vec::new();
+                let ids = faces
+                    .iter()
+                    .map(|&face| {
+                        face_materials[face]
+                            .and_then(|id| self.materials.get(id as usize))
+                            .map(|material| material_id(&mut materials, material))
+                    })
+                    .collect();
+                (materials, Some(ids))
+            }
+            _ => (Vec::new(), None),
+        };
+
+        let vertex_count = self.vertices.len();
+        MeshData {
+            vertices: pick(&self.vertices, &kept),
+            indices,
+            normals: pick_attribute(&self.normals, vertex_count, &kept),
+            colors: pick_attribute(&self.colors, vertex_count, &kept),
+            uvs: pick_attribute(&self.uvs, vertex_count, &kept),
+            face_materials,
+            materials,
+        }
+    }
+
+    /// Builds a `TriMesh` from the vertices and indices, dropping all other attributes.
+    pub fn into_trimesh(self, flags: TriMeshFlags) -> Result<TriMesh, String> {
+        #[cfg(any(feature = "parry_19", feature = "parry_19_f64"))]
+        {
+            TriMesh::with_flags(self.vertices, self.indices, flags).map_err(|e| e.to_string())
+        }
+
+        #[cfg(any(
+            feature = "parry13",
+            feature = "parry17",
+            feature = "parry13_f64",
+            feature = "parry17_f64"
+        ))]
+        {
+            Ok(TriMesh::with_flags(self.vertices, self.indices, flags))
+        }
+    }
+}
+
+/// Marks a corner without a value for an attribute in `CornerVertices`.
+pub(crate) const NO_INDEX: u32 = u32::MAX;
+
+/// Maps the corners of faces to vertices, for formats that index `N` attributes
+/// separately from positions. The first combination of attribute indices used with a
+/// position keeps its vertex, the position is copied to a new vertex for each other
+/// combination. The copies come after the vertices of the file, which keep their order.
+pub(crate) struct CornerVertices<const N: usize> {
+    /// The attribute indices of each vertex of the file, None while no corner used it.
+    first: Vec<Option<[u32; N]>>,
+    split: HashMap<(u32, [u32; N]), u32>,
+    /// The position and attribute indices of each copied vertex.
+    copies: Vec<(u32, [u32; N])>,
+}
+
+impl<const N: usize> CornerVertices<N> {
+    pub(crate) fn new(vertex_count: usize) -> Self {
+        CornerVertices {
+            first: vec![None; vertex_count],
+            split: HashMap::new(),
+            copies: Vec::new(),
+        }
+    }
+
+    /// Returns the vertex of a corner. Positions out of bounds are returned as they
+    /// are, so that validation reports them.
+    pub(crate) fn vertex(&mut self, position: u32, attributes: [u32; N]) -> u32 {
+        match self.first.get_mut(position as usize) {
+            None => return position,
+            Some(first @ None) => {
+                *first = Some(attributes);
+                return position;
+            }
+            Some(Some(first)) if *first == attributes => return position,
+            Some(Some(_)) => {}
+        }
+        let vertex = (self.first.len() + self.copies.len()) as u32;
+        *self.split.entry((position, attributes)).or_insert_with(|| {
+            self.copies.push((position, attributes));
+            vertex
+        })
+    }
+
+    /// Appends the copied vertices to the vertices of the file.
+    pub(crate) fn extend<T: Clone>(&self, vertices: &mut Vec<T>) {
+        let copies = self.copies.iter();
+        let copies = copies.filter_map(|&(position, _)| vertices.get(position as usize).cloned());
+        let copies: Vec<T> = copies.collect();
+        vertices.extend(copies);
+    }
+
+    /// Returns one value of the attribute per vertex, from `values` by the attribute
+    /// index of its corners, or `default` for vertices without a value.
+    pub(crate) fn attribute<T: Clone>(&self, attribute: usize, values: &[T], default: T) -> Vec<T> {
+        let indices = self
+            .first
+            .iter()
+            .map(|indices| indices.map(|indices| indices[attribute]));
+        let copies = self
+            .copies
+            .iter()
+            .map(|(_, indices)| Some(indices[attribute]));
+        indices
+            .chain(copies)
+            .map(|index| {
+                index
+                    .and_then(|index| values.get(index as usize))
+                    .cloned()
+                    .unwrap_or_else(|| default.clone())
+            })
+            .collect()
+    }
+}
+
+/// Merges several meshes into one, offsetting the indices of each subsequent mesh.
+/// Attributes present in only some of the meshes are filled with defaults for
+/// the others, and materials with the same name are shared.
+pub(crate) fn merge_meshes(meshes: Vec<MeshData>) -> Result<MeshData, String> {
+    if meshes.len() == 1 {
+        return Ok(meshes.into_iter().next().unwrap_or_default());
+    }
+
+    let has_normals = meshes.iter().any(|mesh| mesh.normals.is_some());
+    let has_colors = meshes.iter().any(|mesh| mesh.colors.is_some());
+    let has_uvs = meshes.iter().any(|mesh| mesh.uvs.is_some());
+    let has_materials = meshes.iter().any(|mesh| mesh.face_materials.is_some());
+
+    let mut merged = MeshData {
+        normals: has_normals.then(Vec::new),
+        colors: has_colors.then(Vec::new),
+        uvs: has_uvs.then(Vec::new),
+        face_materials: has_materials.then(Vec::new),
+        ..Default::default()
+    };
+    let mut vertex_offset = 0u32;
+
+    for mesh in meshes {
+        let vertex_count = mesh.vertices.len();
+        let face_count = mesh.indices.len();
+
+        // Add vertices
+        merged.vertices.extend(mesh.vertices);
+
+        // The offset is checked below, so that valid indices can not overflow
+        let next_offset = u32::try_from(vertex_count)
+            .ok()
+            .and_then(|count| vertex_offset.checked_add(count))
+            .ok_or_else(|| "The merged mesh has too many vertices for u32 indices".to_string())?;
+
+        // Adjust indices and add them. Indices out of range for their mesh are kept
+        // out of range, rather than pointing into the next mesh
+        merged
+            .indices
+            .extend(mesh.indices.into_iter().map(|triangle| {
+                triangle.map(|index| {
+                    if (index as usize) < vertex_count {
+                        index + vertex_offset
+                    } else {
+                        u32::MAX
+                    }
+                })
+            }));
+
+        merge_attribute(
+            &mut merged.normals,
+            mesh.normals,
+            vertex_count,
+            Vector::zeros(),
+        );
+        merge_attribute(&mut merged.colors, mesh.colors, vertex_count, [1.0; 4]);
+        merge_attribute(&mut merged.uvs, mesh.uvs, vertex_count, [0.0; 2]);
+
+        // Remap material ids into the merged material list
+        if let Some(face_materials) = &mut merged.face_materials {
+            let remapped: Vec<u32> = mesh
+                .materials
+                .iter()
+                .map(|material| material_id(&mut merged.materials, material))
+                .collect();
+            match mesh.face_materials {
+                Some(ids) => face_materials.extend(
+                    ids.into_iter()
+                        .map(|id| id.and_then(|id| remapped.get(id as usize).copied())),
+                ),
+                None => face_materials.extend(std::iter::repeat_n(None, face_count)),
+            }
+        }
+
+        // Update vertex offset for next mesh
+        vertex_offset = next_offset;
+    }
+
+    Ok(merged)
+}
+
+/// Returns the index of the material with the same name, adding it if not yet known.
+pub(crate) fn material_id(materials: &mut Vec<Material>, material: &Material) -> u32 {
+    match materials
+        .iter()
+        .position(|known| known.name == material.name)
+    {
+        Some(id) => id as u32,
+        None => {
+            materials.push(material.clone());
+            (materials.len() - 1) as u32
+        }
+    }
+}
+
+fn is_degenerate(triangle: &[u32; 3]) -> bool {
+    triangle[0] == triangle[1] || triangle[1] == triangle[2] || triangle[0] == triangle[2]
+}
+
+/// Returns the values of the given vertices, in their order.
+fn pick<T: Clone>(values: &[T], vertices: &[usize]) -> Vec<T> {
+    vertices
+        .iter()
+        .map(|&vertex| values[vertex].clone())
+        .collect()
+}
+
+/// Like `pick`, for an attribute that is dropped if it does not have one value per vertex.
+fn pick_attribute<T: Clone>(
+    values: &Option<Vec<T>>,
+    vertex_count: usize,
+    vertices: &[usize],
+) -> Option<Vec<T>> {
+    values
+        .as_ref()
+        .filter(|values| values.len() == vertex_count)
+        .map(|values| pick(values, vertices))
+}
+
+fn merge_attribute<T: Clone>(
+    merged: &mut Option<Vec<T>>,
+    values: Option<Vec<T>>,
+    vertex_count: usize,
+    default: T,
+) {
+    if let Some(merged) = merged {
+        match values {
+            // Pad or cut, so that the values of the next mesh stay aligned
+            Some(mut values) => {
+                values.resize(vertex_count, default);
+                merged.extend(values);
+            }
+            None => merged.extend(std::iter::repeat_n(default, vertex_count)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_meshes() {
+        fn point(x: Real, y: Real, z: Real) -> Point<Real> {
+            Point::new(x, y, z)
+        }
+
+        let mesh1 = MeshData::new(
+            vec![
+                point(0.0, 0.0, 0.0),
+                point(1.0, 0.0, 0.0),
+                point(0.0, 1.0, 0.0),
+            ],
+            vec![[0, 1, 2]],
+        );
+
+        let mesh2 = MeshData::new(
+            vec![
+                point(1.0, 1.0, 0.0),
+                point(2.0, 1.0, 0.0),
+                point(1.0, 2.0, 0.0),
+            ],
+            vec![[0, 1, 2]],
+        );
+
+        let merged = merge_meshes(vec![mesh1, mesh2]).unwrap();
+
+        let expected_vertices = vec![
+            point(0.0, 0.0, 0.0),
+            point(1.0, 0.0, 0.0),
+            point(0.0, 1.0, 0.0),
+            point(1.0, 1.0, 0.0),
+            point(2.0, 1.0, 0.0),
+            point(1.0, 2.0, 0.0),
+        ];
+
+        let expected_indices = vec![[0, 1, 2], [3, 4, 5]];
+
+        assert_eq!(merged.vertices, expected_vertices);
+        assert_eq!(merged.indices, expected_indices);
+        assert_eq!(merged.normals, None);
+        assert_eq!(merged.face_materials, None);
+    }
+
+    #[test]
+    fn test_merge_meshes_attributes() {
+        let mut mesh1 = MeshData::new(vec![Point::origin(); 3], vec![[0, 1, 2]]);
+        mesh1.colors = Some(vec![[1.0, 0.0, 0.0, 1.0]; 3]);
+        mesh1.materials = vec![Material::named("steel")];
+        mesh1.face_materials = Some(vec![Some(0)]);
+
+        let mut mesh2 = MeshData::new(vec![Point::origin(); 3], vec![[0, 1, 2], [2, 1, 0]]);
+        mesh2.materials = vec![Material::named("rubber"), Material::named("steel")];
+        mesh2.face_materials = Some(vec![Some(1), Some(0)]);
+
+        let merged = merge_meshes(vec![mesh1, mesh2]).unwrap();
+
+        let mut expected_colors = vec![[1.0, 0.0, 0.0, 1.0]; 3];
+        expected_colors.extend([[1.0; 4]; 3]);
+        assert_eq!(merged.colors, Some(expected_colors));
+        assert_eq!(
+            merged.materials,
+            vec![Material::named("steel"), Material::named("rubber")]
+        );
+        assert_eq!(merged.face_materials, Some(vec![Some(0), Some(0), Some(1)]));
+    }
+
+    #[test]
+    fn test_merge_meshes_invalid_indices() {
+        let triangle = || {
+            vec![
+                Point::new(0.0, 0.0, 0.0),
+                Point::new(1.0, 0.0, 0.0),
+                Point::new(0.0, 1.0, 0.0),
+            ]
+        };
+        // Index 3 does not exist in the first mesh, and must not point into the second one
+        let mesh1 = MeshData::new(triangle(), vec![[0, 1, 3]]);
+        let mesh2 = MeshData::new(triangle(), vec![[0, 1, u32::MAX]]);
+
+        let merged = merge_meshes(vec![mesh1, mesh2]).unwrap();
+        assert_eq!(merged.indices, vec![[0, 1, u32::MAX], [3, 4, u32::MAX]]);
+
+        let mut validated = merged.clone();
+        validated.validate(Validation::Lenient).unwrap();
+        assert!(validated.indices.is_empty());
+        assert!(merged.clone().validate(Validation::Strict).is_err());
+
+        // The other operations skip such faces instead of panicking
+        assert_eq!(merged.volume(), 0.0);
+        // Both triangles are at the same positions, so they are one component
+        assert_eq!(merged.split_components(Default::default()).len(), 1);
+    }
+
+    #[test]
+    fn test_split_by_material() {
+        let mut mesh = MeshData::new(
+            vec![
+                Point::new(0.0, 0.0, 0.0),
+                Point::new(1.0, 0.0, 0.0),
+                Point::new(0.0, 1.0, 0.0),
+                Point::new(1.0, 1.0, 0.0),
+            ],
+            vec![[0, 1, 2], [1, 3, 2], [0, 2, 3]],
+        );
+        mesh.uvs = Some(vec![[0.0, 0.0], [1.0, 0.0], [0.0, 1.0], [1.0, 1.0]]);
+        mesh.materials = vec![Material::named("unused"), Material::named("steel")];
+        mesh.face_materials = Some(vec![None, Some(1), None]);
+
+        let parts = mesh.split_by_material();
+        assert_eq!(parts.len(), 2);
+
+        let (material, steel) = &parts[0];
+        assert_eq!(material.as_ref(), Some(&Material::named("steel")));
+        assert_eq!(
+            steel.vertices,
+            vec![
+                Point::new(1.0, 0.0, 0.0),
+                Point::new(1.0, 1.0, 0.0),
+                Point::new(0.0, 1.0, 0.0)
+            ]
+        );
+        assert_eq!(steel.indices, vec![[0, 1, 2]]);
+        assert_eq!(steel.uvs, Some(vec![[1.0, 0.0], [1.0, 1.0], [0.0, 1.0]]));
+        assert_eq!(steel.materials, vec![Material::named("steel")]);
+        assert_eq!(steel.face_materials, Some(vec![Some(0)]));
+
+        let (material, rest) = &parts[1];
+        assert_eq!(material, &None);
+        assert_eq!(rest.vertices.len(), 4);
+        assert_eq!(rest.indices, vec![[0, 1, 2], [0, 2, 3]]);
+        assert_eq!(rest.face_materials, Some(vec![None, None]));
+    }
+}
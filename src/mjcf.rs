@@ -0,0 +1,313 @@
+use crate::error::LoadError;
+use crate::loader::TrimeshLoader;
+use crate::mesh_data::MeshData;
+use crate::resolver::UriResolver;
+use crate::robot::{self, Bodies, MeshSources, parse_values, parse_vector};
+use crate::xml::children;
+use crate::{Isometry, Point, Real, TriMesh, Vector, na};
+use roxmltree::Node;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// Name of the body of the geoms placed directly in `<worldbody>`.
+const WORLD: &str = "world";
+
+/// Loads the collision meshes of a MuJoCo MJCF model, with the `mjcf` feature. Each body
+/// with mesh geoms becomes one `TriMesh` in the frame of the body: the `<geom>` elements
+/// referring to a `<mesh>` asset are loaded with the `scale` of the asset, placed by
+/// their `pos` and orientation (`quat`, `axisangle`, `euler`, `xyaxes` or `zaxis`) and
+/// merged. Geoms directly in the `<worldbody>` belong to the body named "world".
+///
+/// Mesh files are found in the `meshdir` (or `assetdir`) of the `<compiler>`, relative
+/// to the model file, or through the `UriResolver` for URIs. Inline assets with `vertex` and `face` lists are supported too,
+/// assets are only loaded if a geom uses them. The `angle` and `eulerseq` settings of the compiler are honored.
+/// Default classes and `<include>` elements are not supported, and the vertices are
+/// used as in the file, while MuJoCo moves meshes to their center of mass.
+///
+/// # Example
+///
+/// ```rust
+/// use rs_read_trimesh::MjcfLoader;
+///
+/// match MjcfLoader::new().load("scene.xml") {
+///     Ok(bodies) => println!("Loaded {} bodies.", bodies.len()),
+///     Err(e) => eprintln!("Failed to load model: {}", e),
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct MjcfLoader {
+    sources: MeshSources,
+}
+
+/// The settings of the `<compiler>` element that matter for the geometry.
+struct Compiler<'a> {
+    mesh_directory: PathBuf,
+    degrees: bool,
+    euler_sequence: &'a str,
+}
+
+impl MjcfLoader {
+    /// Creates a loader that loads the mesh files with the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the loader used for the mesh files. Its scale and transform are applied to
+    /// each file before the scale and placement from the model.
+    pub fn mesh_loader(mut self, loader: TrimeshLoader) -> Self {
+        self.sources.loader = loader;
+        self
+    }
+
+    /// Sets how the mesh file names that are URIs, like `file://`, are resolved. The
+    /// default is `UriResolver::from_env`.
+    pub fn resolver(mut self, resolver: UriResolver) -> Self {
+        self.sources.resolver = resolver;
+        self
+    }
+
+    /// Loads the collision mesh of every body that has one, by body name.
+    pub fn load(&self, mjcf_path: &str) -> Result<BTreeMap<String, TriMesh>, LoadError> {
+        let mut loader = self.clone();
+        loader.sources.loader = loader.sources.loader.positions_only();
+        loader
+            .sources
+            .to_trimeshes(loader.load_mesh_data(mjcf_path)?)
+    }
+
+    /// Loads the collision mesh of every body with its attributes, see `load`.
+    pub fn load_mesh_data(&self, mjcf_path: &str) -> Result<BTreeMap<String, MeshData>, LoadError> {
+        let text = self.sources.read_xml(mjcf_path)?;
+        let document = self.sources.parse_xml(&text, mjcf_path, "mujoco")?;
+        let mujoco = document.root_element();
+        let compiler = compiler(mujoco, robot::directory(mjcf_path))?;
+
+        // Mesh assets are named after their file if they have no name
+        let mut assets = HashMap::new();
+        for mesh in children(mujoco, "asset").flat_map(|asset| children(asset, "mesh")) {
+            let name = mesh.attribute("name").or_else(|| {
+                let file = mesh.attribute("file")?;
+                Path::new(file).file_stem()?.to_str()
+            });
+            // Without a name nor a file, no geom can refer to the asset
+            if let Some(name) = name {
+                assets.insert(name, mesh);
+            }
+        }
+
+        let mut bodies = Bodies::default();
+        let mut loaded = HashMap::new();
+        for worldbody in children(mujoco, "worldbody") {
+            self.load_body(
+                worldbody,
+                WORLD,
+                &compiler,
+                &assets,
+                &mut loaded,
+                &mut bodies,
+            )?;
+        }
+        bodies.finish()
+    }
+
+    /// Loads the mesh geoms of a body and of its child bodies. Each asset is only
+    /// loaded once, even if several geoms use it.
+    fn load_body(
+        &self,
+        body: Node,
+        name: &str,
+        compiler: &Compiler,
+        assets: &HashMap<&str, Node>,
+        loaded: &mut HashMap<String, MeshData>,
+        bodies: &mut Bodies,
+    ) -> Result<(), LoadError> {
+        for geom in children(body, "geom") {
+            let Some(asset) = geom.attribute("mesh") else {
+                continue;
+            };
+            if geom.attribute("type").is_some_and(|kind| kind != "mesh") {
+                continue;
+            }
+
+            let mesh = *assets
+                .get(asset)
+                .ok_or_else(|| format!("Body '{}': unknown mesh asset '{}'", name, asset))?;
+            if !loaded.contains_key(asset) {
+                let data = self
+                    .load_mesh(mesh, compiler)
+                    .map_err(|err| err.context(format_args!("Body '{}'", name)))?;
+                loaded.insert(asset.to_string(), data);
+            }
+            bodies.add(name, loaded[asset].clone(), &placement(geom, compiler)?);
+        }
+
+        for child in children(body, "body") {
+            let child_name = child
+                .attribute("name")
+                .ok_or_else(|| format!("A <body> in '{}' has no name", name))?;
+            self.load_body(child, child_name, compiler, assets, loaded, bodies)?;
+        }
+        Ok(())
+    }
+
+    /// Loads the file or the inline vertices and faces of a `<mesh>` asset and applies
+    /// its scale.
+    fn load_mesh(&self, mesh: Node, compiler: &Compiler) -> Result<MeshData, LoadError> {
+        let scale = mesh
+            .attribute("scale")
+            .map(|scale| parse_vector(scale, "scale"))
+            .transpose()?;
+        if let Some(file) = mesh.attribute("file") {
+            let path = self
+                .sources
+                .resolver
+                .resolve(file, &compiler.mesh_directory)?;
+            return self.sources.load(&path, scale);
+        }
+
+        let vertices = mesh
+            .attribute("vertex")
+            .ok_or_else(|| "A <mesh> asset has neither a file nor vertices".to_string())?;
+        // MuJoCo computes the convex hull of vertices without faces, which is not done here
+        let faces = mesh
+            .attribute("face")
+            .ok_or_else(|| "A <mesh> asset has vertices but no faces".to_string())?;
+        let mut data = inline_mesh(vertices, faces)?;
+        if let Some(scale) = scale {
+            data.scale_axes(scale);
+        }
+        Ok(data)
+    }
+}
+
+/// Builds the mesh of an inline asset from its "x y z ..." vertices and "a b c ..." faces.
+fn inline_mesh(vertices: &str, faces: &str) -> Result<MeshData, String> {
+    let coordinates = parse_values(vertices, "vertex")?;
+    let indices = faces
+        .split_whitespace()
+        .map(|index| index.parse::<u32>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| format!("Invalid face '{}'", faces))?;
+    if coordinates.len() % 3 != 0 || indices.len() % 3 != 0 {
+        return Err("Inline mesh vertices and faces must have three values each".to_string());
+    }
+    if let Some(index) = indices
+        .iter()
+        .find(|&&index| index as usize >= coordinates.len() / 3)
+    {
+        return Err(format!(
+            "Inline mesh face refers to missing vertex {}",
+            index
+        ));
+    }
+    Ok(MeshData::new(
+        coordinates
+            .chunks_exact(3)
+            .map(|xyz| Point::new(xyz[0], xyz[1], xyz[2]))
+            .collect(),
+        indices
+            .chunks_exact(3)
+            .map(|abc| [abc[0], abc[1], abc[2]])
+            .collect(),
+    ))
+}
+
+/// Reads the `<compiler>` settings, MuJoCo's defaults if there is none.
+fn compiler<'a>(mujoco: Node<'a, '_>, directory: &Path) -> Result<Compiler<'a>, String> {
+    let mut compiler = Compiler {
+        mesh_directory: directory.to_path_buf(),
+        degrees: true,
+        euler_sequence: "xyz",
+    };
+    for element in children(mujoco, "compiler") {
+        if let Some(asset_directory) = element
+            .attribute("meshdir")
+            .or_else(|| element.attribute("assetdir"))
+        {
+            compiler.mesh_directory = directory.join(asset_directory);
+        }
+        match element.attribute("angle") {
+            Some("degree") => compiler.degrees = true,
+            Some("radian") => compiler.degrees = false,
+            Some(angle) => return Err(format!("Invalid angle unit '{}'", angle)),
+            None => {}
+        }
+        if let Some(sequence) = element.attribute("eulerseq") {
+            if sequence.len() != 3 || !sequence.chars().all(|axis| "xyzXYZ".contains(axis)) {
+                return Err(format!("Invalid euler sequence '{}'", sequence));
+            }
+            compiler.euler_sequence = sequence;
+        }
+    }
+    Ok(compiler)
+}
+
+/// Parses the position and orientation of a geom within its body.
+fn placement(geom: Node, compiler: &Compiler) -> Result<Isometry<Real>, String> {
+    let position = geom
+        .attribute("pos")
+        .map_or(Ok(Vector::zeros()), |pos| parse_vector(pos, "pos"))?;
+    let angle = |value: Real| match compiler.degrees {
+        true => value.to_radians(),
+        false => value,
+    };
+
+    let rotation = if let Some(quat) = geom.attribute("quat") {
+        // MuJoCo writes the scalar part first
+        match parse_values(quat, "quat")?[..] {
+            [w, x, y, z] => na::UnitQuaternion::from_quaternion(na::Quaternion::new(w, x, y, z)),
+            _ => return Err(format!("Invalid quat '{}', four values are expected", quat)),
+        }
+    } else if let Some(axis_angle) = geom.attribute("axisangle") {
+        match parse_values(axis_angle, "axisangle")?[..] {
+            [x, y, z, a] => na::UnitQuaternion::from_axis_angle(
+                &na::Unit::new_normalize(Vector::new(x, y, z)),
+                angle(a),
+            ),
+            _ => return Err(format!("Invalid axisangle '{}'", axis_angle)),
+        }
+    } else if let Some(euler) = geom.attribute("euler") {
+        let angles = parse_vector(euler, "euler")?;
+        // Lower case axes rotate with the frame, upper case ones stay fixed
+        let mut rotation = na::UnitQuaternion::identity();
+        for (axis, value) in compiler.euler_sequence.chars().zip(angles.iter()) {
+            let unit = match axis.to_ascii_lowercase() {
+                'x' => Vector::x_axis(),
+                'y' => Vector::y_axis(),
+                _ => Vector::z_axis(),
+            };
+            let step = na::UnitQuaternion::from_axis_angle(&unit, angle(*value));
+            rotation = match axis.is_ascii_lowercase() {
+                true => rotation * step,
+                false => step * rotation,
+            };
+        }
+        rotation
+    } else if let Some(axes) = geom.attribute("xyaxes") {
+        match parse_values(axes, "xyaxes")?[..] {
+            [x1, x2, x3, y1, y2, y3] => {
+                let x = Vector::new(x1, x2, x3).normalize();
+                let y = Vector::new(y1, y2, y3);
+                let y = (y - x * x.dot(&y)).normalize();
+                let matrix = na::Matrix3::from_columns(&[x, y, x.cross(&y)]);
+                na::UnitQuaternion::from_rotation_matrix(&na::Rotation3::from_matrix_unchecked(
+                    matrix,
+                ))
+            }
+            _ => return Err(format!("Invalid xyaxes '{}'", axes)),
+        }
+    } else if let Some(zaxis) = geom.attribute("zaxis") {
+        let zaxis = parse_vector(zaxis, "zaxis")?;
+        na::UnitQuaternion::rotation_between(&Vector::z(), &zaxis)
+            // Opposite to z, half a turn about x
+            .unwrap_or_else(|| {
+                na::UnitQuaternion::from_quaternion(na::Quaternion::new(0.0, 1.0, 0.0, 0.0))
+            })
+    } else {
+        na::UnitQuaternion::identity()
+    };
+    Ok(Isometry::from_parts(
+        na::Translation3::from(position),
+        rotation,
+    ))
+}
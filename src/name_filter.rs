@@ -0,0 +1,119 @@
+/// Include and exclude patterns for the names of the parts of a file: objects and
+/// groups in .obj files, geometries in .dae files (also known by the names and ids of
+/// the nodes that instance them) and objects in .amf files. Patterns are globs matched
+/// against the whole name, where `*` matches any text and `?` any one character.
+///
+/// A part is loaded if one of its names matches an include pattern, or if there are
+/// none, and none of its names matches an exclude pattern. The filter applies on top of
+/// `SubMeshes`.
+///
+/// # Example
+///
+/// ```rust
+/// use rs_read_trimesh::{NameFilter, TrimeshLoader};
+///
+/// let filter = NameFilter::new().include("*collision*").exclude("*_old");
+/// match TrimeshLoader::new().name_filter(filter).load("robot.obj") {
+///     Ok(mesh) => println!("Loaded mesh with {} vertices.", mesh.vertices().len()),
+///     Err(e) => eprintln!("Failed to load mesh: {}", e),
+/// }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NameFilter {
+    /// Parts are only loaded if they have a name matching one of these, unless it is empty.
+    pub include: Vec<String>,
+    /// Parts with a name matching one of these are not loaded.
+    pub exclude: Vec<String>,
+}
+
+impl NameFilter {
+    /// Creates a filter that accepts all parts.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an include pattern.
+    pub fn include(mut self, pattern: impl Into<String>) -> Self {
+        self.include.push(pattern.into());
+        self
+    }
+
+    /// Adds an exclude pattern.
+    pub fn exclude(mut self, pattern: impl Into<String>) -> Self {
+        self.exclude.push(pattern.into());
+        self
+    }
+
+    /// Whether a part known under the given names is loaded.
+    pub fn matches(&self, names: &[Option<&str>]) -> bool {
+        let any_matches = |patterns: &[String]| {
+            names
+                .iter()
+                .flatten()
+                .any(|name| patterns.iter().any(|pattern| glob_matches(pattern, name)))
+        };
+        (self.include.is_empty() || any_matches(&self.include)) && !any_matches(&self.exclude)
+    }
+}
+
+/// Matches a glob with `*` and `?` against the whole name.
+fn glob_matches(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // After a mismatch, the last `*` takes one more character and matching resumes
+    let mut star = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p + 1, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    star = Some((star_p, star_n + 1));
+                    p = star_p;
+                    n = star_n + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_matches() {
+        assert!(glob_matches("collision", "collision"));
+        assert!(!glob_matches("collision", "collision_2"));
+        assert!(glob_matches("*collision*", "base_collision_2"));
+        assert!(glob_matches("link_?", "link_3"));
+        assert!(!glob_matches("link_?", "link_12"));
+        assert!(glob_matches("*a*b", "xaxxab"));
+        assert!(!glob_matches("*a*b", "xaxxa"));
+        assert!(glob_matches("*", ""));
+        assert!(!glob_matches("?", ""));
+    }
+
+    #[test]
+    fn test_name_filter() {
+        let filter = NameFilter::new().include("*collision*").exclude("*_old");
+        assert!(filter.matches(&[Some("collision")]));
+        assert!(filter.matches(&[Some("arm"), Some("arm_collision")]));
+        assert!(!filter.matches(&[Some("collision_old")]));
+        assert!(!filter.matches(&[Some("visual")]));
+        assert!(!filter.matches(&[None]));
+
+        let filter = NameFilter::new().exclude("visual*");
+        assert!(filter.matches(&[None]));
+        assert!(!filter.matches(&[Some("visual_detail")]));
+    }
+}
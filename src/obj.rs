@@ -0,0 +1,217 @@
+use crate::error::LoadError;
+use crate::limits::Limit;
+use crate::loader::{LoadContext, Triangulation};
+use crate::mesh_data::{CornerVertices, Material, MeshData, NO_INDEX, merge_meshes};
+use crate::{Point, Real, Vector};
+use std::cell::RefCell;
+use std::io::BufRead;
+use std::path::Path;
+
+/// Function to load a TriMesh from an OBJ file. Material libraries are resolved
+/// relative to the directory of the file. When loading from a reader, they can not
+/// be resolved, so materials are only named by their index.
+pub(crate) fn load_trimesh_from_obj<R: BufRead>(
+    mut reader: R,
+    context: &mut LoadContext,
+) -> Result<MeshData, LoadError> {
+    let directory = context
+        .file_path()
+        .map(|path| Path::new(path).parent().unwrap_or(Path::new("")));
+
+    // Load the OBJ file using the `tobj` library, the material libraries are recorded
+    // for the cache
+    let libraries = RefCell::new(Vec::new());
+    let loaded =
+        tobj::load_obj_buf(
+            &mut reader,
+            &tobj_options(context),
+            |material_path| match directory {
+                Some(directory) => {
+                    let library = directory.join(material_path);
+                    libraries.borrow_mut().push(library.clone());
+                    tobj::load_mtl(library)
+                }
+                None => Err(tobj::LoadError::OpenFileFailed),
+            },
+        );
+    for library in libraries.into_inner() {
+        context.add_dependency(library);
+    }
+    mesh_from_obj(loaded, context.name(), context)
+}
+
+fn tobj_options(context: &LoadContext) -> tobj::LoadOptions {
+    tobj::LoadOptions {
+        // tobj splits polygons into fans itself, the other policies need the polygons
+        triangulate: context.options.triangulation == Triangulation::Fan,
+        ignore_points: true,
+        ignore_lines: true,
+        ..Default::default()
+    }
+}
+
+fn mesh_from_obj(
+    loaded: tobj::LoadResult,
+    obj_file_path: &str,
+    context: &mut LoadContext,
+) -> Result<MeshData, LoadError> {
+    let (models, materials) =
+        loaded.map_err(|e| format!("Failed to load .obj file '{}': {}", obj_file_path, e))?;
+
+    // tobj reads everything at once, so the counts can only be checked before conversion
+    let limits = &context.options.limits;
+    let count = |count: fn(&tobj::Mesh) -> usize| -> usize {
+        models.iter().map(|model| count(&model.mesh)).sum()
+    };
+    limits.check_count(Limit::Vertices, count(|mesh| mesh.positions.len() / 3))?;
+    let faces = count(face_count);
+    limits.check_count(Limit::Faces, faces)?;
+    context.set_total_elements(faces as u64);
+
+    // A missing or broken .mtl file is not fatal, materials are then named by their index
+    let materials: Vec<Material> = materials
+        .map(|materials| {
+            materials
+                .into_iter()
+                .map(|material| Material {
+                    diffuse: material.diffuse.map(|[r, g, b]| {
+                        [r, g, b, material.dissolve.unwrap_or(1.0)].map(|value| value as f32)
+                    }),
+                    name: material.name,
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    // Each model indexes its own vertices, so they are merged with offsets
+    let mut meshes = Vec::new();
+    for (index, model) in models.into_iter().enumerate() {
+        let names = [Some(model.name.as_str())];
+        let options = context.options;
+        if options.sub_meshes.selects(index, &names) && options.name_filter.matches(&names) {
+            meshes.push(mesh_from_model(model.mesh, &materials, context)?);
+        } else {
+            context.elements_parsed(face_count(&model.mesh) as u64)?;
+        }
+    }
+
+    Ok(merge_meshes(meshes)?)
+}
+
+/// Number of faces in a mesh, the face arities are only there if some faces are not triangles.
+fn face_count(mesh: &tobj::Mesh) -> usize {
+    match mesh.face_arities.len() {
+        0 => mesh.indices.len() / 3,
+        faces => faces,
+    }
+}
+
+fn mesh_from_model(
+    mut mesh: tobj::Mesh,
+    materials: &[Material],
+    context: &mut LoadContext,
+) -> Result<MeshData, String> {
+    // Extract vertices
+    let mut vertices: Vec<Point<Real>> = mesh
+        .positions
+        .chunks_exact(3)
+        .map(|chunk| context.frame.point(chunk[0], chunk[1], chunk[2]))
+        .collect();
+
+    // Normals and texture coordinates may have their own indices, corners that use
+    // other values than the first corner of their vertex get a copy of the vertex.
+    // When the attributes are not used, the corners are their positions.
+    if !context.attributes() {
+        mesh.normals.clear();
+        mesh.texcoords.clear();
+        mesh.vertex_color.clear();
+    }
+    let normals: Vec<Vector<Real>> = mesh
+        .normals
+        .chunks_exact(3)
+        .map(|chunk| Vector::new(chunk[0] as Real, chunk[1] as Real, chunk[2] as Real))
+        .collect();
+    let uvs: Vec<[f32; 2]> = mesh
+        .texcoords
+        .chunks_exact(2)
+        .map(|chunk| [chunk[0] as f32, chunk[1] as f32])
+        .collect();
+    let mut corners = CornerVertices::new(vertices.len());
+    let corner_indices: Vec<u32> = mesh
+        .indices
+        .iter()
+        .enumerate()
+        .map(|(corner, &position)| {
+            let normal = attribute_index(&normals, &mesh.normal_indices, corner, position);
+            let uv = attribute_index(&uvs, &mesh.texcoord_indices, corner, position);
+            corners.vertex(position, [normal, uv])
+        })
+        .collect();
+
+    // Extract indices, the face arities are only there if some faces are not triangles
+    let mut indices: Vec<[u32; 3]> = Vec::new();
+    if mesh.face_arities.is_empty() {
+        indices.extend(
+            corner_indices
+                .chunks_exact(3)
+                .map(|chunk| [chunk[0], chunk[1], chunk[2]]),
+        );
+    } else {
+        let mut start = 0;
+        for (face, &arity) in mesh.face_arities.iter().enumerate() {
+            let end = start + arity as usize;
+            let polygon = corner_indices
+                .get(start..end)
+                .ok_or_else(|| format!("Missing indices for face {}", face))?;
+            context
+                .options
+                .triangulation
+                .triangulate(polygon, face, &mut indices)?;
+            start = end;
+        }
+    }
+
+    context.elements_parsed(face_count(&mesh) as u64)?;
+
+    corners.extend(&mut vertices);
+    let mut data = MeshData::new(vertices, Vec::new());
+    if !normals.is_empty() {
+        data.normals = Some(corners.attribute(0, &normals, Vector::zeros()));
+    }
+    if !uvs.is_empty() {
+        data.uvs = Some(corners.attribute(1, &uvs, [0.0; 2]));
+    }
+
+    // Vertex colors are written on the 'v' lines, so they always follow positions
+    if mesh.vertex_color.len() == mesh.positions.len() && !mesh.vertex_color.is_empty() {
+        let mut colors: Vec<[f32; 4]> = mesh
+            .vertex_color
+            .chunks_exact(3)
+            .map(|chunk| [chunk[0] as f32, chunk[1] as f32, chunk[2] as f32, 1.0])
+            .collect();
+        corners.extend(&mut colors);
+        data.colors = Some(colors);
+    }
+
+    if let Some(material_id) = mesh.material_id {
+        let material = materials
+            .get(material_id)
+            .cloned()
+            .unwrap_or_else(|| Material::named(format!("material_{}", material_id)));
+        data.materials = vec![material];
+        data.face_materials = Some(vec![Some(0); indices.len()]);
+    }
+
+    data.indices = indices;
+    Ok(data)
+}
+
+/// Returns the index of the value of an attribute at a corner. Without separate indices,
+/// the attribute follows the positions directly.
+fn attribute_index<T>(values: &[T], indices: &[u32], corner: usize, position: u32) -> u32 {
+    match (values.is_empty(), indices.is_empty()) {
+        (true, _) => NO_INDEX,
+        (false, true) => position,
+        (false, false) => indices.get(corner).copied().unwrap_or(NO_INDEX),
+    }
+}
@@ -0,0 +1,150 @@
+use crate::TriMesh;
+use crate::cache::{CacheKey, Lookup};
+use crate::error::LoadError;
+use crate::limits::Limit;
+use crate::loader::TrimeshLoader;
+use crate::mesh_data::MeshData;
+use rayon::prelude::*;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Parallel loading of many files, with the `rayon` feature.
+impl TrimeshLoader {
+    /// Loads the meshes of all given files in parallel on the rayon thread pool, and
+    /// returns the results in the same order. Paths to the same file and files with the
+    /// same content are only parsed once, their results share the same `Arc`.
+    ///
+    /// The progress callback, if any, receives the reports of all files as they come.
+    pub fn load_parallel<S: AsRef<str> + Sync>(
+        &self,
+        file_paths: &[S],
+    ) -> Vec<Result<Arc<TriMesh>, LoadError>> {
+        let loader = &self.clone().positions_only();
+        // Paths to the same file are read once
+        let mut file_ids = HashMap::new();
+        let mut files: Vec<Cow<str>> = Vec::new();
+        let file_of_path: Vec<Result<usize, LoadError>> = file_paths
+            .iter()
+            .map(|file_path| {
+                let file_path = loader.resolve(file_path.as_ref())?;
+                let canonical = fs::canonicalize(&*file_path)
+                    .map_err(|err| format!("Could not open '{}': {}", file_path, err))?;
+                Ok(*file_ids.entry(canonical).or_insert_with(|| {
+                    files.push(file_path);
+                    files.len() - 1
+                }))
+            })
+            .collect();
+        // The cache is looked up first, so that cached files are not read
+        let lookups: Vec<Lookup> = files
+            .par_iter()
+            .map(|file_path| loader.cache_lookup(file_path))
+            .collect();
+        let contents: Vec<Option<Result<Vec<u8>, LoadError>>> = files
+            .par_iter()
+            .zip(&lookups)
+            .map(|(file_path, lookup)| match lookup {
+                Lookup::Hit(..) => None,
+                Lookup::Miss(_) => Some(loader.read_file(file_path)),
+            })
+            .collect();
+
+        // Files with the same content are parsed once
+        let mut mesh_ids = HashMap::new();
+        let mut jobs: Vec<Job> = Vec::new();
+        let mesh_of_file: Vec<Result<usize, LoadError>> = files
+            .iter()
+            .zip(lookups)
+            .zip(&contents)
+            .map(|((file_path, lookup), content)| {
+                let key = match lookup {
+                    Lookup::Hit(mesh, _) => {
+                        jobs.push(Job::Cached(mesh));
+                        return Ok(jobs.len() - 1);
+                    }
+                    Lookup::Miss(key) => key,
+                };
+                let bytes = match content {
+                    Some(content) => content.as_ref().map_err(LoadError::clone)?,
+                    None => return Err("The file was not read".into()),
+                };
+                let content_key = (content_key(file_path), bytes.as_slice());
+                Ok(*mesh_ids.entry(content_key).or_insert_with(|| {
+                    jobs.push(Job::Parse(file_path, bytes, key));
+                    jobs.len() - 1
+                }))
+            })
+            .collect();
+        let meshes: Vec<Result<Arc<TriMesh>, LoadError>> = jobs
+            .into_par_iter()
+            .map(|job| {
+                let mesh = match job {
+                    Job::Cached(mesh) => mesh,
+                    Job::Parse(file_path, bytes, key) => {
+                        let tracker = loader.tracker(Some(bytes.len() as u64));
+                        tracker.update(|progress| progress.bytes_read = bytes.len() as u64);
+                        let loaded = loader.load_mesh_data_bytes_recentered(
+                            bytes,
+                            Some(file_path),
+                            tracker,
+                        )?;
+                        loader.cache_store(key, loaded).0
+                    }
+                };
+                Ok(Arc::new(mesh.into_trimesh(loader.options().flags)?))
+            })
+            .collect();
+
+        file_of_path
+            .into_iter()
+            .map(|file| meshes[mesh_of_file[file?].clone()?].clone())
+            .collect()
+    }
+
+    fn read_file(&self, file_path: &str) -> Result<Vec<u8>, LoadError> {
+        let limits = &self.options().limits;
+        let file = File::open(file_path)
+            .map_err(|err| format!("Could not open '{}': {}", file_path, err))?;
+        let size = file
+            .metadata()
+            .map_err(|err| format!("Could not open '{}': {}", file_path, err))?
+            .len();
+        limits.check(Limit::FileSize, size)?;
+
+        let mut bytes = Vec::new();
+        limits
+            .bounded(Limit::FileSize, file)
+            .read_to_end(&mut bytes)
+            .map_err(|err| format!("Could not read '{}': {}", file_path, err))?;
+        limits.check_count(Limit::FileSize, bytes.len())?;
+        Ok(bytes)
+    }
+}
+
+/// A mesh to build the `TriMesh` of: a cached one, or the content of a file to parse,
+/// with the key to cache it under.
+enum Job<'a> {
+    Cached(MeshData),
+    Parse(&'a str, &'a [u8], Option<CacheKey>),
+}
+
+/// What besides the content decides the loaded mesh: the extension selects the loader,
+/// and .obj and .dae files refer to other files relative to their directory.
+fn content_key(file_path: &str) -> (Option<String>, Option<PathBuf>) {
+    let path = Path::new(file_path);
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_lowercase);
+    let directory = match extension.as_deref() {
+        Some("obj" | "dae") => fs::canonicalize(path)
+            .ok()
+            .and_then(|path| path.parent().map(Path::to_path_buf)),
+        _ => None,
+    };
+    (extension, directory)
+}
@@ -0,0 +1,686 @@
+use crate::error::LoadError;
+use crate::limits::{Limit, LimitExceeded, Limits};
+use crate::loader::LoadContext;
+use crate::mesh_data::MeshData;
+use crate::{Point, Real, Vector};
+use ply_rs_bw::parser::{Parser, Reader};
+use ply_rs_bw::ply::{
+    Addable, ElementDef, Encoding, Header, Property, PropertyAccess, PropertyAccessResult,
+    PropertyDef, PropertyType,
+};
+use std::collections::HashMap;
+use std::io::BufRead;
+
+/// Property names accepted for normals, colors and texture coordinates, in order of preference.
+const NORMAL_NAMES: [&[&str]; 3] = [
+    &["nx", "normal_x"],
+    &["ny", "normal_y"],
+    &["nz", "normal_z"],
+];
+const COLOR_NAMES: [&[&str]; 4] = [
+    &["red", "r", "diffuse_red"],
+    &["green", "g", "diffuse_green"],
+    &["blue", "b", "diffuse_blue"],
+    &["alpha", "a", "diffuse_alpha"],
+];
+const UV_NAMES: [&[&str]; 2] = [
+    &["u", "s", "texture_u", "texture_s"],
+    &["v", "t", "texture_v", "texture_t"],
+];
+
+/// Names of the elements and properties that hold the geometry of .ply files. Each is a
+/// list of names tried in order, the first one present in the file is used. The
+/// defaults accept the names used by common exporters: `vertex` or `vertices`
+/// elements with `x`, `px`, `pos_x` or `position_x` coordinates, and `face`, `faces`
+/// or `polygon` elements with `vertex_indices`, `vertex_index` or `indices` lists.
+/// Triangle strips are read from `tristrips` elements, with the same index list names.
+///
+/// # Example
+///
+/// ```rust
+/// use rs_read_trimesh::{PlySchema, TrimeshLoader};
+///
+/// // Tried before the built-in names
+/// let schema = PlySchema::new()
+///     .face_element("triangle")
+///     .coordinates("lon", "lat", "height");
+/// match TrimeshLoader::new().ply_schema(schema).load("terrain.ply") {
+///     Ok(mesh) => println!("Loaded mesh with {} vertices.", mesh.vertices().len()),
+///     Err(e) => eprintln!("Failed to load mesh: {}", e),
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlySchema {
+    /// Names of the element with the vertices.
+    pub vertex_element: Vec<String>,
+    /// Names of the element with the faces.
+    pub face_element: Vec<String>,
+    /// Names of the x, y and z coordinate properties of the vertices.
+    pub coordinates: [Vec<String>; 3],
+    /// Names of the element with the triangle strips.
+    pub tristrips_element: Vec<String>,
+    /// Names of the list property with the vertex indices of the faces and strips.
+    pub vertex_indices: Vec<String>,
+}
+
+impl Default for PlySchema {
+    fn default() -> Self {
+        let names = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+        PlySchema {
+            vertex_element: names(&["vertex", "vertices"]),
+            face_element: names(&["face", "faces", "polygon"]),
+            tristrips_element: names(&["tristrips"]),
+            coordinates: [
+                names(&["x", "px", "pos_x", "position_x"]),
+                names(&["y", "py", "pos_y", "position_y"]),
+                names(&["z", "pz", "pos_z", "position_z"]),
+            ],
+            vertex_indices: names(&["vertex_indices", "vertex_index", "indices"]),
+        }
+    }
+}
+
+impl PlySchema {
+    /// Creates a schema with the built-in names.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a name for the element with the vertices, tried before the others.
+    pub fn vertex_element(mut self, name: impl Into<String>) -> Self {
+        self.vertex_element.insert(0, name.into());
+        self
+    }
+
+    /// Adds a name for the element with the faces, tried before the others.
+    pub fn face_element(mut self, name: impl Into<String>) -> Self {
+        self.face_element.insert(0, name.into());
+        self
+    }
+
+    /// Adds a name for the element with the triangle strips, tried before the others.
+    pub fn tristrips_element(mut self, name: impl Into<String>) -> Self {
+        self.tristrips_element.insert(0, name.into());
+        self
+    }
+
+    /// Adds names for the coordinate properties, tried before the others.
+    pub fn coordinates(
+        mut self,
+        x: impl Into<String>,
+        y: impl Into<String>,
+        z: impl Into<String>,
+    ) -> Self {
+        for (names, name) in self
+            .coordinates
+            .iter_mut()
+            .zip([x.into(), y.into(), z.into()])
+        {
+            names.insert(0, name);
+        }
+        self
+    }
+
+    /// Adds a name for the list of vertex indices of the faces and strips, tried before the others.
+    pub fn vertex_indices(mut self, name: impl Into<String>) -> Self {
+        self.vertex_indices.insert(0, name.into());
+        self
+    }
+
+    /// Returns the first of the names that the header has an element for.
+    fn element<'a>(names: &'a [String], header: &Header) -> Option<&'a str> {
+        names
+            .iter()
+            .map(String::as_str)
+            .find(|name| header.elements.contains_key(*name))
+    }
+
+    /// Returns the first of the names that the element has a property for.
+    fn property<'a>(names: &'a [String], element: &ElementDef) -> Option<&'a str> {
+        names
+            .iter()
+            .map(String::as_str)
+            .find(|name| element.properties.contains_key(*name))
+    }
+
+    /// Returns the name of the index list of the faces or strips element.
+    fn indices<'a>(&'a self, element: &ElementDef) -> Result<&'a str, String> {
+        Self::property(&self.vertex_indices, element).ok_or_else(|| {
+            format!(
+                "Missing '{}' property for the {}, also looked for {}",
+                self.vertex_indices[0],
+                element.name,
+                self.vertex_indices[1..].join(", ")
+            )
+        })
+    }
+}
+
+/// Names the properties read from the payload are renamed to, one slot each: the
+/// coordinates, normals, colors and texture coordinates of the vertices.
+const SLOTS: [&str; 12] = [
+    "x", "y", "z", "nx", "ny", "nz", "red", "green", "blue", "alpha", "u", "v",
+];
+/// Name the index list of the faces and strips is renamed to.
+const INDICES: &str = "vertex_indices";
+
+/// One element of the payload with only the properties that are read. The others are
+/// dropped as they are parsed, instead of being kept in a map for each element.
+#[derive(Default)]
+struct PlyRecord {
+    slots: [Option<Property>; SLOTS.len()],
+    indices: Option<Property>,
+}
+
+impl PropertyAccess for PlyRecord {
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn set_property(&mut self, property_name: &str, property: Property) -> PropertyAccessResult {
+        if property_name == INDICES {
+            self.indices = Some(property);
+        } else if let Some(slot) = SLOTS.iter().position(|slot| *slot == property_name) {
+            self.slots[slot] = Some(property);
+        } else {
+            return PropertyAccessResult::Ignored;
+        }
+        PropertyAccessResult::Set
+    }
+}
+
+impl PlyRecord {
+    /// Returns the value of a slot as f64, so that the caller decides about the precision.
+    fn value(&self, slot: usize) -> Option<f64> {
+        self.slots[slot].as_ref().and_then(scalar_value)
+    }
+}
+
+/// What a .ply file holds, found from its header: the definitions of its elements, with
+/// the properties that are read renamed to their slot and the others to names that match
+/// none, and which elements and vertex attributes are read.
+struct PlyLayout {
+    elements: Vec<ElementDef>,
+    vertex_element: String,
+    /// Element of the faces and the original name of its index list.
+    faces: Option<(String, String)>,
+    /// Element of the triangle strips and the original name of its index list.
+    strips: Option<(String, String)>,
+    normals: bool,
+    colors: bool,
+    uvs: bool,
+}
+
+impl PlyLayout {
+    fn new<'s>(header: &'s Header, schema: &'s PlySchema) -> Result<Self, String> {
+        let vertex_element =
+            PlySchema::element(&schema.vertex_element, header).ok_or_else(|| {
+                format!(
+                    "No vertex element ({}) found in the .ply file",
+                    schema.vertex_element.join(", ")
+                )
+            })?;
+        let vertex_def = &header.elements[vertex_element];
+        // Slot of each property read, by element and property name
+        let mut renames: HashMap<(&str, &str), &str> = HashMap::new();
+
+        for (names, slot) in schema.coordinates.iter().zip(SLOTS) {
+            let name = PlySchema::property(names, vertex_def).ok_or_else(|| {
+                format!(
+                    "Missing '{}' coordinate in vertex, also looked for {}",
+                    names[0],
+                    names[1..].join(", ")
+                )
+            })?;
+            if let PropertyType::List(..) = vertex_def.properties[name].data_type {
+                return Err(format!(
+                    "Unexpected list type for vertex '{}' coordinate",
+                    name
+                ));
+            }
+            renames.insert((vertex_element, name), slot);
+        }
+        // The first scalar property among the names of each attribute
+        let attributes = NORMAL_NAMES.iter().chain(&COLOR_NAMES).chain(&UV_NAMES);
+        for (names, slot) in attributes.zip(&SLOTS[3..]) {
+            let name = names.iter().copied().find(|name| {
+                vertex_def
+                    .properties
+                    .get(*name)
+                    .is_some_and(|property| matches!(property.data_type, PropertyType::Scalar(_)))
+            });
+            if let Some(name) = name {
+                renames.insert((vertex_element, name), slot);
+            }
+        }
+        let has_slots = |slots: &[&str]| {
+            slots
+                .iter()
+                .all(|slot| renames.values().any(|renamed| renamed == slot))
+        };
+        let (normals, colors, uvs) = (
+            has_slots(&SLOTS[3..6]),
+            has_slots(&SLOTS[6..9]),
+            has_slots(&SLOTS[10..12]),
+        );
+
+        let mut list_element = |names: &'s [String]| -> Result<Option<(String, String)>, String> {
+            let Some(element) = PlySchema::element(names, header) else {
+                return Ok(None);
+            };
+            let indices = schema.indices(&header.elements[element])?;
+            renames.insert((element, indices), INDICES);
+            Ok(Some((element.to_string(), indices.to_string())))
+        };
+        let faces = list_element(&schema.face_element)?;
+        let strips = list_element(&schema.tristrips_element)?;
+
+        let elements = header
+            .elements
+            .values()
+            .map(|element| {
+                let mut renamed = ElementDef::new(element.name.clone());
+                renamed.count = element.count;
+                for (n, property) in element.properties.values().enumerate() {
+                    let name = match renames.get(&(element.name.as_str(), property.name.as_str())) {
+                        Some(slot) => slot.to_string(),
+                        None => format!("\0{}", n),
+                    };
+                    renamed
+                        .properties
+                        .add(PropertyDef::new(name, property.data_type.clone()));
+                }
+                renamed
+            })
+            .collect();
+
+        Ok(PlyLayout {
+            elements,
+            vertex_element: vertex_element.to_string(),
+            faces,
+            strips,
+            normals,
+            colors,
+            uvs,
+        })
+    }
+
+    /// Returns the original name of the index list if the element is the one named.
+    fn indices<'a>(element: &ElementDef, named: &'a Option<(String, String)>) -> Option<&'a str> {
+        match named {
+            Some((name, indices)) if *name == element.name => Some(indices),
+            _ => None,
+        }
+    }
+}
+
+/// Reads the payload of a .ply file one element at a time.
+struct PlyPayload<'a, R: BufRead> {
+    parser: Parser<PlyRecord>,
+    reader: Reader<R>,
+    encoding: Encoding,
+    line: String,
+    ply_file_path: &'a str,
+}
+
+impl<R: BufRead> PlyPayload<'_, R> {
+    /// Reads the next element, which is defined by `element`.
+    fn read(&mut self, element: &ElementDef) -> Result<PlyRecord, String> {
+        let parse_error = |err: &dyn std::fmt::Display| {
+            format!(
+                "Could not parse .ply file '{}': {}",
+                self.ply_file_path, err
+            )
+        };
+        let reader = self.reader.get_mut();
+        let record = match self.encoding {
+            Encoding::Ascii => {
+                self.line.clear();
+                let length = reader
+                    .read_line(&mut self.line)
+                    .map_err(|err| parse_error(&err))?;
+                if length == 0 {
+                    return Err(parse_error(&format!(
+                        "Unexpected end of file while reading element '{}'",
+                        element.name
+                    )));
+                }
+                self.parser.read_ascii_element(&self.line, element)
+            }
+            Encoding::BinaryBigEndian => self.parser.read_big_endian_element(reader, element),
+            Encoding::BinaryLittleEndian => self.parser.read_little_endian_element(reader, element),
+        };
+        record.map_err(|err| parse_error(&err))
+    }
+}
+
+/// Parses the header of a PLY file and finds what to read from it. The element counts
+/// declared in the header are checked before the payload is read.
+fn open_ply<'a, R: BufRead>(
+    reader: R,
+    ply_file_path: &'a str,
+    limits: &Limits,
+    schema: &PlySchema,
+) -> Result<(PlyPayload<'a, R>, PlyLayout), LoadError> {
+    let parser = Parser::<PlyRecord>::new();
+    let mut reader = Reader::new(reader);
+    let header = parser
+        .read_header(&mut reader)
+        .map_err(|err| format!("Could not parse .ply file '{}': {}", ply_file_path, err))?;
+    if let Some(vertices) = PlySchema::element(&schema.vertex_element, &header) {
+        limits.check_count(Limit::Vertices, header.elements[vertices].count)?;
+    }
+    if let Some(faces) = PlySchema::element(&schema.face_element, &header) {
+        limits.check_count(Limit::Faces, header.elements[faces].count)?;
+    }
+    // Each strips element gives at least one triangle, usually many more
+    if let Some(strips) = PlySchema::element(&schema.tristrips_element, &header) {
+        limits.check_count(Limit::Faces, header.elements[strips].count)?;
+    }
+
+    let layout = PlyLayout::new(&header, schema)?;
+    let payload = PlyPayload {
+        parser,
+        reader,
+        encoding: header.encoding,
+        line: String::new(),
+        ply_file_path,
+    };
+    Ok((payload, layout))
+}
+
+/// Capacity reserved for a count declared in a header, which may not be trusted.
+fn capacity(count: usize) -> usize {
+    count.min(1 << 20)
+}
+
+/// Function to load a TriMesh from a PLY file. The payload is streamed into the mesh
+/// one element at a time.
+pub(crate) fn load_trimesh_from_ply<R: BufRead>(
+    reader: R,
+    ply_file_path: &str,
+    context: &mut LoadContext,
+) -> Result<MeshData, LoadError> {
+    let options = context.options;
+    let schema = &options.ply_schema;
+    let (mut payload, layout) = open_ply(reader, ply_file_path, &options.limits, schema)?;
+    if layout.faces.is_none() && layout.strips.is_none() {
+        return Err(format!(
+            "No face element ({}) or triangle strips element ({}) found in the PLY file",
+            schema.face_element.join(", "),
+            schema.tristrips_element.join(", ")
+        )
+        .into());
+    }
+    let triangulation = options.triangulation;
+    let read_elements = layout.elements.iter().filter(|element| {
+        element.name == layout.vertex_element
+            || PlyLayout::indices(element, &layout.faces).is_some()
+            || PlyLayout::indices(element, &layout.strips).is_some()
+    });
+    context.set_total_elements(read_elements.map(|element| element.count as u64).sum());
+
+    let mut mesh = MeshData::default();
+    for element in &layout.elements {
+        if element.name == layout.vertex_element {
+            // Extract vertices and their attributes
+            mesh.vertices.reserve(capacity(element.count));
+            let mut normals = Vec::with_capacity(if layout.normals {
+                capacity(element.count)
+            } else {
+                0
+            });
+            let mut colors = Vec::with_capacity(if layout.colors {
+                capacity(element.count)
+            } else {
+                0
+            });
+            let mut uvs = Vec::with_capacity(if layout.uvs {
+                capacity(element.count)
+            } else {
+                0
+            });
+            for _ in 0..element.count {
+                let vertex = payload.read(element)?;
+                context.elements_parsed(1)?;
+                let value = |slot| vertex.value(slot).unwrap_or(0.0);
+                mesh.vertices
+                    .push(context.frame.point(value(0), value(1), value(2)));
+                if layout.normals {
+                    normals.push(Vector::new(
+                        value(3) as Real,
+                        value(4) as Real,
+                        value(5) as Real,
+                    ));
+                }
+                if layout.colors {
+                    colors.push([6, 7, 8, 9].map(|slot| {
+                        vertex.slots[slot]
+                            .as_ref()
+                            .and_then(color_value)
+                            .unwrap_or(1.0)
+                    }));
+                }
+                if layout.uvs {
+                    uvs.push([value(10) as f32, value(11) as f32]);
+                }
+            }
+            mesh.normals = layout.normals.then_some(normals);
+            mesh.colors = layout.colors.then_some(colors);
+            mesh.uvs = layout.uvs.then_some(uvs);
+        } else if let Some(indices_name) = PlyLayout::indices(element, &layout.faces) {
+            // Extract faces (indices)
+            mesh.indices.reserve(capacity(element.count));
+            for i in 0..element.count {
+                let face = payload.read(element)?;
+                context.elements_parsed(1)?;
+                if let Some(property) = &face.indices {
+                    let polygon = extract_face_indices(property, indices_name, i)?;
+                    triangulation.triangulate(&polygon, i, &mut mesh.indices)?;
+                }
+            }
+        } else if let Some(indices_name) = PlyLayout::indices(element, &layout.strips) {
+            // Extract triangle strips
+            for i in 0..element.count {
+                let strips = payload.read(element)?;
+                context.elements_parsed(1)?;
+                if let Some(property) = &strips.indices {
+                    let indices = extract_strip_indices(property, indices_name, i)?;
+                    for strip in indices.split(|index| index.is_none()) {
+                        let strip: Vec<u32> = strip.iter().flatten().copied().collect();
+                        add_triangle_strip(&strip, &mut mesh.indices, &options.limits)?;
+                    }
+                }
+            }
+        } else {
+            // Other elements are parsed to reach the next ones, and dropped
+            for _ in 0..element.count {
+                payload.read(element)?;
+            }
+        }
+    }
+
+    Ok(mesh)
+}
+
+/// Reads the vertices of a PLY file, which needs no faces. The elements after the
+/// vertices are not read.
+pub(crate) fn load_ply_points<R: BufRead>(
+    reader: R,
+    ply_file_path: &str,
+    context: &mut LoadContext,
+) -> Result<Vec<Point<Real>>, LoadError> {
+    let options = context.options;
+    let (mut payload, layout) =
+        open_ply(reader, ply_file_path, &options.limits, &options.ply_schema)?;
+
+    for element in &layout.elements {
+        if element.name != layout.vertex_element {
+            for _ in 0..element.count {
+                payload.read(element)?;
+            }
+            continue;
+        }
+        context.set_total_elements(element.count as u64);
+        let mut vertices = Vec::with_capacity(capacity(element.count));
+        for _ in 0..element.count {
+            let vertex = payload.read(element)?;
+            context.elements_parsed(1)?;
+            let value = |slot| vertex.value(slot).unwrap_or(0.0);
+            vertices.push(context.frame.point(value(0), value(1), value(2)));
+        }
+        return Ok(vertices);
+    }
+    Ok(Vec::new())
+}
+
+/// Returns a color channel normalized to 0.0 .. 1.0. Integer channels are
+/// divided by the maximum of their type, negative values of signed types count as 0.
+/// Floating point channels are kept as they are.
+fn color_value(property: &Property) -> Option<f32> {
+    let value = match *property {
+        Property::Float(val) => return Some(val),
+        Property::Double(val) => return Some(val as f32),
+        Property::Char(val) => val as f64 / i8::MAX as f64,
+        Property::UChar(val) => val as f64 / u8::MAX as f64,
+        Property::Short(val) => val as f64 / i16::MAX as f64,
+        Property::UShort(val) => val as f64 / u16::MAX as f64,
+        Property::Int(val) => val as f64 / i32::MAX as f64,
+        Property::UInt(val) => val as f64 / u32::MAX as f64,
+        _ => return None,
+    };
+    Some(value.max(0.0) as f32)
+}
+
+/// Returns the value of a scalar property of any numeric type.
+fn scalar_value(property: &Property) -> Option<f64> {
+    match *property {
+        Property::Char(val) => Some(val as f64),
+        Property::UChar(val) => Some(val as f64),
+        Property::Short(val) => Some(val as f64),
+        Property::UShort(val) => Some(val as f64),
+        Property::Int(val) => Some(val as f64),
+        Property::UInt(val) => Some(val as f64),
+        Property::Float(val) => Some(val as f64),
+        Property::Double(val) => Some(val),
+        _ => None,
+    }
+}
+
+// Helper function to convert the index list of a face, of any numeric type, to u32
+fn extract_face_indices(property: &Property, name: &str, i: usize) -> Result<Vec<u32>, String> {
+    match property {
+        Property::ListChar(indices_list) => extract_indices(indices_list, i),
+        Property::ListUChar(indices_list) => extract_indices(indices_list, i),
+        Property::ListShort(indices_list) => extract_indices(indices_list, i),
+        Property::ListUShort(indices_list) => extract_indices(indices_list, i),
+        Property::ListInt(indices_list) => extract_indices(indices_list, i),
+        Property::ListUInt(indices_list) => extract_indices(indices_list, i),
+        Property::ListFloat(indices_list) => {
+            let indices_list: Vec<f64> = indices_list.iter().map(|&index| index as f64).collect();
+            extract_float_indices(&indices_list, i)
+        }
+        Property::ListDouble(indices_list) => extract_float_indices(indices_list, i),
+        _ => Err(format!(
+            "Unexpected property type for '{}' in face {}, a list is expected",
+            name, i
+        )),
+    }
+}
+
+// Helper function to convert the index list of triangle strips, of any numeric type, to
+// u32 with None for the -1 that separates the strips
+fn extract_strip_indices(
+    property: &Property,
+    name: &str,
+    i: usize,
+) -> Result<Vec<Option<u32>>, String> {
+    fn widen<T: Copy + Into<f64>>(list: &[T]) -> Vec<f64> {
+        list.iter().map(|&index| index.into()).collect()
+    }
+    let indices_list = match property {
+        Property::ListChar(list) => widen(list),
+        Property::ListUChar(list) => widen(list),
+        Property::ListShort(list) => widen(list),
+        Property::ListUShort(list) => widen(list),
+        Property::ListInt(list) => widen(list),
+        Property::ListUInt(list) => widen(list),
+        Property::ListFloat(list) => widen(list),
+        Property::ListDouble(list) => list.clone(),
+        _ => {
+            return Err(format!(
+                "Unexpected property type for '{}' in strips {}, a list is expected",
+                name, i
+            ));
+        }
+    };
+    indices_list
+        .iter()
+        .enumerate()
+        .map(|(n, &index)| match index {
+            -1.0 => Ok(None),
+            _ if index.fract() == 0.0 && (0.0..=u32::MAX as f64).contains(&index) => {
+                Ok(Some(index as u32))
+            }
+            _ => Err(format!(
+                "Failed to convert index {} in strips {} to u32",
+                n, i
+            )),
+        })
+        .collect()
+}
+
+/// Adds the triangles of a strip. Every other triangle has its first two vertices swapped
+/// to keep the winding of the first one. Degenerate triangles, which join strips, are
+/// skipped but still count for the winding. The triangles are checked against the face
+/// limit before they are added.
+fn add_triangle_strip(
+    strip: &[u32],
+    triangles: &mut Vec<[u32; 3]>,
+    limits: &Limits,
+) -> Result<(), LimitExceeded> {
+    let count = triangles.len() + strip.len().saturating_sub(2);
+    limits.check_count(Limit::Faces, count)?;
+    for (k, window) in strip.windows(3).enumerate() {
+        let [a, b, c] = [window[0], window[1], window[2]];
+        if a == b || b == c || a == c {
+            continue;
+        }
+        triangles.push(if k % 2 == 0 { [a, b, c] } else { [b, a, c] });
+    }
+    Ok(())
+}
+
+// Helper function to convert floating point indices, which must be whole numbers, to u32
+fn extract_float_indices(indices_list: &[f64], i: usize) -> Result<Vec<u32>, String> {
+    indices_list
+        .iter()
+        .enumerate()
+        .map(|(n, &index)| {
+            if index.fract() == 0.0 && (0.0..=u32::MAX as f64).contains(&index) {
+                Ok(index as u32)
+            } else {
+                Err(format!(
+                    "Failed to convert index {} in face {} to u32",
+                    n, i
+                ))
+            }
+        })
+        .collect()
+}
+
+// Helper function to convert the indices of a face to u32
+fn extract_indices<T>(indices_list: &[T], i: usize) -> Result<Vec<u32>, String>
+where
+    T: TryInto<u32> + Copy,
+{
+    indices_list
+        .iter()
+        .enumerate()
+        .map(|(n, &index)| {
+            index
+                .try_into()
+                .map_err(|_| format!("Failed to convert index {} in face {} to u32", n, i))
+        })
+        .collect()
+}
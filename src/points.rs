@@ -0,0 +1,274 @@
+use crate::recenter::CoordinateFrame;
+use crate::{Point, Real};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+
+/// Function to load points from an ASCII .xyz or .pts file. Each line holds x, y, z
+/// optionally followed by other values (intensity, color), separated by whitespace or commas.
+/// Lines with fewer than three values, such as the point count header of .pts files,
+/// are skipped, as are empty lines and comments starting with '#' or '//'.
+pub(crate) fn load_points_from_xyz(
+    xyz_file_path: &str,
+    frame: &mut CoordinateFrame,
+) -> Result<Vec<Point<Real>>, String> {
+    let file = File::open(xyz_file_path)
+        .map_err(|err| format!("Could not open point file '{}': {}", xyz_file_path, err))?;
+    let reader = BufReader::new(file);
+
+    let mut points = Vec::new();
+    for (line_number, line) in reader.lines().enumerate() {
+        let line =
+            line.map_err(|err| format!("Could not read point file '{}': {}", xyz_file_path, err))?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with("//") {
+            continue;
+        }
+
+        let values: Vec<&str> = line
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|value| !value.is_empty())
+            .collect();
+        if values.len() < 3 {
+            continue;
+        }
+
+        let mut coordinates = [0.0f64; 3];
+        for (coordinate, value) in coordinates.iter_mut().zip(&values) {
+            *coordinate = value.parse().map_err(|_| {
+                format!(
+                    "Invalid coordinate '{}' on line {} of '{}'",
+                    value,
+                    line_number + 1,
+                    xyz_file_path
+                )
+            })?;
+        }
+        points.push(frame.point(coordinates[0], coordinates[1], coordinates[2]));
+    }
+
+    Ok(points)
+}
+
+/// Single field of a PCD point as declared by the FIELDS, SIZE, TYPE and COUNT header lines.
+struct PcdField {
+    name: String,
+    size: usize,
+    kind: char,
+    count: usize,
+}
+
+/// Function to load points from a PCL .pcd file in ascii or binary encoding.
+/// Points with non-finite coordinates (used by organized clouds to mark
+/// missing measurements) are skipped.
+pub(crate) fn load_points_from_pcd(
+    pcd_file_path: &str,
+    frame: &mut CoordinateFrame,
+) -> Result<Vec<Point<Real>>, String> {
+    let file = File::open(pcd_file_path)
+        .map_err(|err| format!("Could not open .pcd file '{}': {}", pcd_file_path, err))?;
+    let mut bytes = Vec::new();
+    BufReader::new(file)
+        .read_to_end(&mut bytes)
+        .map_err(|err| format!("Could not read .pcd file '{}': {}", pcd_file_path, err))?;
+
+    // Parse the header, which is ASCII and ends with the DATA line
+    let mut names = Vec::new();
+    let mut sizes = Vec::new();
+    let mut kinds = Vec::new();
+    let mut counts = Vec::new();
+    let mut point_count = None;
+    let mut data = None;
+    let mut position = 0;
+
+    while position < bytes.len() {
+        let end = bytes[position..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(bytes.len(), |offset| position + offset);
+        let line = String::from_utf8_lossy(&bytes[position..end]);
+        position = (end + 1).min(bytes.len());
+
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        let values: Vec<&str> = tokens.collect();
+        match keyword.to_uppercase().as_str() {
+            "FIELDS" => names = values.iter().map(|value| value.to_string()).collect(),
+            "SIZE" => sizes = parse_header_values(&values, "SIZE")?,
+            "TYPE" => {
+                kinds = values
+                    .iter()
+                    .filter_map(|value| value.chars().next())
+                    .collect()
+            }
+            "COUNT" => counts = parse_header_values(&values, "COUNT")?,
+            "POINTS" => point_count = parse_header_values(&values, "POINTS")?.first().copied(),
+            "DATA" => {
+                data = values.first().map(|value| value.to_lowercase());
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    let data = data.ok_or_else(|| "Missing DATA line in the .pcd header".to_string())?;
+    if counts.is_empty() {
+        counts = vec![1; names.len()];
+    }
+    if sizes.len() != names.len() || kinds.len() != names.len() || counts.len() != names.len() {
+        return Err("Inconsistent FIELDS, SIZE, TYPE and COUNT in the .pcd header".to_string());
+    }
+    let fields: Vec<PcdField> = names
+        .into_iter()
+        .zip(sizes)
+        .zip(kinds)
+        .zip(counts)
+        .map(|(((name, size), kind), count)| PcdField {
+            name,
+            size,
+            kind,
+            count,
+        })
+        .collect();
+
+    match data.as_str() {
+        "ascii" => pcd_ascii_points(&fields, &bytes[position..], frame),
+        "binary" => pcd_binary_points(&fields, &bytes[position..], point_count, frame),
+        other => Err(format!("Unsupported .pcd data encoding '{}'", other)),
+    }
+}
+
+fn parse_header_values(values: &[&str], keyword: &str) -> Result<Vec<usize>, String> {
+    values
+        .iter()
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|_| format!("Invalid {} value '{}' in the .pcd header", keyword, value))
+        })
+        .collect()
+}
+
+/// Returns the position of the x, y and z values among all values of a point.
+fn pcd_xyz_columns(fields: &[PcdField]) -> Result<[usize; 3], String> {
+    let mut columns = [0; 3];
+    for (column, name) in columns.iter_mut().zip(["x", "y", "z"]) {
+        let mut position = 0;
+        let mut found = false;
+        for field in fields {
+            if field.name == name {
+                found = true;
+                break;
+            }
+            position += field.count;
+        }
+        if !found {
+            return Err(format!("Missing '{}' field in the .pcd file", name));
+        }
+        *column = position;
+    }
+    Ok(columns)
+}
+
+fn pcd_ascii_points(
+    fields: &[PcdField],
+    data: &[u8],
+    frame: &mut CoordinateFrame,
+) -> Result<Vec<Point<Real>>, String> {
+    let columns = pcd_xyz_columns(fields)?;
+    let text = String::from_utf8_lossy(data);
+
+    let mut points = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let values: Vec<&str> = line.split_whitespace().collect();
+        if values.is_empty() {
+            continue;
+        }
+
+        let mut coordinates = [0.0f64; 3];
+        for (coordinate, &column) in coordinates.iter_mut().zip(&columns) {
+            let value = values
+                .get(column)
+                .ok_or_else(|| format!("Missing values for point {} in the .pcd file", i))?;
+            *coordinate = value
+                .parse()
+                .map_err(|_| format!("Invalid coordinate '{}' for point {}", value, i))?;
+        }
+        if coordinates.iter().all(|c| c.is_finite()) {
+            points.push(frame.point(coordinates[0], coordinates[1], coordinates[2]));
+        }
+    }
+    Ok(points)
+}
+
+fn pcd_binary_points(
+    fields: &[PcdField],
+    data: &[u8],
+    point_count: Option<usize>,
+    frame: &mut CoordinateFrame,
+) -> Result<Vec<Point<Real>>, String> {
+    // Byte offsets of x, y, z within a point record
+    let mut offsets = [None; 3];
+    let mut record_size = 0;
+    for field in fields {
+        if let Some(axis) = ["x", "y", "z"].iter().position(|&name| name == field.name) {
+            offsets[axis] = Some((record_size, field.size, field.kind));
+        }
+        record_size = field
+            .size
+            .checked_mul(field.count)
+            .and_then(|size| size.checked_add(record_size))
+            .ok_or_else(|| "The point record of the .pcd file is too large".to_string())?;
+    }
+    if record_size == 0 {
+        return Err("Empty point record in the .pcd file".to_string());
+    }
+
+    let available = data.len() / record_size;
+    let point_count = point_count.unwrap_or(available);
+    if point_count > available {
+        return Err(format!(
+            "The .pcd file declares {} points but contains data for {}",
+            point_count, available
+        ));
+    }
+
+    let mut points = Vec::with_capacity(point_count);
+    for record in data.chunks_exact(record_size).take(point_count) {
+        let mut coordinates = [0.0f64; 3];
+        for (axis, (coordinate, offset)) in coordinates.iter_mut().zip(&offsets).enumerate() {
+            let (offset, size, kind) = offset.ok_or_else(|| {
+                format!("Missing '{}' field in the .pcd file", ["x", "y", "z"][axis])
+            })?;
+            *coordinate = pcd_binary_value(&record[offset..offset + size], kind)?;
+        }
+        if coordinates.iter().all(|c| c.is_finite()) {
+            points.push(frame.point(coordinates[0], coordinates[1], coordinates[2]));
+        }
+    }
+    Ok(points)
+}
+
+/// Decodes a single little-endian value of the given PCD TYPE and SIZE.
+fn pcd_binary_value(bytes: &[u8], kind: char) -> Result<f64, String> {
+    let value = match (kind, bytes.len()) {
+        ('F', 4) => f32::from_le_bytes(bytes.try_into().unwrap()) as f64,
+        ('F', 8) => f64::from_le_bytes(bytes.try_into().unwrap()) as f64,
+        ('I', 1) => i8::from_le_bytes(bytes.try_into().unwrap()) as f64,
+        ('I', 2) => i16::from_le_bytes(bytes.try_into().unwrap()) as f64,
+        ('I', 4) => i32::from_le_bytes(bytes.try_into().unwrap()) as f64,
+        ('I', 8) => i64::from_le_bytes(bytes.try_into().unwrap()) as f64,
+        ('U', 1) => bytes[0] as f64,
+        ('U', 2) => u16::from_le_bytes(bytes.try_into().unwrap()) as f64,
+        ('U', 4) => u32::from_le_bytes(bytes.try_into().unwrap()) as f64,
+        ('U', 8) => u64::from_le_bytes(bytes.try_into().unwrap()) as f64,
+        (kind, size) => {
+            return Err(format!(
+                "Unsupported .pcd field type '{}' of size {}",
+                kind, size
+            ));
+        }
+    };
+    Ok(value)
+}
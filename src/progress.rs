@@ -0,0 +1,247 @@
+use crate::error::LoadError;
+use std::cell::Cell;
+use std::fmt;
+use std::io::{self, BufRead, Read, Seek, SeekFrom};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Error message of a load that was cancelled through its `CancellationToken`, as
+/// `LoadError::Cancelled` is displayed.
+pub const CANCELLED: &str = "Loading was cancelled";
+
+/// Bytes read between two progress reports.
+const REPORT_BYTES: u64 = 1 << 20;
+
+/// Elements parsed between two progress reports.
+const REPORT_ELEMENTS: u64 = 1 << 12;
+
+/// State of a load, as passed to the progress callback.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Progress {
+    /// Bytes read from the file or reader so far.
+    pub bytes_read: u64,
+    /// Size of the file, if known.
+    pub total_bytes: Option<u64>,
+    /// Elements converted so far. What an element is depends on the format: vertices and
+    /// faces for .ply, faces for .stl and .obj, geometries for .dae, objects for .amf.
+    pub elements: u64,
+    /// Number of elements, once the loader knows it.
+    pub total_elements: Option<u64>,
+}
+
+/// Callback receiving the `Progress` of a load. It is called on the loading thread,
+/// about every megabyte read and every few thousand elements, and once at the end.
+#[derive(Clone)]
+pub struct ProgressCallback(Arc<dyn Fn(Progress) + Send + Sync>);
+
+impl ProgressCallback {
+    pub fn new(callback: impl Fn(Progress) + Send + Sync + 'static) -> Self {
+        ProgressCallback(Arc::new(callback))
+    }
+}
+
+impl fmt::Debug for ProgressCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ProgressCallback")
+    }
+}
+
+/// Cancels loads from another thread. Clones share the same state, so one clone can be
+/// given to the load options and another kept to call `cancel` on. A cancelled load
+/// fails with `LoadError::Cancelled`.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests loads using this token to stop.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Collects the progress of one load, shared by the context and the reader.
+pub(crate) struct Tracker {
+    callback: Option<ProgressCallback>,
+    cancellation: Option<CancellationToken>,
+    progress: Cell<Progress>,
+    reported: Cell<Progress>,
+}
+
+impl Tracker {
+    pub(crate) fn new(
+        callback: Option<ProgressCallback>,
+        cancellation: Option<CancellationToken>,
+        total_bytes: Option<u64>,
+    ) -> Self {
+        let progress = Progress {
+            total_bytes,
+            ..Progress::default()
+        };
+        Tracker {
+            callback,
+            cancellation,
+            progress: Cell::new(progress),
+            reported: Cell::new(progress),
+        }
+    }
+
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.cancellation
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+    }
+
+    /// Fails if the load is cancelled.
+    pub(crate) fn check(&self) -> Result<(), LoadError> {
+        if self.is_cancelled() {
+            Err(LoadError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+
+    pub(crate) fn update(&self, change: impl FnOnce(&mut Progress)) {
+        let mut progress = self.progress.get();
+        change(&mut progress);
+        self.progress.set(progress);
+
+        let reported = self.reported.get();
+        if progress.bytes_read >= reported.bytes_read + REPORT_BYTES
+            || progress.elements >= reported.elements + REPORT_ELEMENTS
+            || (progress.total_elements.is_some()
+                && progress.total_elements == Some(progress.elements)
+                && reported.elements != progress.elements)
+        {
+            self.report();
+        }
+    }
+
+    /// Calls the callback with the current progress.
+    pub(crate) fn report(&self) {
+        let progress = self.progress.get();
+        self.reported.set(progress);
+        if let Some(callback) = &self.callback {
+            (callback.0)(progress);
+        }
+    }
+
+    /// Reports the end of a load, with everything read.
+    pub(crate) fn finish(&self) {
+        self.update(|progress| {
+            if let Some(total) = progress.total_bytes {
+                progress.bytes_read = progress.bytes_read.max(total);
+            }
+        });
+        self.report();
+    }
+}
+
+/// Reader that counts the bytes read and fails once the load is cancelled.
+pub(crate) struct TrackedReader<R> {
+    inner: R,
+    tracker: Rc<Tracker>,
+    position: u64,
+}
+
+impl<R> TrackedReader<R> {
+    pub(crate) fn new(inner: R, tracker: Rc<Tracker>) -> Self {
+        TrackedReader {
+            inner,
+            tracker,
+            position: 0,
+        }
+    }
+
+    fn advance(&mut self, amount: usize) {
+        self.position += amount as u64;
+        let position = self.position;
+        self.tracker.update(|progress| {
+            progress.bytes_read = progress.bytes_read.max(position);
+        });
+    }
+
+    fn check(&self) -> io::Result<()> {
+        if self.tracker.is_cancelled() {
+            Err(io::Error::other(CANCELLED))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<R: Read> Read for TrackedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.check()?;
+        let amount = self.inner.read(buf)?;
+        self.advance(amount);
+        Ok(amount)
+    }
+}
+
+impl<R: BufRead> BufRead for TrackedReader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.check()?;
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.inner.consume(amount);
+        self.advance(amount);
+    }
+}
+
+impl<R: Seek> Seek for TrackedReader<R> {
+    fn seek(&mut self, position: SeekFrom) -> io::Result<u64> {
+        self.position = self.inner.seek(position)?;
+        Ok(self.position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_tracked_reader() {
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let callback = {
+            let reports = reports.clone();
+            ProgressCallback::new(move |progress| reports.lock().unwrap().push(progress))
+        };
+        let token = CancellationToken::new();
+        let tracker = Rc::new(Tracker::new(
+            Some(callback),
+            Some(token.clone()),
+            Some(3 << 20),
+        ));
+
+        let mut reader = TrackedReader::new(Cursor::new(vec![0u8; 3 << 20]), tracker.clone());
+        let mut buffer = vec![0u8; 3 << 19];
+        reader.read_exact(&mut buffer).unwrap();
+        reader.read_exact(&mut buffer).unwrap();
+        assert_eq!(reports.lock().unwrap().len(), 2);
+        assert_eq!(reports.lock().unwrap()[1].bytes_read, 3 << 20);
+
+        // Seeking back does not count the bytes twice
+        reader.seek(SeekFrom::Start(0)).unwrap();
+        reader.read_exact(&mut buffer).unwrap();
+        tracker.finish();
+        assert_eq!(reports.lock().unwrap().last().unwrap().bytes_read, 3 << 20);
+
+        token.cancel();
+        let error = reader.read_exact(&mut buffer).unwrap_err();
+        assert_eq!(error.to_string(), CANCELLED);
+        assert_eq!(tracker.check(), Err(LoadError::Cancelled));
+    }
+}
@@ -0,0 +1,120 @@
+use crate::{Point, Real};
+
+/// Origin subtracted from the coordinates before they are narrowed to the precision
+/// of the mesh. Meshes in survey or site coordinates (like 500000, 4000000) lose
+/// precision in f32 unless they are moved close to zero first.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Recenter {
+    /// Keep the coordinates as they are in the file.
+    #[default]
+    None,
+    /// Subtract the center of the bounding box, computed in f64.
+    BoundingBoxCenter,
+    /// Subtract the given origin.
+    Origin([f64; 3]),
+}
+
+/// Narrows the coordinates read by the loaders to points of the mesh precision,
+/// subtracting the origin in f64 first.
+pub(crate) struct CoordinateFrame {
+    recenter: Recenter,
+    /// Origin subtracted so far. In automatic mode this is the first point, as the
+    /// bounding box is only known at the end.
+    origin: Option<[f64; 3]>,
+    mins: [f64; 3],
+    maxs: [f64; 3],
+}
+
+impl CoordinateFrame {
+    pub(crate) fn new(recenter: Recenter) -> Self {
+        CoordinateFrame {
+            recenter,
+            origin: match recenter {
+                Recenter::Origin(origin) => Some(origin),
+                _ => None,
+            },
+            mins: [f64::INFINITY; 3],
+            maxs: [f64::NEG_INFINITY; 3],
+        }
+    }
+
+    /// Returns the point relative to the origin, in the precision of the mesh.
+    pub(crate) fn point(&mut self, x: f64, y: f64, z: f64) -> Point<Real> {
+        if self.recenter == Recenter::None {
+            return Point::new(x as Real, y as Real, z as Real);
+        }
+
+        let coordinates = [x, y, z];
+        for (axis, coordinate) in coordinates.iter().enumerate() {
+            self.mins[axis] = self.mins[axis].min(*coordinate);
+            self.maxs[axis] = self.maxs[axis].max(*coordinate);
+        }
+        let origin = *self.origin.get_or_insert(coordinates);
+        Point::new(
+            (x - origin[0]) as Real,
+            (y - origin[1]) as Real,
+            (z - origin[2]) as Real,
+        )
+    }
+
+    /// Moves the points returned so far from the provisional to the final origin and
+    /// returns it, that is the offset to add to the points to get the coordinates in the file.
+    pub(crate) fn finish(self, vertices: &mut [Point<Real>]) -> [f64; 3] {
+        match (self.recenter, self.origin) {
+            (Recenter::None, _) => [0.0; 3],
+            (Recenter::Origin(origin), _) => origin,
+            (Recenter::BoundingBoxCenter, None) => [0.0; 3],
+            (Recenter::BoundingBoxCenter, Some(provisional)) => {
+                let center: [f64; 3] =
+                    std::array::from_fn(|axis| (self.mins[axis] + self.maxs[axis]) / 2.0);
+                // Both are within the extent of the mesh, so the shift is small
+                let shift: [Real; 3] =
+                    std::array::from_fn(|axis| (center[axis] - provisional[axis]) as Real);
+                for vertex in vertices {
+                    vertex.x -= shift[0];
+                    vertex.y -= shift[1];
+                    vertex.z -= shift[2];
+                }
+                center
+            }
+        }
+    }
+
+    /// Passes points that were narrowed without this frame through it, for loaders that
+    /// need to transform the points before the origin can be applied. The precision is
+    /// already that of the mesh in this case.
+    pub(crate) fn renarrow(&mut self, vertices: &mut [Point<Real>]) {
+        for vertex in vertices {
+            *vertex = self.point(vertex.x as f64, vertex.y as f64, vertex.z as f64);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bounding_box_center() {
+        let mut frame = CoordinateFrame::new(Recenter::BoundingBoxCenter);
+        let mut vertices = vec![
+            frame.point(500000.5, 4000000.25, 10.0),
+            frame.point(500002.5, 4000004.25, 12.0),
+        ];
+        let offset = frame.finish(&mut vertices);
+
+        assert_eq!(offset, [500001.5, 4000002.25, 11.0]);
+        assert_eq!(vertices[0], Point::new(-1.0, -2.0, -1.0));
+        assert_eq!(vertices[1], Point::new(1.0, 2.0, 1.0));
+    }
+
+    #[test]
+    fn test_fixed_origin() {
+        let mut frame = CoordinateFrame::new(Recenter::Origin([500000.0, 4000000.0, 0.0]));
+        let mut vertices = vec![frame.point(500000.125, 4000000.5, 3.0)];
+        let offset = frame.finish(&mut vertices);
+
+        assert_eq!(offset, [500000.0, 4000000.0, 0.0]);
+        assert_eq!(vertices[0], Point::new(0.125, 0.5, 3.0));
+    }
+}
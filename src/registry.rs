@@ -0,0 +1,404 @@
+use crate::error::LoadError;
+use crate::loader::LoadContext;
+use crate::mesh_data::MeshData;
+use crate::{amf, dae, obj, ply, stl};
+use std::fmt;
+use std::io::{BufRead, Seek};
+use std::sync::Arc;
+
+/// Number of bytes from the start of a file that are passed to `MeshFormatLoader::sniff`.
+pub const SNIFF_LENGTH: usize = 512;
+
+/// Cache version of the built-in loaders, which change with the crate.
+const BUILT_IN_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Input of a `MeshFormatLoader`, a buffered reader that can also seek (binary STL
+/// readers need to). Files and in-memory cursors both qualify.
+pub trait MeshReader: BufRead + Seek {}
+
+impl<R: BufRead + Seek> MeshReader for R {}
+
+/// A loader for one mesh format, as kept in a `FormatRegistry`.
+///
+/// # Example
+///
+/// ```rust
+/// use rs_read_trimesh::{FormatRegistry, LoadContext, LoadError, MeshData, MeshFormatLoader,
+///     MeshReader, TrimeshLoader};
+/// use std::io::Read;
+/// use std::sync::Arc;
+///
+/// /// Reads "x y z" lines, each three lines forming a triangle.
+/// struct TriangleSoup;
+///
+/// impl MeshFormatLoader for TriangleSoup {
+///     fn name(&self) -> &str {
+///         "soup"
+///     }
+///
+///     fn extensions(&self) -> &[&str] {
+///         &["soup"]
+///     }
+///
+///     fn cache_version(&self) -> Option<&str> {
+///         Some("1")
+///     }
+///
+///     fn load(
+///         &self,
+///         reader: &mut dyn MeshReader,
+///         context: &mut LoadContext,
+///     ) -> Result<MeshData, LoadError> {
+///         let mut text = String::new();
+///         reader.read_to_string(&mut text).map_err(|e| e.to_string())?;
+///
+///         let mut vertices = Vec::new();
+///         for line in text.lines() {
+///             let values: Vec<f64> = line
+///                 .split_whitespace()
+///                 .map(|value| value.parse().map_err(|_| format!("Invalid value '{}'", value)))
+///                 .collect::<Result<_, String>>()?;
+///             if let [x, y, z] = values[..] {
+///                 vertices.push(context.point(x, y, z));
+///             }
+///         }
+///         let indices = (0..vertices.len() as u32 / 3)
+///             .map(|face| [3 * face, 3 * face + 1, 3 * face + 2])
+///             .collect();
+///         Ok(MeshData::new(vertices, indices))
+///     }
+/// }
+///
+/// let mut registry = FormatRegistry::default();
+/// registry.register(Arc::new(TriangleSoup));
+///
+/// let loader = TrimeshLoader::new().registry(Arc::new(registry));
+/// match loader.load("example.soup") {
+///     Ok(mesh) => println!("Loaded mesh with {} triangles.", mesh.indices().len()),
+///     Err(e) => eprintln!("Failed to load mesh: {}", e),
+/// }
+/// ```
+pub trait MeshFormatLoader: Send + Sync {
+    /// Name of the format, also used to force it with `TrimeshLoader::format`.
+    /// A loader replaces the registered loader with the same name.
+    fn name(&self) -> &str;
+
+    /// File extensions of the format, in lower case and without the dot.
+    fn extensions(&self) -> &[&str];
+
+    /// Whether the start of a file (up to `SNIFF_LENGTH` bytes) looks like this format.
+    /// Used when the extension is not known. The default recognizes nothing.
+    fn sniff(&self, _header: &[u8]) -> bool {
+        false
+    }
+
+    /// Version of the meshes this loader produces, part of the key of the meshes cached
+    /// with `TrimeshLoader::cache_directory`. It should change whenever the loader would
+    /// load a file differently. Loads are not cached while a loader without a version,
+    /// the default, is registered.
+    fn cache_version(&self) -> Option<&str> {
+        None
+    }
+
+    /// Loads the mesh from the reader. Coordinates should be converted with
+    /// `LoadContext::point`, so that recentering applies. Scale, transform, validation
+    /// and welding are applied by the caller afterwards. Loaders of large inputs should
+    /// call `LoadContext::elements_parsed` as they go, to report progress and stop
+    /// when the load is cancelled.
+    fn load(
+        &self,
+        reader: &mut dyn MeshReader,
+        context: &mut LoadContext,
+    ) -> Result<MeshData, LoadError>;
+}
+
+/// The loaders known to a `TrimeshLoader`. The default registry holds the built-in
+/// loaders, named "stl", "ply", "obj", "dae" and "amf".
+#[derive(Clone)]
+pub struct FormatRegistry {
+    loaders: Vec<Arc<dyn MeshFormatLoader>>,
+}
+
+impl Default for FormatRegistry {
+    fn default() -> Self {
+        let mut registry = FormatRegistry::empty();
+        registry.register(Arc::new(StlLoader));
+        registry.register(Arc::new(PlyLoader));
+        registry.register(Arc::new(ObjLoader));
+        registry.register(Arc::new(DaeLoader));
+        registry.register(Arc::new(AmfLoader));
+        registry
+    }
+}
+
+impl fmt::Debug for FormatRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.loaders.iter().map(|loader| loader.name()))
+            .finish()
+    }
+}
+
+impl FormatRegistry {
+    /// Creates a registry without any loaders.
+    pub fn empty() -> Self {
+        FormatRegistry {
+            loaders: Vec::new(),
+        }
+    }
+
+    /// Adds a loader, replacing the one with the same name if there is one. Otherwise,
+    /// if several loaders claim an extension, the last registered one is used.
+    pub fn register(&mut self, loader: Arc<dyn MeshFormatLoader>) {
+        match self
+            .loaders
+            .iter()
+            .position(|registered| registered.name() == loader.name())
+        {
+            Some(position) => self.loaders[position] = loader,
+            None => self.loaders.push(loader),
+        }
+    }
+
+    /// Removes the loader with the given name and returns it.
+    pub fn unregister(&mut self, name: &str) -> Option<Arc<dyn MeshFormatLoader>> {
+        let position = self
+            .loaders
+            .iter()
+            .position(|loader| loader.name() == name)?;
+        Some(self.loaders.remove(position))
+    }
+
+    /// Returns the loader with the given name.
+    pub fn by_name(&self, name: &str) -> Option<&Arc<dyn MeshFormatLoader>> {
+        self.loaders.iter().find(|loader| loader.name() == name)
+    }
+
+    /// Returns the loader for the given file extension, ignoring case.
+    pub fn by_extension(&self, extension: &str) -> Option<&Arc<dyn MeshFormatLoader>> {
+        let extension = extension.to_lowercase();
+        self.loaders
+            .iter()
+            .rev()
+            .find(|loader| loader.extensions().contains(&extension.as_str()))
+    }
+
+    /// Returns the loader recognizing the start of a file.
+    pub fn sniff(&self, header: &[u8]) -> Option<&Arc<dyn MeshFormatLoader>> {
+        self.loaders
+            .iter()
+            .rev()
+            .find(|loader| loader.sniff(header))
+    }
+
+    /// The names and cache versions of the loaders, or None if one has no version.
+    pub(crate) fn cache_versions(&self) -> Option<Vec<(&str, &str)>> {
+        self.loaders
+            .iter()
+            .map(|loader| Some((loader.name(), loader.cache_version()?)))
+            .collect()
+    }
+
+    /// Lists the known extensions for error messages, like ".stl, .ply and .obj".
+    pub(crate) fn describe_extensions(&self) -> String {
+        let extensions: Vec<String> = self
+            .loaders
+            .iter()
+            .flat_map(|loader| loader.extensions().iter())
+            .map(|extension| format!(".{}", extension))
+            .collect();
+        match extensions.split_last() {
+            Some((last, [])) => last.clone(),
+            Some((last, rest)) => format!("{} and {}", rest.join(", "), last),
+            None => "no formats".to_string(),
+        }
+    }
+}
+
+struct StlLoader;
+
+impl MeshFormatLoader for StlLoader {
+    fn name(&self) -> &str {
+        "stl"
+    }
+
+    fn cache_version(&self) -> Option<&str> {
+        Some(BUILT_IN_VERSION)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["stl"]
+    }
+
+    /// Only ASCII files can be recognized, binary ones have a free-form header.
+    fn sniff(&self, header: &[u8]) -> bool {
+        header.starts_with(b"solid") && contains(header, b"facet")
+    }
+
+    fn load(
+        &self,
+        reader: &mut dyn MeshReader,
+        context: &mut LoadContext,
+    ) -> Result<MeshData, LoadError> {
+        stl::load_trimesh_from_stl(reader, context.name(), context)
+    }
+}
+
+struct PlyLoader;
+
+impl MeshFormatLoader for PlyLoader {
+    fn name(&self) -> &str {
+        "ply"
+    }
+
+    fn cache_version(&self) -> Option<&str> {
+        Some(BUILT_IN_VERSION)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ply"]
+    }
+
+    fn sniff(&self, header: &[u8]) -> bool {
+        header.starts_with(b"ply")
+    }
+
+    fn load(
+        &self,
+        reader: &mut dyn MeshReader,
+        context: &mut LoadContext,
+    ) -> Result<MeshData, LoadError> {
+        ply::load_trimesh_from_ply(reader, context.name(), context)
+    }
+}
+
+struct ObjLoader;
+
+impl MeshFormatLoader for ObjLoader {
+    fn name(&self) -> &str {
+        "obj"
+    }
+
+    fn cache_version(&self) -> Option<&str> {
+        Some(BUILT_IN_VERSION)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["obj"]
+    }
+
+    fn load(
+        &self,
+        reader: &mut dyn MeshReader,
+        context: &mut LoadContext,
+    ) -> Result<MeshData, LoadError> {
+        obj::load_trimesh_from_obj(reader, context)
+    }
+}
+
+struct DaeLoader;
+
+impl MeshFormatLoader for DaeLoader {
+    fn name(&self) -> &str {
+        "dae"
+    }
+
+    fn cache_version(&self) -> Option<&str> {
+        Some(BUILT_IN_VERSION)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["dae"]
+    }
+
+    fn sniff(&self, header: &[u8]) -> bool {
+        contains(header, b"<COLLADA")
+    }
+
+    fn load(
+        &self,
+        reader: &mut dyn MeshReader,
+        context: &mut LoadContext,
+    ) -> Result<MeshData, LoadError> {
+        dae::load_trimesh_from_dae(reader, context.name(), context)
+    }
+}
+
+struct AmfLoader;
+
+impl MeshFormatLoader for AmfLoader {
+    fn name(&self) -> &str {
+        "amf"
+    }
+
+    fn cache_version(&self) -> Option<&str> {
+        Some(BUILT_IN_VERSION)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["amf"]
+    }
+
+    /// Zip-compressed files can not be told apart from other archives by their start.
+    fn sniff(&self, header: &[u8]) -> bool {
+        contains(header, b"<amf")
+    }
+
+    fn load(
+        &self,
+        reader: &mut dyn MeshReader,
+        context: &mut LoadContext,
+    ) -> Result<MeshData, LoadError> {
+        amf::load_trimesh_from_amf(reader, context.name(), context)
+    }
+}
+
+fn contains(header: &[u8], pattern: &[u8]) -> bool {
+    header
+        .windows(pattern.len())
+        .any(|window| window == pattern)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe_extensions() {
+        let registry = FormatRegistry::default();
+        assert_eq!(
+            registry.describe_extensions(),
+            ".stl, .ply, .obj, .dae and .amf"
+        );
+    }
+
+    #[test]
+    fn test_sniff() {
+        let registry = FormatRegistry::default();
+        let sniffed = |header: &[u8]| registry.sniff(header).map(|loader| loader.name());
+
+        assert_eq!(sniffed(b"ply\nformat ascii 1.0\n"), Some("ply"));
+        assert_eq!(sniffed(b"solid cube\n  facet normal 0 0 1\n"), Some("stl"));
+        assert_eq!(
+            sniffed(b"<?xml version=\"1.0\"?>\n<COLLADA xmlns="),
+            Some("dae")
+        );
+        assert_eq!(sniffed(b"<?xml version=\"1.0\"?>\n<amf unit="), Some("amf"));
+        assert_eq!(sniffed(b"v 0 0 0\n"), None);
+    }
+
+    #[test]
+    fn test_register_replaces_by_name() {
+        let mut registry = FormatRegistry::default();
+        let obj = registry.unregister("obj").expect("obj is built in");
+        assert!(registry.by_extension("obj").is_none());
+
+        registry.register(obj);
+        registry.register(Arc::new(StlLoader));
+        assert_eq!(
+            format!("{:?}", registry),
+            r#"["stl", "ply", "dae", "amf", "obj"]"#
+        );
+        assert_eq!(registry.by_extension("OBJ").map(|l| l.name()), Some("obj"));
+    }
+}
@@ -0,0 +1,193 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::env;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+/// Resolves the URIs that robot descriptions use for their mesh files, like
+/// `package://my_robot/meshes/base.dae` or `model://my_model/meshes/base.dae`, to paths.
+///
+/// Each scheme has a list of root directories, searched in order. A root either
+/// contains the packages (`root/my_robot/meshes/base.dae`) or is the package itself
+/// (`root` named `my_robot`, giving `root/meshes/base.dae`). Packages can also be set
+/// one by one with `package`, which takes precedence for every scheme. `file://` URIs
+/// and plain paths are always accepted; relative ones start from the directory of the
+/// file that refers to them, or from the current directory.
+///
+/// Without a resolver set in the options, URIs are resolved with `UriResolver::from_env`.
+///
+/// # Example
+///
+/// ```rust
+/// use rs_read_trimesh::{TrimeshLoader, UriResolver};
+///
+/// let resolver = UriResolver::from_env().package("my_robot", "/opt/robots/my_robot");
+/// match TrimeshLoader::new().resolver(resolver).load("package://my_robot/meshes/base.stl") {
+///     Ok(mesh) => println!("Loaded mesh with {} vertices.", mesh.vertices().len()),
+///     Err(e) => eprintln!("Failed to load mesh: {}", e),
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct UriResolver {
+    roots: HashMap<String, Vec<PathBuf>>,
+    packages: HashMap<String, PathBuf>,
+}
+
+impl UriResolver {
+    /// Creates a resolver without roots, which only accepts `file://` URIs and paths.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a resolver with the roots of the usual environment variables:
+    /// `ROS_PACKAGE_PATH` and the `share` directories of `AMENT_PREFIX_PATH` for
+    /// `package://`, `GAZEBO_MODEL_PATH` and `GZ_SIM_RESOURCE_PATH` for `model://`.
+    /// Unset variables are skipped.
+    pub fn from_env() -> Self {
+        let mut resolver = Self::new();
+        if let Some(paths) = env::var_os("ROS_PACKAGE_PATH") {
+            resolver = resolver.roots("package", paths);
+        }
+        if let Some(prefixes) = env::var_os("AMENT_PREFIX_PATH") {
+            for prefix in env::split_paths(&prefixes) {
+                resolver = resolver.root("package", prefix.join("share"));
+            }
+        }
+        for variable in ["GAZEBO_MODEL_PATH", "GZ_SIM_RESOURCE_PATH"] {
+            if let Some(paths) = env::var_os(variable) {
+                resolver = resolver.roots("model", paths);
+            }
+        }
+        resolver
+    }
+
+    /// Adds a root directory for the URIs of the scheme, like "package" or "model",
+    /// searched after the roots added before.
+    pub fn root(mut self, scheme: impl Into<String>, directory: impl Into<PathBuf>) -> Self {
+        let roots = self.roots.entry(scheme.into()).or_default();
+        roots.push(directory.into());
+        self
+    }
+
+    /// Adds the root directories of a list in the format of the `PATH` variable, like
+    /// the value of `ROS_PACKAGE_PATH`. Empty entries are skipped.
+    pub fn roots(mut self, scheme: impl Into<String>, paths: impl AsRef<OsStr>) -> Self {
+        let scheme = scheme.into();
+        for directory in env::split_paths(&paths) {
+            if !directory.as_os_str().is_empty() {
+                self = self.root(scheme.clone(), directory);
+            }
+        }
+        self
+    }
+
+    /// Sets the directory of a package, so that `package://name/meshes/link.dae` (or
+    /// any other scheme than `file://`) resolves to `directory/meshes/link.dae`.
+    pub fn package(mut self, name: impl Into<String>, directory: impl Into<PathBuf>) -> Self {
+        self.packages.insert(name.into(), directory.into());
+        self
+    }
+
+    /// Resolves a URI or path to the path of the file. Relative paths and `file://`
+    /// URIs are joined to `directory`. For the other schemes, the first root where the
+    /// file exists is used.
+    pub fn resolve(&self, uri: &str, directory: &Path) -> Result<PathBuf, String> {
+        let Some((scheme, rest)) = uri.split_once("://") else {
+            return Ok(directory.join(uri));
+        };
+        if scheme == "file" {
+            // `file:///absolute` has an empty host, `file://relative` is accepted too. On
+            // Windows, the drive follows the slash of the empty host: `file:///C:/meshes`
+            let rest = match rest.as_bytes() {
+                [b'/', drive, b':', ..] if cfg!(windows) && drive.is_ascii_alphabetic() => {
+                    &rest[1..]
+                }
+                _ => rest,
+            };
+            return Ok(directory.join(rest));
+        }
+
+        let (package, path) = rest.split_once('/').unwrap_or((rest, ""));
+        if let Some(root) = self.packages.get(package) {
+            return Ok(root.join(path));
+        }
+        let roots = self.roots.get(scheme).ok_or_else(|| {
+            format!(
+                "Can not resolve '{}': no directories are set for {}:// URIs, and the \
+                 directory of the package '{}' is not set",
+                uri, scheme, package
+            )
+        })?;
+        roots
+            .iter()
+            .flat_map(|root| {
+                // The root contains the package, or is the package
+                let is_package = root.file_name() == Some(OsStr::new(package));
+                [Some(root.join(rest)), is_package.then(|| root.join(path))]
+            })
+            .flatten()
+            .find(|candidate| candidate.exists())
+            .ok_or_else(|| format!("Could not find '{}' in {:?}", uri, roots))
+    }
+}
+
+/// Returns the path of a file to load, resolving it if it is a URI with the resolver or,
+/// if there is none, with the one of the environment.
+pub(crate) fn resolve_path<'a>(
+    file_path: &'a str,
+    resolver: Option<&UriResolver>,
+) -> Result<Cow<'a, str>, String> {
+    if !file_path.contains("://") {
+        return Ok(Cow::Borrowed(file_path));
+    }
+    let path = match resolver {
+        Some(resolver) => resolver.resolve(file_path, Path::new(""))?,
+        None => UriResolver::from_env().resolve(file_path, Path::new(""))?,
+    };
+    path.into_os_string()
+        .into_string()
+        .map(Cow::Owned)
+        .map_err(|path| format!("The path {:?} of '{}' is not valid UTF-8", path, file_path))
+}
+
+/// Decodes the `%XX` escapes of a URI, like the space in `my%20part.dae`. Invalid
+/// escapes are kept as they are.
+pub(crate) fn percent_decode(uri: &str) -> Cow<'_, str> {
+    if !uri.contains('%') {
+        return Cow::Borrowed(uri);
+    }
+    let bytes = uri.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = bytes
+            .get(index + 1..index + 3)
+            .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match (bytes[index], escaped) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                index += 1;
+            }
+        }
+    }
+    Cow::Owned(String::from_utf8_lossy(&decoded).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percent_decode() {
+        assert_eq!(percent_decode("my%20part.dae"), "my part.dae");
+        assert_eq!(percent_decode("caf%C3%A9.dae"), "café.dae");
+        // Invalid escapes are kept
+        assert_eq!(percent_decode("100%.dae"), "100%.dae");
+        assert_eq!(percent_decode("%2x%"), "%2x%");
+    }
+}
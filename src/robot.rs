@@ -0,0 +1,150 @@
+use crate::error::LoadError;
+use crate::loader::TrimeshLoader;
+use crate::mesh_data::{MeshData, merge_meshes};
+use crate::resolver::UriResolver;
+use crate::{Isometry, Real, TriMesh, Vector};
+use roxmltree::{Document, ParsingOptions};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// What the loaders of robot descriptions share: the loader for the mesh files, and the
+/// resolver of the URIs that refer to them.
+#[derive(Clone, Debug)]
+pub(crate) struct MeshSources {
+    pub(crate) loader: TrimeshLoader,
+    pub(crate) resolver: UriResolver,
+}
+
+impl Default for MeshSources {
+    fn default() -> Self {
+        MeshSources {
+            loader: TrimeshLoader::default(),
+            resolver: UriResolver::from_env(),
+        }
+    }
+}
+
+impl MeshSources {
+    /// Loads a mesh file and scales it per axis.
+    pub(crate) fn load(
+        &self,
+        file_path: &Path,
+        scale: Option<Vector<Real>>,
+    ) -> Result<MeshData, LoadError> {
+        let file_path = file_path
+            .to_str()
+            .ok_or_else(|| format!("The path {:?} is not valid UTF-8", file_path))?;
+        let mut data = self.loader.load_mesh_data(file_path)?;
+        if let Some(scale) = scale {
+            data.scale_axes(scale);
+        }
+        Ok(data)
+    }
+
+    /// Sets the directory of a package or model.
+    #[cfg(any(feature = "urdf", feature = "sdf"))]
+    pub(crate) fn package(&mut self, name: String, directory: std::path::PathBuf) {
+        self.resolver = std::mem::take(&mut self.resolver).package(name, directory);
+    }
+
+    /// Converts the meshes of each body to `TriMesh`.
+    pub(crate) fn to_trimeshes(
+        &self,
+        bodies: BTreeMap<String, MeshData>,
+    ) -> Result<BTreeMap<String, TriMesh>, LoadError> {
+        bodies
+            .into_iter()
+            .map(|(body, mesh)| Ok((body, mesh.into_trimesh(self.loader.options().flags)?)))
+            .collect()
+    }
+
+    /// Reads a robot description.
+    pub(crate) fn read_xml(&self, file_path: &str) -> Result<String, String> {
+        fs::read_to_string(file_path)
+            .map_err(|err| format!("Could not open '{}': {}", file_path, err))
+    }
+
+    /// Parses a robot description, checking it against the XML limits and its root
+    /// element against the expected one.
+    pub(crate) fn parse_xml<'input>(
+        &self,
+        text: &'input str,
+        file_path: &str,
+        root: &str,
+    ) -> Result<Document<'input>, LoadError> {
+        let document = self
+            .loader
+            .options()
+            .limits
+            .parse_xml(text, ParsingOptions::default())
+            .map_err(|err| err.context(format_args!("Could not parse '{}'", file_path)))?;
+        let element = document.root_element();
+        if !element.has_tag_name(root) {
+            return Err(format!(
+                "'{}' is not a valid file, its root element is <{}> instead of <{}>",
+                file_path,
+                element.tag_name().name(),
+                root
+            )
+            .into());
+        }
+        Ok(document)
+    }
+}
+
+/// Collects the meshes of the bodies, merging those of the same body.
+#[derive(Default)]
+pub(crate) struct Bodies {
+    meshes: BTreeMap<String, Vec<MeshData>>,
+}
+
+impl Bodies {
+    /// Adds a mesh, placed in the frame of its body.
+    pub(crate) fn add(&mut self, body: &str, mut mesh: MeshData, pose: &Isometry<Real>) {
+        mesh.transform_by(pose);
+        self.meshes.entry(body.to_string()).or_default().push(mesh);
+    }
+
+    pub(crate) fn finish(self) -> Result<BTreeMap<String, MeshData>, LoadError> {
+        self.meshes
+            .into_iter()
+            .map(|(body, meshes)| Ok((body, merge_meshes(meshes)?)))
+            .collect()
+    }
+}
+
+/// The directory of a robot description, that relative paths in it start from.
+pub(crate) fn directory(file_path: &str) -> &Path {
+    Path::new(file_path).parent().unwrap_or(Path::new(""))
+}
+
+/// Builds a transform from a translation and roll, pitch and yaw angles in radians,
+/// about the fixed x, y and z axes in this order.
+#[cfg(any(feature = "urdf", feature = "sdf"))]
+pub(crate) fn pose(translation: Vector<Real>, rpy: Vector<Real>) -> Isometry<Real> {
+    Isometry::from_parts(
+        crate::na::Translation3::from(translation),
+        crate::na::UnitQuaternion::from_euler_angles(rpy.x, rpy.y, rpy.z),
+    )
+}
+
+/// Parses numbers separated by whitespace.
+pub(crate) fn parse_values(value: &str, name: &str) -> Result<Vec<Real>, String> {
+    value
+        .split_whitespace()
+        .map(|component| component.parse::<Real>())
+        .collect::<Result<_, _>>()
+        .map_err(|_| format!("Invalid {} '{}'", name, value))
+}
+
+/// Parses three numbers separated by whitespace.
+pub(crate) fn parse_vector(value: &str, name: &str) -> Result<Vector<Real>, String> {
+    match parse_values(value, name)?[..] {
+        [x, y, z] => Ok(Vector::new(x, y, z)),
+        _ => Err(format!(
+            "Invalid {} '{}', three values are expected",
+            name, value
+        )),
+    }
+}
@@ -0,0 +1,180 @@
+use crate::error::LoadError;
+use crate::loader::TrimeshLoader;
+use crate::mesh_data::MeshData;
+use crate::resolver::UriResolver;
+use crate::robot::{self, Bodies, MeshSources, parse_values, parse_vector};
+use crate::xml::children;
+use crate::{Isometry, Real, TriMesh, Vector, na};
+use roxmltree::Node;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Loads the collision meshes of a Gazebo SDF model, with the `sdf` feature. Each link
+/// with `<collision>` meshes becomes one `TriMesh` in the frame of the link: the
+/// `<mesh><uri>` files are loaded, scaled per axis by the `<scale>` of the mesh, placed
+/// by the `<pose>` of their `<collision>` and merged. Primitive geometries are not loaded.
+///
+/// Links of the top-level model keep their names. Links of models within a world or
+/// within another model are scoped by the model names, like `gripper::finger`.
+/// Poses are only supported relative to the parent element (without `relative_to`).
+///
+/// Mesh URIs can be relative to the SDF file, `file://` URIs, or `model://` URIs
+/// resolved through the model directories set with `package`, then through
+/// `GAZEBO_MODEL_PATH` (see `UriResolver`).
+///
+/// # Example
+///
+/// ```rust
+/// use rs_read_trimesh::SdfLoader;
+///
+/// let loader = SdfLoader::new().package("my_robot", "/opt/models/my_robot");
+/// match loader.load("model.sdf") {
+///     Ok(links) => println!("Loaded {} links.", links.len()),
+///     Err(e) => eprintln!("Failed to load model: {}", e),
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct SdfLoader {
+    sources: MeshSources,
+}
+
+impl SdfLoader {
+    /// Creates a loader with the model directories of the environment, loading the mesh
+    /// files with the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the loader used for the mesh files. Its scale and transform are applied to
+    /// each file before the scale and pose from the SDF file.
+    pub fn mesh_loader(mut self, loader: TrimeshLoader) -> Self {
+        self.sources.loader = loader;
+        self
+    }
+
+    /// Sets the directory of a model, so that `model://name/meshes/link.dae` resolves
+    /// to `root/meshes/link.dae`.
+    pub fn package(mut self, name: impl Into<String>, root: impl Into<PathBuf>) -> Self {
+        self.sources.package(name.into(), root.into());
+        self
+    }
+
+    /// Sets how the model:// and other mesh URIs are resolved, replacing the
+    /// directories set before. The default is `UriResolver::from_env`.
+    pub fn resolver(mut self, resolver: UriResolver) -> Self {
+        self.sources.resolver = resolver;
+        self
+    }
+
+    /// Loads the collision mesh of every link that has one, by scoped link name.
+    pub fn load(&self, sdf_path: &str) -> Result<BTreeMap<String, TriMesh>, LoadError> {
+        let mut loader = self.clone();
+        loader.sources.loader = loader.sources.loader.positions_only();
+        loader
+            .sources
+            .to_trimeshes(loader.load_mesh_data(sdf_path)?)
+    }
+
+    /// Loads the collision mesh of every link with its attributes, see `load`.
+    pub fn load_mesh_data(&self, sdf_path: &str) -> Result<BTreeMap<String, MeshData>, LoadError> {
+        let text = self.sources.read_xml(sdf_path)?;
+        let document = self.sources.parse_xml(&text, sdf_path, "sdf")?;
+        let directory = robot::directory(sdf_path);
+
+        let mut links = Bodies::default();
+        let sdf = document.root_element();
+        for model in children(sdf, "model") {
+            self.load_model(model, "", directory, &mut links)?;
+        }
+        for world in children(sdf, "world") {
+            for model in children(world, "model") {
+                let scope = scoped("", model)?;
+                self.load_model(model, &scope, directory, &mut links)?;
+            }
+        }
+        links.finish()
+    }
+
+    /// Loads the links of a model and of its nested models, with names in the scope.
+    fn load_model(
+        &self,
+        model: Node,
+        scope: &str,
+        directory: &Path,
+        links: &mut Bodies,
+    ) -> Result<(), LoadError> {
+        for link in children(model, "link") {
+            let name = scoped(scope, link)?;
+            for collision in children(link, "collision") {
+                let pose = pose(collision)?;
+                let geometries = children(collision, "geometry");
+                for mesh in geometries.flat_map(|geometry| children(geometry, "mesh")) {
+                    let data = self
+                        .load_mesh(mesh, directory)
+                        .map_err(|err| err.context(format_args!("Link '{}'", name)))?;
+                    links.add(&name, data, &pose);
+                }
+            }
+        }
+        for nested in children(model, "model") {
+            self.load_model(nested, &scoped(scope, nested)?, directory, links)?;
+        }
+        Ok(())
+    }
+
+    /// Loads the file of a `<mesh>` element and applies its scale.
+    fn load_mesh(&self, mesh: Node, directory: &Path) -> Result<MeshData, LoadError> {
+        let uri = child_text(mesh, "uri")
+            .ok_or_else(|| "A <mesh> has no <uri>".to_string())?
+            .trim();
+        let scale = child_text(mesh, "scale")
+            .map(|scale| parse_vector(scale, "scale"))
+            .transpose()?;
+        self.sources
+            .load(&self.sources.resolver.resolve(uri, directory)?, scale)
+    }
+}
+
+/// The name of an element within the scope of its models, separated by `::`.
+fn scoped(scope: &str, element: Node) -> Result<String, String> {
+    let name = element
+        .attribute("name")
+        .ok_or_else(|| format!("A <{}> has no name", element.tag_name().name()))?;
+    Ok(match scope {
+        "" => name.to_string(),
+        _ => format!("{}::{}", scope, name),
+    })
+}
+
+/// Parses the `<pose>` of an element, "x y z roll pitch yaw" with the angles in radians
+/// (or in degrees with `degrees="true"`), or "x y z qx qy qz qw" with
+/// `rotation_format="quat_xyzw"`. The identity if there is no pose.
+fn pose(element: Node) -> Result<Isometry<Real>, String> {
+    let Some(pose) = children(element, "pose").next() else {
+        return Ok(Isometry::identity());
+    };
+    let text = pose.text().unwrap_or_default();
+    let values = parse_values(text, "pose")?;
+    match (pose.attribute("rotation_format"), &values[..]) {
+        (Some("quat_xyzw"), &[x, y, z, qx, qy, qz, qw]) => Ok(Isometry::from_parts(
+            na::Translation3::new(x, y, z),
+            na::UnitQuaternion::from_quaternion(na::Quaternion::new(qw, qx, qy, qz)),
+        )),
+        (None | Some("euler_rpy"), &[x, y, z, roll, pitch, yaw]) => {
+            let angles = Vector::new(roll, pitch, yaw);
+            let angles = match pose.attribute("degrees") {
+                Some("true" | "1") => angles.map(Real::to_radians),
+                _ => angles,
+            };
+            Ok(robot::pose(Vector::new(x, y, z), angles))
+        }
+        // An empty pose is the identity
+        (_, []) => Ok(Isometry::identity()),
+        _ => Err(format!("Invalid pose '{}'", text.trim())),
+    }
+}
+
+/// Returns the text content of the named child element, if there is one.
+fn child_text<'a>(node: Node<'a, '_>, name: &'static str) -> Option<&'a str> {
+    children(node, name).next().and_then(|child| child.text())
+}
@@ -0,0 +1,77 @@
+use crate::error::LoadError;
+use crate::limits::Limit;
+use crate::loader::LoadContext;
+use crate::mesh_data::MeshData;
+use crate::{Point, Real};
+use std::io::{Read, Seek, SeekFrom};
+use stl_io::read_stl;
+
+/// Function to load a TriMesh from an STL file
+pub(crate) fn load_trimesh_from_stl<R: Read + Seek>(
+    mut reader: R,
+    stl_file_path: &str,
+    context: &mut LoadContext,
+) -> Result<MeshData, LoadError> {
+    // A binary file declares its triangle count, check it before reading the triangles
+    if let Some(count) = binary_triangle_count(&mut reader)
+        .map_err(|err| format!("Could not read STL file {}: {}", stl_file_path, err))?
+    {
+        context.options.limits.check(Limit::Faces, count as u64)?;
+    }
+
+    // Read the STL file into IndexedMesh
+    let stl = read_stl(&mut reader)
+        .map_err(|err| format!("Could not parse STL file {}: {}", stl_file_path, err))?;
+    context.set_total_elements(stl.faces.len() as u64);
+
+    // Extract vertices and convert them to Point<Real>
+    let vertices: Vec<Point<Real>> = stl
+        .vertices
+        .into_iter()
+        .map(|vertex| {
+            context
+                .frame
+                .point(vertex[0] as f64, vertex[1] as f64, vertex[2] as f64)
+        })
+        .collect();
+
+    // Convert face indices from `usize` to `u32` and handle any potential issues
+    let indices: Vec<[u32; 3]> = stl
+        .faces
+        .into_iter()
+        .map(|face| {
+            context.elements_parsed(1)?;
+            let mut converted_face = [0u32; 3];
+            for (i, &vertex_index) in face.vertices.iter().enumerate() {
+                converted_face[i] = vertex_index.try_into().map_err(|_| {
+                    format!(
+                        "Could not convert vertex index {} in face {:?} to u32",
+                        vertex_index, face.vertices
+                    )
+                })?;
+            }
+            Ok(converted_face)
+        })
+        .collect::<Result<Vec<[u32; 3]>, String>>()?; // Collect and propagate errors
+
+    Ok(MeshData::new(vertices, indices))
+}
+
+/// Returns the triangle count declared by a binary STL file, or None for ASCII files.
+/// Binary files may also start with "solid", the size of the file tells them apart
+/// then. The reader is rewound.
+fn binary_triangle_count<R: Read + Seek>(reader: &mut R) -> std::io::Result<Option<u32>> {
+    let start = reader.stream_position()?;
+    let length = reader.seek(SeekFrom::End(0))? - start;
+    reader.seek(SeekFrom::Start(start))?;
+    if length < 84 {
+        return Ok(None);
+    }
+
+    let mut header = [0u8; 84];
+    reader.read_exact(&mut header)?;
+    reader.seek(SeekFrom::Start(start))?;
+    let count = u32::from_le_bytes([header[80], header[81], header[82], header[83]]);
+    let binary = !header.starts_with(b"solid") || 84 + 50 * count as u64 == length;
+    Ok(binary.then_some(count))
+}
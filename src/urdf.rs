@@ -0,0 +1,131 @@
+use crate::error::LoadError;
+use crate::loader::TrimeshLoader;
+use crate::mesh_data::MeshData;
+use crate::resolver::UriResolver;
+use crate::robot::{self, Bodies, MeshSources, parse_vector};
+use crate::xml::children;
+use crate::{Isometry, Real, TriMesh, Vector};
+use roxmltree::Node;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Loads the collision meshes of a URDF robot description, with the `urdf` feature.
+/// Each link with `<collision>` meshes becomes one `TriMesh` in the frame of the link:
+/// the mesh files are loaded, scaled per axis by the `scale` of their `<mesh>`, placed by
+/// the `<origin>` of their `<collision>` and merged. Primitive geometries (boxes,
+/// cylinders, spheres) and `<visual>` elements are not loaded.
+///
+/// Mesh file names can be relative to the URDF file, `file://` URIs or `package://`
+/// URIs. Packages are resolved through the directories set with `package`, then
+/// through `ROS_PACKAGE_PATH` (see `UriResolver`), so no ROS installation is needed.
+///
+/// # Example
+///
+/// ```rust
+/// use rs_read_trimesh::{TrimeshLoader, UrdfLoader};
+///
+/// let loader = UrdfLoader::new()
+///     .package("my_robot_description", "/opt/robots/my_robot_description")
+///     .mesh_loader(TrimeshLoader::new().welding_tolerance(1e-6));
+///
+/// match loader.load("robot.urdf") {
+///     Ok(links) => {
+///         for (link, mesh) in &links {
+///             println!("Link {} has {} triangles.", link, mesh.indices().len());
+///         }
+///     }
+///     Err(e) => eprintln!("Failed to load robot: {}", e),
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct UrdfLoader {
+    sources: MeshSources,
+}
+
+impl UrdfLoader {
+    /// Creates a loader with the packages of the environment, loading the mesh files with the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the loader used for the mesh files. Its scale and transform are applied to
+    /// each file before the scale and origin from the URDF file.
+    pub fn mesh_loader(mut self, loader: TrimeshLoader) -> Self {
+        self.sources.loader = loader;
+        self
+    }
+
+    /// Sets the directory of a package, so that `package://name/meshes/link.dae`
+    /// resolves to `root/meshes/link.dae`.
+    pub fn package(mut self, name: impl Into<String>, root: impl Into<PathBuf>) -> Self {
+        self.sources.package(name.into(), root.into());
+        self
+    }
+
+    /// Sets how the package:// and other mesh URIs are resolved, replacing the
+    /// directories set before. The default is `UriResolver::from_env`.
+    pub fn resolver(mut self, resolver: UriResolver) -> Self {
+        self.sources.resolver = resolver;
+        self
+    }
+
+    /// Loads the collision mesh of every link that has one, by link name.
+    pub fn load(&self, urdf_path: &str) -> Result<BTreeMap<String, TriMesh>, LoadError> {
+        let mut loader = self.clone();
+        loader.sources.loader = loader.sources.loader.positions_only();
+        loader
+            .sources
+            .to_trimeshes(loader.load_mesh_data(urdf_path)?)
+    }
+
+    /// Loads the collision mesh of every link with its attributes, see `load`.
+    pub fn load_mesh_data(&self, urdf_path: &str) -> Result<BTreeMap<String, MeshData>, LoadError> {
+        let text = self.sources.read_xml(urdf_path)?;
+        let document = self.sources.parse_xml(&text, urdf_path, "robot")?;
+        let directory = robot::directory(urdf_path);
+
+        let mut links = Bodies::default();
+        for link in children(document.root_element(), "link") {
+            let name = link
+                .attribute("name")
+                .ok_or_else(|| format!("A <link> in '{}' has no name", urdf_path))?;
+            for collision in children(link, "collision") {
+                let origin = origin(collision)?;
+                let geometries = children(collision, "geometry");
+                for mesh in geometries.flat_map(|geometry| children(geometry, "mesh")) {
+                    let data = self
+                        .load_mesh(mesh, directory)
+                        .map_err(|err| err.context(format_args!("Link '{}'", name)))?;
+                    links.add(name, data, &origin);
+                }
+            }
+        }
+        links.finish()
+    }
+
+    /// Loads the file of a `<mesh>` element and applies its scale.
+    fn load_mesh(&self, mesh: Node, directory: &Path) -> Result<MeshData, LoadError> {
+        let filename = mesh
+            .attribute("filename")
+            .ok_or_else(|| "A <mesh> has no filename".to_string())?;
+        let scale = mesh
+            .attribute("scale")
+            .map(|scale| parse_vector(scale, "scale"))
+            .transpose()?;
+        self.sources
+            .load(&self.sources.resolver.resolve(filename, directory)?, scale)
+    }
+}
+
+/// Parses the `<origin>` of an element, the identity if there is none.
+fn origin(element: Node) -> Result<Isometry<Real>, String> {
+    let Some(origin) = children(element, "origin").next() else {
+        return Ok(Isometry::identity());
+    };
+    let attribute = |name: &str| -> Result<Vector<Real>, String> {
+        origin
+            .attribute(name)
+            .map_or(Ok(Vector::zeros()), |value| parse_vector(value, name))
+    };
+    Ok(robot::pose(attribute("xyz")?, attribute("rpy")?))
+}
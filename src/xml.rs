@@ -0,0 +1,60 @@
+use roxmltree::Node;
+use std::str::FromStr;
+
+/// Iterates over the child elements with the given name.
+pub(crate) fn children<'a, 'input>(
+    node: Node<'a, 'input>,
+    name: &'static str,
+) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children()
+        .filter(move |child| child.has_tag_name(name))
+}
+
+/// Parses the whitespace separated values in the text of an element.
+pub(crate) fn values<T: FromStr>(node: Node, what: &str) -> Result<Vec<T>, String> {
+    node.text()
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|_| format!("Invalid value '{}' in {}", value, what))
+        })
+        .collect()
+}
+
+/// Parses the text content of the named child element.
+pub(crate) fn child_value<T: FromStr>(node: Node, name: &str) -> Result<T, String> {
+    let text = node
+        .children()
+        .find(|child| child.has_tag_name(name))
+        .and_then(|child| child.text())
+        .ok_or_else(|| format!("Missing <{}> in <{}>", name, node.tag_name().name()))?;
+    text.trim()
+        .parse()
+        .map_err(|_| format!("Invalid value '{}' in <{}>", text.trim(), name))
+}
+
+pub(crate) fn required<'a>(node: Node<'a, '_>, name: &str) -> Result<&'a str, String> {
+    node.attribute(name).ok_or_else(|| {
+        format!(
+            "Missing attribute '{}' of <{}>",
+            name,
+            node.tag_name().name()
+        )
+    })
+}
+
+pub(crate) fn attribute_or<T: FromStr>(node: Node, name: &str, default: T) -> Result<T, String> {
+    match node.attribute(name) {
+        Some(value) => value.trim().parse().map_err(|_| {
+            format!(
+                "Invalid {} '{}' of <{}>",
+                name,
+                value,
+                node.tag_name().name()
+            )
+        }),
+        None => Ok(default),
+    }
+}
@@ -1,19 +1,48 @@
-use rs_read_trimesh::{load_trimesh, load_trimesh_with_flags};
+use rs_read_trimesh::{
+    CANCELLED, CancellationToken, Connectivity, DEFAULT_MAX_XML_DEPTH, Format, FormatRegistry,
+    Limit, LimitExceeded, Limits, LoadContext, LoadError, Material, MeshData, MeshFormatLoader,
+    MeshReader, NameFilter, PlySchema, Progress, Recenter, SubMeshes, Triangulation, TrimeshLoader,
+    UriResolver, Validation, load_mesh_data, load_points, load_points_recentered, load_trimesh,
+    load_trimesh_components, load_trimesh_recentered, load_trimesh_with_flags,
+    load_trimeshes_by_material,
+};
 use std::path::Path;
+use std::sync::{Arc, Mutex};
 
 #[cfg(feature = "parry13")]
-use {parry13::math::Point,
-     parry13::shape::{TriMesh, TriMeshFlags}
+use {
+    parry13::math::{Isometry, Point, Real},
+    parry13::shape::{TriMesh, TriMeshFlags},
 };
 
 #[cfg(feature = "parry17")]
-use {parry17::math::Point,
-     parry17::shape::{TriMesh, TriMeshFlags}
+use {
+    parry17::math::{Isometry, Point, Real},
+    parry17::shape::{TriMesh, TriMeshFlags},
 };
 
 #[cfg(feature = "parry_19")]
-use {parry_19::math::Point,
-     parry_19::shape::{TriMesh, TriMeshFlags}
+use {
+    parry_19::math::{Isometry, Point, Real},
+    parry_19::shape::{TriMesh, TriMeshFlags},
+};
+
+#[cfg(feature = "parry13_f64")]
+use {
+    parry13_f64::math::{Isometry, Point, Real},
+    parry13_f64::shape::{TriMesh, TriMeshFlags},
+};
+
+#[cfg(feature = "parry17_f64")]
+use {
+    parry17_f64::math::{Isometry, Point, Real},
+    parry17_f64::shape::{TriMesh, TriMeshFlags},
+};
+
+#[cfg(feature = "parry_19_f64")]
+use {
+    parry_19_f64::math::{Isometry, Point, Real},
+    parry_19_f64::shape::{TriMesh, TriMeshFlags},
 };
 
 #[test]
@@ -40,6 +69,38 @@ fn test_floats_shorts_ply() {
     run_trimesh_test(file_path);
 }
 
+#[test]
+fn test_ply_field_types() {
+    // Generated by scripts/generate_diverse_field_types.py, with the test triangle scaled
+    // by 10 and moved by 35 along x for the unsigned coordinate types
+    let types = [
+        "char", "uchar", "short", "ushort", "int", "uint", "float", "double",
+    ];
+    for vertex_type in types {
+        let offset = if vertex_type.starts_with('u') {
+            35.0
+        } else {
+            0.0
+        };
+        let expected = [
+            Point::new(-7.0 + offset, 21.0, 0.0),
+            Point::new(14.0 + offset, 42.0, 0.0),
+            Point::new(-35.0 + offset, 49.0, 0.0),
+        ];
+        for index_type in types {
+            let file_path = format!(
+                "tests/sample_files/field_types/{}_{}.ply",
+                vertex_type, index_type
+            );
+            let mesh = TrimeshLoader::new()
+                .load_mesh_data(&file_path)
+                .unwrap_or_else(|e| panic!("Failed to load {}: {}", file_path, e));
+            assert_eq!(mesh.vertices, expected, "{}", file_path);
+            assert_eq!(mesh.indices, vec![[0, 1, 2]], "{}", file_path);
+        }
+    }
+}
+
 #[test]
 fn test_object_obj() {
     let file_path = "tests/sample_files/object.obj";
@@ -58,6 +119,182 @@ fn test_collada() {
     run_trimesh_test(file_path);
 }
 
+#[test]
+fn test_collada_external_references() {
+    // The triangle is loaded for each of its two instances, the window keeps its material
+    let triangle = load_mesh_data("tests/sample_files/collada.dae").unwrap();
+    let window = load_mesh_data("tests/sample_files/materials.dae").unwrap();
+    let assembly =
+        load_mesh_data("tests/sample_files/assembly.dae").expect("Failed to load assembly");
+    assert_eq!(
+        assembly.vertices.len(),
+        2 * triangle.vertices.len() + window.vertices.len()
+    );
+    assert_eq!(assembly.vertices[..3], triangle.vertices[..]);
+    assert_eq!(assembly.materials, window.materials);
+
+    // Each instance is placed by the transforms of its nodes
+    let instances =
+        load_mesh_data("tests/sample_files/instances.dae").expect("Failed to load instances");
+    assert_eq!(instances.vertices.len(), 2 * triangle.vertices.len());
+    for (index, vertex) in triangle.vertices.iter().enumerate() {
+        let left = instances.vertices[index];
+        let right = instances.vertices[index + triangle.vertices.len()];
+        let expected_left = Point::new(vertex.x - 10.0, vertex.y, vertex.z + 5.0);
+        let expected_right = Point::new(vertex.x + 10.0, vertex.y, vertex.z + 5.0);
+        assert!((left - expected_left).norm() < 1e-5, "{:?}", left);
+        assert!((right - expected_right).norm() < 1e-5, "{:?}", right);
+    }
+
+    // Referenced geometries are selected by name
+    let loader = TrimeshLoader::new().sub_meshes(SubMeshes::Names(vec!["Triangle".to_string()]));
+    let selected = loader
+        .load_mesh_data("tests/sample_files/assembly.dae")
+        .expect("Failed to load assembly");
+    assert_eq!(selected.vertices.len(), 2 * triangle.vertices.len());
+    assert_eq!(selected.vertices[..3], triangle.vertices[..]);
+
+    // The limits apply to the referenced files
+    let limits = Limits {
+        max_file_size: Some(1500),
+        ..Limits::default()
+    };
+    let error = TrimeshLoader::new()
+        .limits(limits)
+        .load_mesh_data("tests/sample_files/assembly.dae")
+        .expect_err("The referenced file is too large");
+    assert_eq!(
+        error.limit_exceeded().map(|exceeded| exceeded.limit),
+        Some(Limit::FileSize)
+    );
+
+    let error =
+        load_mesh_data("tests/sample_files/cycle_a.dae").expect_err("The references are cyclic");
+    assert!(error.contains("Cyclic"), "{}", error);
+    let error = TrimeshLoader::new()
+        .load_mesh_data_reader(std::fs::File::open("tests/sample_files/assembly.dae").unwrap())
+        .expect_err("There is no directory to resolve the references");
+    assert!(error.to_string().contains("collada.dae"), "{}", error);
+}
+
+#[test]
+fn test_collada_local_instances() {
+    // Local instances are placed by their nodes like external ones, also through nodes
+    // of the library: the pair is loaded above, the local triangle once more below
+    let triangle = load_mesh_data("tests/sample_files/collada.dae").unwrap();
+    let mesh = load_mesh_data("tests/sample_files/local_instances.dae")
+        .expect("Failed to load local instances");
+    assert_eq!(mesh.vertices.len(), 3 * triangle.vertices.len());
+    for (instance, offset) in [5.0, 5.0, -5.0].into_iter().enumerate() {
+        for (index, vertex) in triangle.vertices.iter().enumerate() {
+            let actual = mesh.vertices[instance * triangle.vertices.len() + index];
+            let expected = Point::new(vertex.x, vertex.y, vertex.z + offset);
+            assert!((actual - expected).norm() < 1e-5, "{:?}", actual);
+        }
+    }
+
+    // Chains of local node instances count as nested elements
+    let depth = DEFAULT_MAX_XML_DEPTH as usize + 1;
+    let nodes: String = (0..depth)
+        .map(|index| {
+            format!(
+                "<node id='n{}'><instance_node url='#n{}'/></node>",
+                index,
+                index + 1
+            )
+        })
+        .collect();
+    let text = format!(
+        "<COLLADA><library_nodes>{}</library_nodes><library_visual_scenes><visual_scene>\
+         <node><instance_node url='#n0'/></node></visual_scene></library_visual_scenes></COLLADA>",
+        nodes
+    );
+    let error = TrimeshLoader::new()
+        .format(Format::Dae)
+        .load_mesh_data_reader(text.as_bytes())
+        .expect_err("The chain is too deep");
+    assert_eq!(
+        error.limit_exceeded().map(|exceeded| exceeded.limit),
+        Some(Limit::XmlDepth)
+    );
+}
+
+#[test]
+fn test_collada_controllers() {
+    // The base mesh of the morph is skinned, scaled by 2 and moved by 10 along x
+    let mesh =
+        load_mesh_data("tests/sample_files/skinned.dae").expect("Failed to load skinned mesh");
+    let expected = [
+        Point::new(10.0, 0.0, 0.0),
+        Point::new(12.0, 0.0, 0.0),
+        Point::new(10.0, 2.0, 0.0),
+    ];
+    assert_eq!(mesh.vertices, expected);
+    assert_eq!(mesh.indices, [[0, 1, 2]]);
+    let normals = mesh.normals.expect("The mesh has normals");
+    assert!(normals.iter().all(|normal| floats_match(normal.z, 1.0)));
+
+    // A singular bind shape matrix flattens the mesh, which then has no normals
+    let text = std::fs::read_to_string("tests/sample_files/skinned.dae").unwrap();
+    let text = text.replace(
+        "0 0 2 0 0 0 0 1</bind_shape_matrix>",
+        "0 0 0 0 0 0 0 1</bind_shape_matrix>",
+    );
+    let mesh = TrimeshLoader::new()
+        .format(Format::Dae)
+        .load_mesh_data_reader(text.as_bytes())
+        .expect("Failed to load flattened mesh");
+    assert_eq!(mesh.vertices[1], Point::new(12.0, 0.0, 0.0));
+    assert!(mesh.normals.is_none());
+
+    // The morph target is not loaded, even when it is selected
+    let loader = TrimeshLoader::new().sub_meshes(SubMeshes::Names(vec!["Smile".to_string()]));
+    let error = loader
+        .load_mesh_data("tests/sample_files/skinned.dae")
+        .expect_err("The target is not loaded");
+    assert!(error.to_string().contains("no mesh"), "{}", error);
+}
+
+#[test]
+fn test_amf() {
+    let file_path = "tests/sample_files/amf.amf";
+    run_trimesh_test(file_path);
+}
+
+#[test]
+fn test_amf_zip() {
+    let file_path = "tests/sample_files/amf_zip.amf";
+    run_trimesh_test(file_path);
+}
+
+#[test]
+fn test_amf_constellation() {
+    let file_path = "tests/sample_files/constellation.amf";
+
+    // Units are inches, the second instance is shifted by 2 inches and rotated by 90 degrees.
+    let expected_vertices = [
+        Point::new(0.0, 0.0, 0.0),
+        Point::new(25.4, 0.0, 0.0),
+        Point::new(0.0, 25.4, 0.0),
+        Point::new(50.8, 0.0, 0.0),
+        Point::new(50.8, 25.4, 0.0),
+        Point::new(25.4, 0.0, 0.0),
+    ];
+
+    let mesh = load_trimesh_with_flags(file_path, 1.0, TriMeshFlags::empty())
+        .unwrap_or_else(|e| panic!("Failed to load TriMesh from {}: {}", file_path, e));
+    assert_eq!(mesh.vertices().len(), expected_vertices.len());
+    for (actual, expected) in mesh.vertices().iter().zip(expected_vertices.iter()) {
+        assert!(
+            (actual - expected).norm() < 1e-4,
+            "Vertex mismatch: expected {:?}, found {:?}",
+            expected,
+            actual
+        );
+    }
+    assert_eq!(mesh.indices(), &[[0, 1, 2], [3, 4, 5]]);
+}
+
 #[test]
 fn test_collada_robot() {
     let expected_vertices = [
@@ -98,15 +335,15 @@ fn test_collada_robot() {
         0.0295452, 0.153431, -0.04, 0.0295452, 0.186569,
     ];
     let expected_indices = [
-        0, 1, 2, 1, 3, 2, 4, 5, 6, 5, 7, 6, 8, 9, 10, 9, 11, 10, 12, 13, 14, 13, 15, 14, 16, 17, 18,
-        17, 19, 18, 20, 21, 22, 21, 23, 22, 29, 24, 26, 25, 26, 27, 28, 25, 27, 27, 26, 24, 31, 32,
-        30, 33, 34, 35, 36, 38, 43, 42, 43, 41, 40, 42, 41, 39, 40, 41, 43, 38, 41, 36, 37, 38, 44,
-        46, 48, 47, 48, 46, 50, 51, 47, 46, 50, 47, 49, 46, 45, 44, 45, 46, 54, 52, 57, 57, 55, 54,
-        57, 58, 55, 57, 52, 56, 56, 52, 59, 52, 53, 59, 60, 61, 62, 61, 63, 62, 64, 65, 66, 65, 67,
-        66, 68, 69, 70, 69, 71, 70, 72, 73, 74, 73, 75, 74, 76, 77, 78, 77, 79, 78, 80, 81, 82, 81,
-        83, 82, 84, 85, 86, 85, 87, 86, 88, 89, 90, 89, 91, 90, 99, 92, 96, 95, 96, 92, 94, 95, 92,
-        93, 98, 94, 97, 98, 93, 92, 93, 94, 107, 100, 104, 103, 104, 100, 102, 103, 100, 101, 106,
-        102, 105, 106, 101, 100, 101, 102,
+        0, 1, 2, 1, 3, 2, 4, 5, 6, 5, 7, 6, 8, 9, 10, 9, 11, 10, 12, 13, 14, 13, 15, 14, 16, 17,
+        18, 17, 19, 18, 20, 21, 22, 21, 23, 22, 29, 24, 26, 25, 26, 27, 28, 25, 27, 27, 26, 24, 31,
+        32, 30, 33, 34, 35, 36, 38, 43, 42, 43, 41, 40, 42, 41, 39, 40, 41, 43, 38, 41, 36, 37, 38,
+        44, 46, 48, 47, 48, 46, 50, 51, 47, 46, 50, 47, 49, 46, 45, 44, 45, 46, 54, 52, 57, 57, 55,
+        54, 57, 58, 55, 57, 52, 56, 56, 52, 59, 52, 53, 59, 60, 61, 62, 61, 63, 62, 64, 65, 66, 65,
+        67, 66, 68, 69, 70, 69, 71, 70, 72, 73, 74, 73, 75, 74, 76, 77, 78, 77, 79, 78, 80, 81, 82,
+        81, 83, 82, 84, 85, 86, 85, 87, 86, 88, 89, 90, 89, 91, 90, 99, 92, 96, 95, 96, 92, 94, 95,
+        92, 93, 98, 94, 97, 98, 93, 92, 93, 94, 107, 100, 104, 103, 104, 100, 102, 103, 100, 101,
+        106, 102, 105, 106, 101, 100, 101, 102,
     ];
 
     let file_path = "tests/sample_files/robot.dae";
@@ -134,21 +371,21 @@ fn test_collada_robot() {
             for (i, chunk) in expected_vertices.chunks(3).enumerate() {
                 let actual = &actual_vertices[i];
                 assert!(
-                    (actual.x - chunk[0]).abs() < f32::EPSILON,
+                    (actual.x - chunk[0]).abs() < Real::EPSILON,
                     "Vertex X-coordinate mismatch at index {}: expected {}, found {}",
                     i,
                     chunk[0],
                     actual.x
                 );
                 assert!(
-                    (actual.y - chunk[1]).abs() < f32::EPSILON,
+                    (actual.y - chunk[1]).abs() < Real::EPSILON,
                     "Vertex Y-coordinate mismatch at index {}: expected {}, found {}",
                     i,
                     chunk[1],
                     actual.y
                 );
                 assert!(
-                    (actual.z - chunk[2]).abs() < f32::EPSILON,
+                    (actual.z - chunk[2]).abs() < Real::EPSILON,
                     "Vertex Z-coordinate mismatch at index {}: expected {}, found {}",
                     i,
                     chunk[2],
@@ -185,7 +422,1426 @@ fn test_collada_robot() {
             panic!("Failed to load TriMesh from {}: {}", file_path, e);
         }
     }
+}
+
+#[test]
+fn test_attributes_ply() {
+    let file_path = "tests/sample_files/attributes.ply";
+    run_trimesh_test(file_path);
+
+    let mesh = load_mesh_data(file_path)
+        .unwrap_or_else(|e| panic!("Failed to load mesh data from {}: {}", file_path, e));
+    let normals = mesh.normals.expect("Normals are missing");
+    assert!(
+        normals.iter().all(|n| n.z == 1.0),
+        "Unexpected normals {:?}",
+        normals
+    );
+    assert_eq!(
+        mesh.colors,
+        Some(vec![
+            [1.0, 0.0, 0.0, 1.0],
+            [0.0, 1.0, 0.0, 1.0],
+            [0.0, 0.0, 1.0, 1.0]
+        ])
+    );
+    assert_eq!(mesh.uvs, Some(vec![[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]]));
+    assert_eq!(mesh.face_materials, None);
+}
+
+#[test]
+fn test_mesh_data_obj_normals() {
+    let mesh = load_mesh_data("tests/sample_files/object.obj").expect("Failed to load mesh data");
+    let normals = mesh.normals.expect("Normals are missing");
+    let expected_z: [Real; 3] = [Real::atan2(1.0, 0.0), 0.927295, 0.643501];
+    assert_eq!(normals.len(), expected_z.len());
+    for (normal, z) in normals.iter().zip(expected_z) {
+        assert!(floats_match(normal.z, z), "Unexpected normal {:?}", normal);
+    }
+    assert_eq!(mesh.colors, None);
+    assert_eq!(mesh.uvs, None);
+}
+
+#[test]
+fn test_mesh_data_collada_normals() {
+    let file_path = "tests/sample_files/robot.dae";
+    let mesh = load_mesh_data(file_path).expect("Failed to load mesh data");
+    let trimesh = load_trimesh_with_flags(file_path, 1.0, TriMeshFlags::empty())
+        .expect("Failed to load TriMesh");
+
+    // The mesh data must carry exactly the geometry of the TriMesh
+    assert_eq!(mesh.vertices[..], trimesh.vertices()[..]);
+    assert_eq!(mesh.indices[..], trimesh.indices()[..]);
+
+    let normals = mesh.normals.expect("Normals are missing");
+    assert_eq!(normals.len(), mesh.vertices.len());
+    assert!(
+        normals.iter().all(|n| n.norm() > 0.1),
+        "Every vertex is expected to have a normal"
+    );
+}
+
+#[test]
+fn test_mesh_data_split_corners() {
+    // Each corner of the cube has three normals, one per face, so it is split in three
+    for file_path in ["tests/sample_files/cube.obj", "tests/sample_files/cube.dae"] {
+        let mesh = load_mesh_data(file_path).expect("Failed to load mesh data");
+        assert_eq!(mesh.vertices.len(), 24, "{}", file_path);
+        let mut corners = mesh.vertices.clone();
+        corners.sort_by(|a, b| {
+            a.coords
+                .as_slice()
+                .partial_cmp(b.coords.as_slice())
+                .unwrap()
+        });
+        corners.dedup();
+        assert_eq!(corners.len(), 8, "{}", file_path);
+        let normals = mesh.normals.expect("Normals are missing");
+        for [a, b, c] in mesh.indices {
+            let [a, b, c] = [a, b, c].map(|index| index as usize);
+            let face = (mesh.vertices[b] - mesh.vertices[a])
+                .cross(&(mesh.vertices[c] - mesh.vertices[a]))
+                .normalize();
+            assert_eq!(normals[a], face, "{}", file_path);
+            assert_eq!(normals[b], face, "{}", file_path);
+            assert_eq!(normals[c], face, "{}", file_path);
+        }
+
+        // The TriMesh shares the corners between the faces, as the file does
+        let mesh = load_trimesh_with_flags(file_path, 1.0, TriMeshFlags::empty())
+            .expect("Failed to load mesh");
+        assert_eq!(mesh.vertices().len(), 8, "{}", file_path);
+        assert_eq!(mesh.indices().len(), 12, "{}", file_path);
+    }
+}
+
+#[test]
+fn test_mesh_data_ply_integer_colors() {
+    let ply = "ply
+format ascii 1.0
+element vertex 3
+property float x
+property float y
+property float z
+property char red
+property short green
+property int blue
+property uint alpha
+element face 1
+property list uchar int vertex_indices
+end_header
+0 0 0 127 0 -5 4294967295
+1 0 0 0 32767 2147483647 0
+0 1 0 -128 0 0 0
+3 0 1 2
+";
+    let mesh = TrimeshLoader::new()
+        .format("ply")
+        .load_mesh_data_reader(ply.as_bytes())
+        .expect("Failed to load mesh data");
+    assert_eq!(
+        mesh.colors,
+        Some(vec![
+            [1.0, 0.0, 0.0, 1.0],
+            [0.0, 1.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 0.0]
+        ])
+    );
+}
+
+#[test]
+fn test_points_ply() {
+    run_points_test("tests/sample_files/points.ply");
+}
+
+#[test]
+fn test_points_xyz() {
+    run_points_test("tests/sample_files/points.xyz");
+}
+
+#[test]
+fn test_points_pts() {
+    run_points_test("tests/sample_files/points.pts");
+}
+
+#[test]
+fn test_points_pcd_ascii() {
+    run_points_test("tests/sample_files/points_ascii.pcd");
+}
+
+#[test]
+fn test_points_pcd_binary() {
+    run_points_test("tests/sample_files/points_binary.pcd");
+}
+
+#[test]
+fn test_points_from_mesh() {
+    run_points_test("tests/sample_files/stl.stl");
+}
+
+#[test]
+fn test_split_by_material_obj() {
+    let parts = load_trimeshes_by_material("tests/sample_files/materials.obj", 1.0)
+        .expect("Failed to load meshes by material");
+    assert_eq!(parts.len(), 2);
+
+    let (frame, frame_mesh) = &parts[0];
+    assert_eq!(
+        frame,
+        &Some(Material {
+            name: "frame".to_string(),
+            diffuse: Some([0.8, 0.4, 0.1, 1.0]),
+        })
+    );
+    assert_eq!(frame_mesh.indices().len(), 2);
+    assert_eq!(frame_mesh.vertices().len(), 4);
+
+    let (glass, glass_mesh) = &parts[1];
+    assert_eq!(
+        glass,
+        &Some(Material {
+            name: "glass".to_string(),
+            diffuse: Some([0.2, 0.6, 1.0, 0.25]),
+        })
+    );
+    assert_eq!(glass_mesh.indices().len(), 1);
+    assert_eq!(glass_mesh.vertices().len(), 3);
+}
+
+#[test]
+fn test_split_by_material_collada() {
+    let parts = load_trimeshes_by_material("tests/sample_files/materials.dae", 1.0)
+        .expect("Failed to load meshes by material");
+    assert_eq!(parts.len(), 2);
+
+    // The bound symbol resolves to the material and its effect
+    let (frame, frame_mesh) = &parts[0];
+    assert_eq!(
+        frame,
+        &Some(Material {
+            name: "frame".to_string(),
+            diffuse: Some([0.8, 0.4, 0.1, 1.0]),
+        })
+    );
+    assert_eq!(frame_mesh.indices().len(), 2);
+
+    // The unbound symbol is kept as name
+    let (glass, glass_mesh) = &parts[1];
+    assert_eq!(glass, &Some(Material::named("glass_symbol")));
+    assert_eq!(glass_mesh.indices().len(), 1);
+}
+
+#[test]
+fn test_split_by_material_without_materials() {
+    let parts = load_trimeshes_by_material("tests/sample_files/stl.stl", 1.0)
+        .expect("Failed to load meshes by material");
+    assert_eq!(parts.len(), 1);
+    assert_eq!(parts[0].0, None);
+    assert!(verify_trimesh_content(&parts[0].1));
+}
+
+#[test]
+fn test_components_stl() {
+    let components = load_trimesh_components(
+        "tests/sample_files/two_bodies.stl",
+        1.0,
+        Connectivity::SharedVertex,
+    )
+    .expect("Failed to load components");
+    assert_eq!(components.len(), 2);
+
+    assert_eq!(components[0].mesh.indices().len(), 4);
+    assert_eq!(components[0].mins, Point::new(0.0, 0.0, 0.0));
+    assert_eq!(components[0].maxs, Point::new(1.0, 1.0, 1.0));
+    assert!(floats_match(components[0].volume, 1.0 / 6.0));
+
+    assert_eq!(components[1].mesh.indices().len(), 4);
+    assert_eq!(components[1].mins, Point::new(10.0, 0.0, 0.0));
+    assert_eq!(components[1].maxs, Point::new(12.0, 2.0, 2.0));
+    assert!(floats_match(components[1].volume, 8.0 / 6.0));
+}
+
+#[cfg(any(
+    feature = "parry13_f64",
+    feature = "parry17_f64",
+    feature = "parry_19_f64"
+))]
+#[test]
+fn test_double_precision() {
+    for file_path in [
+        "tests/sample_files/large_coordinates.ply",
+        "tests/sample_files/large_coordinates.obj",
+        "tests/sample_files/large_coordinates.dae",
+    ] {
+        let mesh = load_trimesh_with_flags(file_path, 1.0, TriMeshFlags::empty())
+            .expect("Failed to load TriMesh");
+        let first = mesh.vertices()[0];
+        assert_eq!(
+            first,
+            Point::new(500000.001, 4000000.002, 100.003),
+            "{}",
+            file_path
+        );
+    }
+}
+
+#[test]
+fn test_recenter_bounding_box() {
+    for file_path in [
+        "tests/sample_files/large_coordinates.ply",
+        "tests/sample_files/large_coordinates.obj",
+        "tests/sample_files/large_coordinates.dae",
+    ] {
+        let (mesh, offset) = load_trimesh_recentered(file_path, 1.0, Recenter::BoundingBoxCenter)
+            .expect("Failed to load TriMesh");
+
+        let expected_offset = [500000.501, 4000000.502, 100.003];
+        for (actual, expected) in offset.iter().zip(expected_offset) {
+            assert!(
+                (actual - expected).abs() < 1e-9,
+                "{}: {:?}",
+                file_path,
+                offset
+            );
+        }
+
+        // The small coordinates keep the millimeters even in f32
+        let expected = [(-0.5, -0.5), (0.5, -0.5), (-0.5, 0.5)];
+        for (vertex, (x, y)) in mesh.vertices().iter().zip(expected) {
+            assert!((vertex.x - x).abs() < 1e-4, "{}: {:?}", file_path, vertex);
+            assert!((vertex.y - y).abs() < 1e-4, "{}: {:?}", file_path, vertex);
+            assert!(vertex.z.abs() < 1e-4, "{}: {:?}", file_path, vertex);
+        }
+    }
+}
+
+#[test]
+fn test_recenter_points_origin() {
+    let (points, offset) = load_points_recentered(
+        "tests/sample_files/large_coordinates.ply",
+        0.001,
+        Recenter::Origin([500000.0, 4000000.0, 100.0]),
+    )
+    .expect("Failed to load points");
+
+    // The offset is scaled by the f32 scale, which is not exactly 0.001
+    for (actual, expected) in offset.iter().zip([500.0, 4000.0, 0.1]) {
+        assert!((actual - expected).abs() < 1e-3, "{:?}", offset);
+    }
+    assert!((points[1].x - 0.001001).abs() < 1e-7, "{:?}", points[1]);
+    assert!((points[2].y - 0.001002).abs() < 1e-7, "{:?}", points[2]);
+}
+
+#[test]
+fn test_loader_triangulation_ply() {
+    let file_path = "tests/sample_files/quads.ply";
+
+    // The quad is split into two triangles, the face with the missing vertex is dropped
+    let mesh = TrimeshLoader::new()
+        .load_mesh_data(file_path)
+        .expect("Failed to load mesh");
+    assert_eq!(mesh.indices, vec![[0, 1, 2], [0, 2, 3], [0, 1, 4]]);
+    assert_eq!(mesh.vertices.len(), 5);
+
+    let mesh = TrimeshLoader::new()
+        .triangulation(Triangulation::FirstTriangle)
+        .load_mesh_data(file_path)
+        .expect("Failed to load mesh");
+    assert_eq!(mesh.indices, vec![[0, 1, 2], [0, 1, 3]]);
+
+    let error = TrimeshLoader::new()
+        .triangulation(Triangulation::Reject)
+        .load_mesh_data(file_path)
+        .expect_err("Quads must be rejected");
+    assert!(error.to_string().contains("only triangles"), "{}", error);
+}
+
+#[test]
+fn test_ply_schema() {
+    // Names of other exporters are accepted by default
+    let mesh = TrimeshLoader::new()
+        .load_mesh_data("tests/sample_files/aliases.ply")
+        .expect("Failed to load mesh");
+    assert_eq!(mesh.vertices.len(), 3);
+    assert_eq!(mesh.indices, vec![[0, 1, 2]]);
+    assert_eq!(mesh.vertices[1], Point::new(1.0, 0.0, 0.0));
+
+    let file_path = "tests/sample_files/custom_schema.ply";
+    let error = TrimeshLoader::new()
+        .load_mesh_data(file_path)
+        .expect_err("The element names are unknown");
+    assert!(error.to_string().contains("No vertex element"), "{}", error);
+
+    let schema = PlySchema::new()
+        .vertex_element("point")
+        .face_element("triangle");
+    let error = TrimeshLoader::new()
+        .ply_schema(schema.clone())
+        .load_mesh_data(file_path)
+        .expect_err("The property names are unknown");
+    assert!(
+        error.to_string().contains("Missing 'x' coordinate"),
+        "{}",
+        error
+    );
+
+    let schema = schema
+        .coordinates("east", "north", "up")
+        .vertex_indices("corners");
+    let mesh = TrimeshLoader::new()
+        .ply_schema(schema)
+        .load_mesh_data(file_path)
+        .expect("Failed to load mesh");
+    assert_eq!(mesh.indices, vec![[0, 1, 2]]);
+    assert_eq!(mesh.vertices[2], Point::new(0.0, 1.0, 0.0));
+}
+
+#[test]
+fn test_ply_layout() {
+    // Generated by scripts/generate_diverse_field_types.py: an element before the vertices,
+    // a list among the vertex coordinates, and both x and px in the same element
+    for file_path in [
+        "tests/sample_files/layout.ply",
+        "tests/sample_files/layout_big_endian.ply",
+    ] {
+        let mesh = TrimeshLoader::new()
+            .load_mesh_data(file_path)
+            .unwrap_or_else(|e| panic!("Failed to load {}: {}", file_path, e));
+        assert_eq!(
+            mesh.vertices,
+            vec![
+                Point::new(0.0, 0.0, 0.0),
+                Point::new(2.0, 0.0, 0.0),
+                Point::new(0.0, 3.0, 0.0),
+            ],
+            "{}",
+            file_path
+        );
+        assert_eq!(mesh.indices, vec![[0, 1, 2]], "{}", file_path);
+
+        // The names tried first win, so the other coordinates are read when asked for
+        let mesh = TrimeshLoader::new()
+            .ply_schema(PlySchema::new().coordinates("px", "py", "pz"))
+            .load_mesh_data(file_path)
+            .unwrap_or_else(|e| panic!("Failed to load {}: {}", file_path, e));
+        assert_eq!(
+            mesh.vertices[2],
+            Point::new(12.0, 22.0, 32.0),
+            "{}",
+            file_path
+        );
+    }
+}
+
+#[test]
+fn test_ply_tristrips() {
+    let mesh = TrimeshLoader::new()
+        .load_mesh_data("tests/sample_files/tristrips.ply")
+        .expect("Failed to load mesh");
+    // Every other triangle is flipped, the degenerate joints are skipped
+    assert_eq!(
+        mesh.indices,
+        vec![[0, 1, 2], [2, 1, 3], [2, 3, 4], [4, 3, 5], [0, 1, 2]]
+    );
+    assert_eq!(mesh.vertices.len(), 6);
+
+    // The strips count against the face limit, declared and decoded
+    let limits = |max_faces| Limits {
+        max_faces: Some(max_faces),
+        ..Limits::default()
+    };
+    let error = TrimeshLoader::new()
+        .limits(limits(0))
+        .load_mesh_data("tests/sample_files/tristrips.ply")
+        .expect_err("The declared strips must be rejected");
+    let exceeded = error.limit_exceeded().expect("Face limit expected");
+    assert_eq!((exceeded.limit, exceeded.actual), (Limit::Faces, 1));
+
+    let error = TrimeshLoader::new()
+        .limits(limits(4))
+        .load_mesh_data("tests/sample_files/tristrips.ply")
+        .expect_err("The decoded triangles must be rejected");
+    let exceeded = error.limit_exceeded().expect("Face limit expected");
+    assert_eq!(exceeded.limit, Limit::Faces);
+}
+
+#[test]
+fn test_load_trimesh_compatibility() {
+    // load_trimesh keeps only the first triangle of polygons, as it always did
+    let file_path = "tests/sample_files/polygons.ply";
+    let mesh = load_trimesh_with_flags(file_path, 1.0, TriMeshFlags::empty())
+        .expect("Failed to load mesh");
+    assert_eq!(mesh.indices().to_vec(), vec![[0, 1, 2], [0, 1, 4]]);
+    assert_eq!(
+        load_trimesh(file_path, 1.0).map(|mesh| mesh.indices().len()),
+        Ok(2)
+    );
+
+    // and fails on faces referencing missing vertices rather than dropping them
+    let Err(error) = load_trimesh("tests/sample_files/quads.ply", 1.0) else {
+        panic!("The missing vertex must be reported");
+    };
+    assert!(error.contains("references vertex 9"), "{}", error);
+
+    // but keeps degenerate faces
+    let mesh = load_trimesh_with_flags(
+        "tests/sample_files/degenerate.stl",
+        1.0,
+        TriMeshFlags::empty(),
+    )
+    .expect("Degenerate faces must be kept");
+    assert_eq!((mesh.vertices().len(), mesh.indices().len()), (3, 2));
+}
+
+#[test]
+fn test_loader_strict_validation() {
+    let error = TrimeshLoader::new()
+        .validation(Validation::Strict)
+        .load_mesh_data("tests/sample_files/quads.ply")
+        .expect_err("The missing vertex must be reported");
+    assert!(
+        error.to_string().contains("references vertex 9"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn test_loader_sub_meshes_obj() {
+    let file_path = "tests/sample_files/quads.obj";
+
+    let mesh = TrimeshLoader::new()
+        .load(file_path)
+        .expect("Failed to load mesh");
+    assert_eq!(mesh.indices().len(), 4);
+
+    let mesh = TrimeshLoader::new()
+        .sub_meshes(SubMeshes::Names(vec!["top".to_string()]))
+        .load_mesh_data(file_path)
+        .expect("Failed to load mesh");
+    assert_eq!(mesh.indices.len(), 2);
+    assert!(mesh.vertices.iter().all(|vertex| vertex.z == 1.0));
+
+    let mesh = TrimeshLoader::new()
+        .sub_meshes(SubMeshes::Indices(vec![0]))
+        .triangulation(Triangulation::FirstTriangle)
+        .load_mesh_data(file_path)
+        .expect("Failed to load mesh");
+    assert_eq!(mesh.indices.len(), 1);
+    assert!(mesh.vertices.iter().all(|vertex| vertex.z == 0.0));
+
+    assert!(
+        TrimeshLoader::new()
+            .triangulation(Triangulation::Reject)
+            .load(file_path)
+            .is_err()
+    );
+}
+
+#[test]
+fn test_loader_name_filter() {
+    let top = TrimeshLoader::new()
+        .name_filter(NameFilter::new().include("t?p"))
+        .load_mesh_data("tests/sample_files/quads.obj")
+        .expect("Failed to load mesh");
+    assert_eq!(top.indices.len(), 2);
+    assert!(top.vertices.iter().all(|vertex| vertex.z == 1.0));
+    let bottom = TrimeshLoader::new()
+        .name_filter(NameFilter::new().exclude("top"))
+        .load_mesh_data("tests/sample_files/quads.obj")
+        .expect("Failed to load mesh");
+    assert!(bottom.vertices.iter().all(|vertex| vertex.z == 0.0));
+
+    // Collada geometries are also known by the nodes instancing them, here through a skin
+    let file_path = "tests/sample_files/skinned.dae";
+    let face = TrimeshLoader::new()
+        .name_filter(NameFilter::new().include("*ace"))
+        .load_mesh_data(file_path)
+        .expect("Failed to load mesh");
+    assert_eq!(face, load_mesh_data(file_path).unwrap());
+    assert!(
+        TrimeshLoader::new()
+            .name_filter(NameFilter::new().include("*").exclude("Face"))
+            .load_mesh_data(file_path)
+            .is_err()
+    );
+
+    // With both, parts must be selected and match
+    let none = TrimeshLoader::new()
+        .sub_meshes(SubMeshes::Names(vec!["bottom".to_string()]))
+        .name_filter(NameFilter::new().include("top"))
+        .load_mesh_data("tests/sample_files/quads.obj")
+        .expect("Failed to load mesh");
+    assert!(none.indices.is_empty());
+}
+
+#[test]
+fn test_loader_reader() {
+    let file_path = "tests/sample_files/stl.stl";
+    let expected = load_trimesh(file_path, 2.0).expect("Failed to load TriMesh");
+
+    let file = std::fs::File::open(file_path).expect("Failed to open file");
+    let mesh = TrimeshLoader::new()
+        .format(Format::Stl)
+        .scale(2.0)
+        .load_reader(file)
+        .expect("Failed to load TriMesh from reader");
+    assert_eq!(mesh.vertices()[..], expected.vertices()[..]);
+    assert_eq!(mesh.indices()[..], expected.indices()[..]);
+
+    let file = std::fs::File::open(file_path).expect("Failed to open file");
+    assert!(TrimeshLoader::new().load_reader(file).is_err());
+}
+
+#[test]
+fn test_loader_transform_and_welding() {
+    // The two tetrahedra touch at no point, so welding with a small tolerance keeps them apart
+    let file_path = "tests/sample_files/two_bodies.stl";
+    let plain = load_mesh_data(file_path).expect("Failed to load mesh");
+    let mesh = TrimeshLoader::new()
+        .transform(Isometry::translation(0.0, 0.0, 10.0))
+        .welding_tolerance(0.01)
+        .load_mesh_data(file_path)
+        .expect("Failed to load mesh");
+    assert_eq!(mesh.vertices.len(), plain.vertices.len());
+    for (vertex, original) in mesh.vertices.iter().zip(&plain.vertices) {
+        assert!(floats_match(vertex.z, original.z + 10.0));
+    }
+
+    // A tolerance larger than the bodies collapses all faces
+    let mesh = TrimeshLoader::new()
+        .welding_tolerance(100.0)
+        .load_mesh_data(file_path)
+        .expect("Failed to load mesh");
+    assert!(mesh.indices.is_empty());
+}
+
+#[test]
+fn test_loader_reader_sniffed() {
+    // Without a format, PLY files are recognized by their header
+    let file = std::fs::File::open("tests/sample_files/quads.ply").expect("Failed to open file");
+    let mesh = TrimeshLoader::new()
+        .load_mesh_data_reader(file)
+        .expect("Failed to load mesh from reader");
+    assert_eq!(mesh.indices.len(), 3);
+}
+
+/// Replaces the content of any file by a single triangle, offset by the length of the file.
+struct SingleTriangle;
+
+impl MeshFormatLoader for SingleTriangle {
+    fn name(&self) -> &str {
+        "stl"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["stl"]
+    }
+
+    fn load(
+        &self,
+        reader: &mut dyn MeshReader,
+        context: &mut LoadContext,
+    ) -> Result<MeshData, LoadError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).map_err(|e| e.to_string())?;
+        let z = bytes.len() as f64;
+        let vertices = vec![
+            context.point(0.0, 0.0, z),
+            context.point(1.0, 0.0, z),
+            context.point(0.0, 1.0, z),
+        ];
+        Ok(MeshData::new(vertices, vec![[0, 1, 2]]))
+    }
+}
+
+#[test]
+fn test_custom_loader() {
+    let mut registry = FormatRegistry::default();
+    registry.register(Arc::new(SingleTriangle));
+    let loader = TrimeshLoader::new().registry(Arc::new(registry));
+
+    // The built-in STL loader is replaced
+    let file_path = "tests/sample_files/two_bodies.stl";
+    let length = std::fs::metadata(file_path).expect("Missing file").len() as Real;
+    let mesh = loader.load(file_path).expect("Failed to load TriMesh");
+    assert_eq!(mesh.indices().len(), 1);
+    assert_eq!(mesh.vertices()[0].z, length);
+
+    // Other formats are still there, and can be forced by name
+    let mesh = loader
+        .load("tests/sample_files/quads.obj")
+        .expect("Failed to load TriMesh");
+    assert_eq!(mesh.indices().len(), 4);
+    let mesh = loader
+        .clone()
+        .format("stl")
+        .load("tests/sample_files/quads.obj")
+        .expect("Failed to load TriMesh");
+    assert_eq!(mesh.indices().len(), 1);
+
+    let error = loader
+        .format("missing")
+        .load_mesh_data(file_path)
+        .expect_err("Unknown formats must be rejected");
+    assert!(error.to_string().contains("'missing'"), "{}", error);
+}
+
+/// Loads the file with the given limits and returns the limit it exceeded.
+fn exceeded_limit(file_path: &str, limits: Limits) -> Option<LimitExceeded> {
+    let error = TrimeshLoader::new()
+        .limits(limits)
+        .load_mesh_data(file_path)
+        .expect_err("The limit must be exceeded");
+    error.limit_exceeded().copied()
+}
+
+#[test]
+fn test_limits() {
+    let exceeded = exceeded_limit(
+        "tests/sample_files/quads.ply",
+        Limits {
+            max_vertices: Some(5),
+            ..Limits::default()
+        },
+    )
+    .expect("Vertex limit expected");
+    assert_eq!(
+        (exceeded.limit, exceeded.actual, exceeded.max),
+        (Limit::Vertices, 6, 5)
+    );
+
+    let exceeded = exceeded_limit(
+        "tests/sample_files/quads.obj",
+        Limits {
+            max_faces: Some(1),
+            ..Limits::default()
+        },
+    );
+    assert_eq!(exceeded.map(|e| e.limit), Some(Limit::Faces));
+
+    let exceeded = exceeded_limit(
+        "tests/sample_files/stl.stl",
+        Limits {
+            max_file_size: Some(100),
+            ..Limits::default()
+        },
+    );
+    assert_eq!(exceeded.map(|e| e.limit), Some(Limit::FileSize));
+
+    let exceeded = exceeded_limit(
+        "tests/sample_files/collada.dae",
+        Limits {
+            max_xml_depth: Some(3),
+            ..Limits::default()
+        },
+    );
+    assert_eq!(exceeded.map(|e| e.limit), Some(Limit::XmlDepth));
+
+    let exceeded = exceeded_limit(
+        "tests/sample_files/amf.amf",
+        Limits {
+            max_xml_elements: Some(10),
+            ..Limits::default()
+        },
+    )
+    .expect("XML node limit expected");
+    // Parsing stops at the first node over the limit
+    assert_eq!((exceeded.limit, exceeded.actual), (Limit::XmlElements, 11));
+
+    // The depth is checked before the document is parsed, which would overflow the stack
+    let depth = 200_000;
+    let text = format!("<amf>{}{}</amf>", "<a>".repeat(depth), "</a>".repeat(depth));
+    let error = TrimeshLoader::new()
+        .format(Format::Amf)
+        .limits(Limits {
+            max_xml_depth: Some(100),
+            ..Limits::default()
+        })
+        .load_mesh_data_reader(text.as_bytes())
+        .expect_err("The document is too deep");
+    assert_eq!(
+        error.limit_exceeded().map(|exceeded| exceeded.limit),
+        Some(Limit::XmlDepth)
+    );
+
+    // Without a maximum, the depth is still limited by default
+    let text = format!(
+        "<COLLADA><library_visual_scenes><visual_scene>{}{}</visual_scene></library_visual_scenes></COLLADA>",
+        "<node>".repeat(depth),
+        "</node>".repeat(depth)
+    );
+    let error = TrimeshLoader::new()
+        .format(Format::Dae)
+        .load_mesh_data_reader(text.as_bytes())
+        .expect_err("The document is too deep");
+    assert_eq!(
+        error
+            .limit_exceeded()
+            .map(|exceeded| (exceeded.limit, exceeded.max)),
+        Some((Limit::XmlDepth, DEFAULT_MAX_XML_DEPTH))
+    );
+
+    let exceeded = exceeded_limit(
+        "tests/sample_files/amf_zip.amf",
+        Limits {
+            max_decompressed_size: Some(100),
+            ..Limits::default()
+        },
+    )
+    .expect("Decompressed size limit expected");
+    assert_eq!(
+        (exceeded.limit, exceeded.actual),
+        (Limit::DecompressedSize, 101)
+    );
+
+    // Instancing multiplies the vertices and faces of the objects
+    let exceeded = exceeded_limit(
+        "tests/sample_files/constellation.amf",
+        Limits {
+            max_vertices: Some(5),
+            ..Limits::default()
+        },
+    )
+    .expect("Vertex limit expected");
+    assert_eq!(
+        (exceeded.limit, exceeded.actual, exceeded.max),
+        (Limit::Vertices, 6, 5)
+    );
+    let exceeded = exceeded_limit(
+        "tests/sample_files/constellation.amf",
+        Limits {
+            max_faces: Some(1),
+            ..Limits::default()
+        },
+    );
+    assert_eq!(exceeded.map(|e| e.limit), Some(Limit::Faces));
+
+    // Generous limits do not get in the way
+    let limits = Limits {
+        max_file_size: Some(1 << 20),
+        max_vertices: Some(1000),
+        max_faces: Some(1000),
+        max_xml_depth: Some(100),
+        max_xml_elements: Some(10000),
+        max_decompressed_size: Some(1 << 20),
+    };
+    for file_path in [
+        "tests/sample_files/amf_zip.amf",
+        "tests/sample_files/collada.dae",
+    ] {
+        assert!(
+            TrimeshLoader::new()
+                .limits(limits)
+                .load_mesh_data(file_path)
+                .is_ok()
+        );
+    }
+}
+
+#[test]
+fn test_limits_binary_stl_declared_count() {
+    // A header claiming four billion triangles, without any of them
+    let mut bytes = vec![0u8; 80];
+    bytes.extend(4_000_000_000u32.to_le_bytes());
+
+    let error = TrimeshLoader::new()
+        .format(Format::Stl)
+        .limits(Limits {
+            max_faces: Some(1000),
+            ..Limits::default()
+        })
+        .load_mesh_data_reader(&bytes[..])
+        .expect_err("The declared count must be rejected");
+    let exceeded = error.limit_exceeded().expect("Face limit expected");
+    assert_eq!(
+        (exceeded.limit, exceeded.actual),
+        (Limit::Faces, 4_000_000_000)
+    );
+
+    let error = TrimeshLoader::new()
+        .format(Format::Stl)
+        .limits(Limits {
+            max_file_size: Some(50),
+            ..Limits::default()
+        })
+        .load_mesh_data_reader(&bytes[..])
+        .expect_err("The size must be rejected");
+    let exceeded = error.limit_exceeded().expect("File size limit expected");
+    assert_eq!((exceeded.limit, exceeded.actual), (Limit::FileSize, 51));
+}
+
+#[test]
+fn test_progress() {
+    for (file_path, elements) in [
+        ("tests/sample_files/floats_ints.ply", 3 + 1),
+        ("tests/sample_files/stl.stl", 1),
+        ("tests/sample_files/quads.obj", 4),
+        ("tests/sample_files/materials.dae", 1),
+        ("tests/sample_files/constellation.amf", 1),
+    ] {
+        let reports: Arc<Mutex<Vec<Progress>>> = Arc::default();
+        let loader = {
+            let reports = reports.clone();
+            TrimeshLoader::new().progress(move |progress| reports.lock().unwrap().push(progress))
+        };
+        loader
+            .load_mesh_data(file_path)
+            .expect("Failed to load mesh");
+
+        let size = std::fs::metadata(file_path).unwrap().len();
+        let last = *reports
+            .lock()
+            .unwrap()
+            .last()
+            .expect("No progress reported");
+        assert_eq!(last.bytes_read, size, "{}", file_path);
+        assert_eq!(last.total_bytes, Some(size), "{}", file_path);
+        assert_eq!(last.elements, elements, "{}", file_path);
+        assert_eq!(last.total_elements, Some(elements), "{}", file_path);
+    }
+}
+
+#[test]
+fn test_cancellation() {
+    let token = CancellationToken::new();
+    let loader = TrimeshLoader::new().cancellation(token.clone());
+    assert!(loader.load_mesh_data("tests/sample_files/stl.stl").is_ok());
+
+    token.cancel();
+    for file_path in [
+        "tests/sample_files/floats_ints.ply",
+        "tests/sample_files/stl.stl",
+        "tests/sample_files/quads.obj",
+        "tests/sample_files/collada.dae",
+        "tests/sample_files/amf.amf",
+    ] {
+        let error = loader
+            .load_mesh_data(file_path)
+            .expect_err("The load must be cancelled");
+        assert_eq!(error, LoadError::Cancelled, "{}", file_path);
+    }
+
+    // Cancelling from the progress callback stops the load at the next check
+    let loader = {
+        let token = CancellationToken::new();
+        TrimeshLoader::new()
+            .cancellation(token.clone())
+            .progress(move |_| token.cancel())
+    };
+    let bytes = std::fs::read("tests/sample_files/floats_ints.ply").unwrap();
+    let error = loader
+        .format(Format::Ply)
+        .load_mesh_data_reader(&bytes[..])
+        .expect_err("The load must be cancelled");
+    assert_eq!(error, LoadError::Cancelled);
+    assert_eq!(error.to_string(), CANCELLED);
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn test_async_loading() {
+    use rs_read_trimesh::{LoadOptions, load_trimesh_async};
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("Failed to build the runtime");
+    runtime.block_on(async {
+        let mesh = load_trimesh_async("tests/sample_files/stl.stl", LoadOptions::default())
+            .await
+            .expect("Failed to load mesh");
+        assert_eq!(mesh.indices().len(), 1);
+
+        // The futures can be spawned on the runtime
+        let loader = TrimeshLoader::new();
+        let mesh = tokio::spawn(async move {
+            loader
+                .load_mesh_data_async("tests/sample_files/materials.obj")
+                .await
+        })
+        .await
+        .expect("The task failed")
+        .expect("Failed to load mesh");
+        assert!(!mesh.materials.is_empty());
+        assert_eq!(
+            load_mesh_data("tests/sample_files/materials.obj")
+                .unwrap()
+                .materials,
+            mesh.materials
+        );
+
+        let bytes = std::fs::read("tests/sample_files/floats_ints.ply").unwrap();
+        let mesh = TrimeshLoader::new()
+            .load_mesh_data_reader_async(&bytes[..])
+            .await
+            .expect("Failed to load mesh");
+        assert_eq!(mesh.vertices.len(), 3);
+
+        let error = TrimeshLoader::new()
+            .limits(Limits {
+                max_file_size: Some(100),
+                ..Limits::default()
+            })
+            .load_mesh_data_reader_async(&bytes[..])
+            .await
+            .expect_err("The size must be rejected");
+        assert_eq!(
+            error.limit_exceeded().map(|exceeded| exceeded.limit),
+            Some(Limit::FileSize)
+        );
+    });
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_parallel_loading() {
+    use rs_read_trimesh::{LoadOptions, load_trimeshes_parallel};
+
+    // A copy of the same content under another name is shared too
+    let copy =
+        std::env::temp_dir().join(format!("rs-read-trimesh-{}-copy.stl", std::process::id()));
+    std::fs::copy("tests/sample_files/stl.stl", &copy).expect("Failed to copy sample file");
+    let file_paths = [
+        "tests/sample_files/stl.stl",
+        "tests/sample_files/collada.dae",
+        "tests/sample_files/../sample_files/stl.stl",
+        copy.to_str().unwrap(),
+        "tests/sample_files/missing.stl",
+        "tests/sample_files/object.obj",
+    ];
+    let results = load_trimeshes_parallel(&file_paths, LoadOptions::default());
+    std::fs::remove_file(&copy).ok();
+
+    assert_eq!(results.len(), file_paths.len());
+    let stl = results[0].as_ref().expect("Failed to load mesh");
+    assert!(Arc::ptr_eq(stl, results[2].as_ref().unwrap()));
+    assert!(Arc::ptr_eq(stl, results[3].as_ref().unwrap()));
+    assert!(!Arc::ptr_eq(stl, results[1].as_ref().unwrap()));
+    assert!(
+        results[4]
+            .as_ref()
+            .is_err_and(|error| error.contains("missing.stl"))
+    );
+    assert_eq!(
+        results[5].as_ref().unwrap().indices(),
+        load_trimesh("tests/sample_files/object.obj", 1.0)
+            .unwrap()
+            .indices()
+    );
+}
+
+#[test]
+fn test_cache() {
+    let directory =
+        std::env::temp_dir().join(format!("rs-read-trimesh-cache-test-{}", std::process::id()));
+    // Left over if an earlier run failed
+    std::fs::remove_dir_all(&directory).ok();
+    let file_path = "tests/sample_files/materials.dae";
+    let reports = Arc::new(Mutex::new(0));
+    let loader = {
+        let reports = reports.clone();
+        TrimeshLoader::new()
+            .cache_directory(&directory)
+            .progress(move |_| *reports.lock().unwrap() += 1)
+    };
+    let cache_files = || {
+        std::fs::read_dir(&directory).map_or(Vec::new(), |entries| {
+            entries.map(|entry| entry.unwrap().path()).collect()
+        })
+    };
+
+    let loaded = loader
+        .load_mesh_data(file_path)
+        .expect("Failed to load mesh");
+    assert_eq!(cache_files().len(), 1);
+    assert!(*reports.lock().unwrap() > 0);
+
+    // A hit is not parsed, so nothing is reported
+    *reports.lock().unwrap() = 0;
+    assert_eq!(
+        loader
+            .load_mesh_data(file_path)
+            .expect("Failed to load mesh"),
+        loaded
+    );
+    assert_eq!(*reports.lock().unwrap(), 0);
+
+    // Other options are another entry
+    let scaled = loader
+        .clone()
+        .scale(2.0)
+        .load_mesh_data(file_path)
+        .expect("Failed to load mesh");
+    assert_eq!(scaled.vertices[0], loaded.vertices[0] * 2.0);
+    assert_eq!(cache_files().len(), 2);
+
+    // Damaged entries are loaded again and rewritten
+    for cache_file in cache_files() {
+        let mut bytes = std::fs::read(&cache_file).unwrap();
+        let middle = bytes.len() / 2;
+        bytes[middle] ^= 0xff;
+        std::fs::write(&cache_file, bytes).unwrap();
+    }
+    assert_eq!(
+        loader
+            .load_mesh_data(file_path)
+            .expect("Failed to load mesh"),
+        loaded
+    );
+    assert!(*reports.lock().unwrap() > 0);
+    *reports.lock().unwrap() = 0;
+    assert_eq!(
+        loader
+            .load_mesh_data(file_path)
+            .expect("Failed to load mesh"),
+        loaded
+    );
+    assert_eq!(*reports.lock().unwrap(), 0);
+
+    // Changing a file read alongside the main one invalidates the entry
+    let sources = directory.join("sources");
+    std::fs::create_dir_all(&sources).unwrap();
+    for name in ["materials.obj", "materials.mtl"] {
+        std::fs::copy(
+            Path::new("tests/sample_files").join(name),
+            sources.join(name),
+        )
+        .unwrap();
+    }
+    let obj_path = sources.join("materials.obj");
+    let obj_path = obj_path.to_str().unwrap();
+    let original = loader
+        .load_mesh_data(obj_path)
+        .expect("Failed to load mesh");
+    let library = std::fs::read_to_string(sources.join("materials.mtl")).unwrap();
+    std::fs::write(
+        sources.join("materials.mtl"),
+        library.replace("Kd 0.8 0.4 0.1", "Kd 0.1 0.4 0.8 "),
+    )
+    .unwrap();
+    *reports.lock().unwrap() = 0;
+    let changed = loader
+        .load_mesh_data(obj_path)
+        .expect("Failed to load mesh");
+    assert!(*reports.lock().unwrap() > 0);
+    assert_ne!(changed.materials, original.materials);
+
+    // A loader without a cache version, here replacing a built-in one, is not cached
+    let stl_path = "tests/sample_files/stl.stl";
+    loader
+        .load_mesh_data(stl_path)
+        .expect("Failed to load mesh");
+    let entries = cache_files().len();
+    let mut registry = FormatRegistry::default();
+    registry.register(Arc::new(SingleTriangle));
+    let custom = loader
+        .clone()
+        .registry(Arc::new(registry))
+        .load_mesh_data(stl_path)
+        .expect("Failed to load mesh");
+    assert_eq!(custom.indices.len(), 1);
+    assert_eq!(cache_files().len(), entries);
+
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn test_uri_resolver() {
+    let expected = load_mesh_data("tests/sample_files/stl.stl").unwrap();
+
+    // A root either contains the packages or is the package itself
+    let paths = std::env::join_paths(["tests/missing", "tests"]).unwrap();
+    for resolver in [
+        UriResolver::new().roots("package", paths),
+        UriResolver::new().root("package", "tests/sample_files"),
+        UriResolver::new().package("sample_files", "tests/sample_files"),
+    ] {
+        let loader = TrimeshLoader::new().resolver(resolver.clone());
+        let mesh = loader
+            .load_mesh_data("package://sample_files/stl.stl")
+            .expect("Failed to resolve");
+        assert_eq!(mesh.vertices, expected.vertices);
+        assert_eq!(
+            resolver.resolve("package://sample_files/stl.stl", Path::new("")),
+            Ok(Path::new("tests/sample_files/stl.stl").to_path_buf())
+        );
+    }
+
+    // Relative file URIs start from the directory, absolute ones do not
+    let resolver = UriResolver::new();
+    let mesh = TrimeshLoader::new()
+        .load_mesh_data("file://tests/sample_files/stl.stl")
+        .unwrap();
+    assert_eq!(mesh.vertices, expected.vertices);
+    assert_eq!(
+        resolver.resolve("file://meshes/base.stl", Path::new("robot")),
+        Ok(Path::new("robot/meshes/base.stl").to_path_buf())
+    );
+    assert_eq!(
+        resolver.resolve("file:///meshes/base.stl", Path::new("robot")),
+        Ok(Path::new("/meshes/base.stl").to_path_buf())
+    );
+
+    let error = resolver
+        .resolve("model://sample_files/stl.stl", Path::new(""))
+        .expect_err("No roots for the scheme");
+    assert!(error.contains("model://"), "{}", error);
+    let error = UriResolver::new()
+        .root("package", "tests")
+        .resolve("package://sample_files/missing.stl", Path::new(""))
+        .expect_err("The file does not exist");
+    assert!(error.contains("missing.stl"), "{}", error);
+}
+
+#[cfg(feature = "urdf")]
+#[test]
+fn test_urdf() {
+    use rs_read_trimesh::UrdfLoader;
+
+    let loader = UrdfLoader::new().package("test_meshes", "tests/sample_files");
+    let links = loader
+        .load_mesh_data("tests/sample_files/robot.urdf")
+        .expect("Failed to load robot");
+    assert_eq!(links.keys().collect::<Vec<_>>(), ["arm", "base_link"]);
+
+    // The first collision is scaled, then rotated a quarter turn and lifted
+    let mut stl = load_mesh_data("tests/sample_files/stl.stl").unwrap();
+    let obj = load_mesh_data("tests/sample_files/object.obj").unwrap();
+    let base_link = &links["base_link"];
+    assert_eq!(
+        base_link.vertices.len(),
+        stl.vertices.len() + obj.vertices.len()
+    );
+    assert_eq!(
+        base_link.indices.len(),
+        stl.indices.len() + obj.indices.len()
+    );
+    stl.scale(2.0);
+    for (vertex, original) in base_link.vertices.iter().zip(&stl.vertices) {
+        assert!(floats_match(vertex.x, -original.y));
+        assert!(floats_match(vertex.y, original.x));
+        assert!(floats_match(vertex.z, original.z + 1.0));
+    }
+
+    // The mirroring scale flips the faces
+    let dae = load_mesh_data("tests/sample_files/robot.dae").unwrap();
+    let arm = &links["arm"];
+    assert_eq!(arm.vertices.len(), dae.vertices.len());
+    assert!(floats_match(arm.vertices[0].z, -dae.vertices[0].z * 0.001));
+    assert!(floats_match(
+        arm.vertices[0].x,
+        dae.vertices[0].x * 0.001 + 1.0
+    ));
+    assert_eq!(
+        arm.indices[0],
+        [dae.indices[0][0], dae.indices[0][2], dae.indices[0][1]]
+    );
+
+    assert_eq!(
+        loader
+            .load("tests/sample_files/robot.urdf")
+            .map(|links| links.len()),
+        Ok(2)
+    );
+    let error = UrdfLoader::new()
+        .load_mesh_data("tests/sample_files/robot.urdf")
+        .expect_err("The package is not known");
+    assert!(error.to_string().contains("test_meshes"), "{}", error);
+    // The limits of the mesh loader are reported as such, not as a message of the link
+    let error = loader
+        .clone()
+        .mesh_loader(TrimeshLoader::new().limits(Limits {
+            max_faces: Some(1),
+            ..Limits::default()
+        }))
+        .load_mesh_data("tests/sample_files/robot.urdf")
+        .expect_err("The meshes have more than one face");
+    assert_eq!(
+        error.limit_exceeded().map(|exceeded| exceeded.limit),
+        Some(Limit::Faces)
+    );
+}
+
+#[cfg(feature = "sdf")]
+#[test]
+fn test_sdf() {
+    use rs_read_trimesh::SdfLoader;
+
+    let loader = SdfLoader::new().package("test_meshes", "tests/sample_files");
+    let links = loader
+        .load_mesh_data("tests/sample_files/robot.sdf")
+        .expect("Failed to load model");
+    assert_eq!(
+        links.keys().collect::<Vec<_>>(),
+        ["robot::base", "robot::gripper::finger"]
+    );
+
+    // The collision is scaled, then rotated a quarter turn and lifted
+    let mut stl = load_mesh_data("tests/sample_files/stl.stl").unwrap();
+    stl.scale(2.0);
+    let base = &links["robot::base"];
+    assert_eq!(base.indices, stl.indices);
+    for (vertex, original) in base.vertices.iter().zip(&stl.vertices) {
+        assert!(floats_match(vertex.x, -original.y));
+        assert!(floats_match(vertex.y, original.x));
+        assert!(floats_match(vertex.z, original.z + 1.0));
+    }
+
+    // The pose of the nested model's link is in degrees
+    let obj = load_mesh_data("tests/sample_files/object.obj").unwrap();
+    let finger = &links["robot::gripper::finger"];
+    for (vertex, original) in finger.vertices.iter().zip(&obj.vertices) {
+        assert!(floats_match(vertex.x, -original.y + 1.0));
+        assert!(floats_match(vertex.y, original.x));
+    }
+
+    let error = SdfLoader::new()
+        .load_mesh_data("tests/sample_files/robot.sdf")
+        .expect_err("The model directory is not known");
+    assert!(error.to_string().contains("test_meshes"), "{}", error);
+    let error = loader
+        .load_mesh_data("tests/sample_files/robot.urdf")
+        .expect_err("Not an SDF file");
+    assert!(error.to_string().contains("<robot>"), "{}", error);
+}
+
+#[cfg(feature = "mjcf")]
+#[test]
+fn test_mjcf() {
+    use rs_read_trimesh::MjcfLoader;
+
+    let loader = MjcfLoader::new();
+    let bodies = loader
+        .load_mesh_data("tests/sample_files/robot_mjcf.xml")
+        .expect("Failed to load model");
+    assert_eq!(
+        bodies.keys().collect::<Vec<_>>(),
+        ["base", "finger", "plate", "world"]
+    );
+
+    // The mesh asset is named after its file, and geoms are in the frame of their body
+    let obj = load_mesh_data("tests/sample_files/object.obj").unwrap();
+    assert_eq!(bodies["world"].vertices, obj.vertices);
+
+    // The euler angles are in degrees by default
+    let mut stl = load_mesh_data("tests/sample_files/stl.stl").unwrap();
+    stl.scale(2.0);
+    for (vertex, original) in bodies["base"].vertices.iter().zip(&stl.vertices) {
+        assert!(floats_match(vertex.x, -original.y));
+        assert!(floats_match(vertex.y, original.x));
+        assert!(floats_match(vertex.z, original.z + 1.0));
+    }
+
+    // The quaternion has its scalar part first, this one is half a turn about x
+    for (vertex, original) in bodies["finger"].vertices.iter().zip(&stl.vertices) {
+        assert!(floats_match(vertex.x, original.x));
+        assert!(floats_match(vertex.y, -original.y));
+        assert!(floats_match(vertex.z, -original.z));
+    }
+
+    assert_eq!(
+        loader
+            .load("tests/sample_files/robot_mjcf.xml")
+            .map(|bodies| bodies.len()),
+        Ok(4)
+    );
+
+    // Inline assets are loaded in the frame of their body, unused ones are never loaded
+    assert_eq!(
+        bodies["plate"].vertices,
+        [
+            Point::new(0.0, 0.0, 0.0),
+            Point::new(1.0, 0.0, 0.0),
+            Point::new(0.0, 1.0, 0.0)
+        ]
+    );
+    assert_eq!(bodies["plate"].indices, [[0, 1, 2]]);
+}
+
+/// Feeds truncated and randomly corrupted copies of the sample files to the loaders,
+/// which must report errors rather than panic. The fuzz targets do this more thoroughly.
+#[test]
+fn test_corrupted_inputs_do_not_panic() {
+    let iterations: usize = std::env::var("CORRUPTION_ITERATIONS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(200);
+    let samples = [
+        ("tests/sample_files/floats_ints.ply", Format::Ply),
+        ("tests/sample_files/attributes.ply", Format::Ply),
+        ("tests/sample_files/stl.stl", Format::Stl),
+        ("tests/sample_files/two_bodies.stl", Format::Stl),
+        ("tests/sample_files/materials.obj", Format::Obj),
+        ("tests/sample_files/collada.dae", Format::Dae),
+        ("tests/sample_files/materials.dae", Format::Dae),
+        ("tests/sample_files/constellation.amf", Format::Amf),
+        ("tests/sample_files/amf_zip.amf", Format::Amf),
+    ];
+
+    // xorshift, so that failures can be reproduced
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    let mut random = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state as usize
+    };
+
+    for (file_path, format) in samples {
+        let original = std::fs::read(file_path).expect("Failed to read sample file");
+        let loader = TrimeshLoader::new()
+            .format(format)
+            .triangulation(Triangulation::Fan)
+            .welding_tolerance(0.01);
+        for iteration in 0..iterations {
+            let mut bytes = original.clone();
+            if iteration % 4 == 0 {
+                bytes.truncate(random() % original.len());
+            } else {
+                for _ in 0..1 + random() % 8 {
+                    let position = random() % bytes.len();
+                    bytes[position] = match random() % 3 {
+                        0 => random() as u8,
+                        1 => b"0123456789-. e"[random() % 14],
+                        _ => 0xff,
+                    };
+                }
+            }
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                loader.load_mesh_data_reader(&bytes[..])
+            }));
+            assert!(
+                result.is_ok(),
+                "{} panicked on iteration {}",
+                file_path,
+                iteration
+            );
+        }
+    }
+}
 
+/// Helper function for running each point cloud test
+fn run_points_test(file_path: &str) {
+    let expected_points = [
+        Point::new(-0.7, 2.1, 0.0),
+        Point::new(1.4, 4.2, 0.0),
+        Point::new(-3.5, 4.9, 0.0),
+    ];
+
+    let points = load_points(file_path, 1.0)
+        .unwrap_or_else(|e| panic!("Failed to load points from {}: {}", file_path, e));
+    assert_eq!(points.len(), expected_points.len(), "Point count mismatch");
+    for (point, expected) in points.iter().zip(expected_points.iter()) {
+        assert!(
+            floats_match(point.x, expected.x)
+                && floats_match(point.y, expected.y)
+                && floats_match(point.z, expected.z),
+            "Point mismatch: expected {:?}, got {:?}",
+            expected,
+            point
+        );
+    }
 }
 
 /// Helper function for running each test
@@ -211,19 +1867,19 @@ fn run_trimesh_test(file_path: &str) {
 }
 
 /// Helper to round floats to two decimal places
-fn round_to_two_decimals(value: f32) -> f32 {
+fn round_to_two_decimals(value: Real) -> Real {
     (value * 100.0).round() / 100.0
 }
 
 /// Compare two floating-point numbers up to two decimal places
-fn floats_match(a: f32, b: f32) -> bool {
+fn floats_match(a: Real, b: Real) -> bool {
     round_to_two_decimals(a) == round_to_two_decimals(b)
 }
 
 /// Verify the content of a TriMesh
 pub fn verify_trimesh_content(mesh: &TriMesh) -> bool {
     // Define the expected vertices and face
-    let expected_vertices = vec![
+    let expected_vertices = [
         Point::new(-0.7, 2.1, 0.0),
         Point::new(1.4, 4.2, 0.0),
         Point::new(-3.5, 4.9, 0.0),